@@ -0,0 +1,77 @@
+//! Benchmark of the hashing of image names and of their insertion and lookup in a
+//! `ClassificationDataset`.
+//!
+//! Run with `cargo bench --bench insertion`. The default SipHash based `RandomState` is
+//! compared against the `FxBuildHasher` selected by the `fxhash` feature. Runs of both
+//! hashers are interleaved and the best time of each is reported, so that the state of the
+//! allocator does not favour either of them.
+
+use std::collections::hash_map::RandomState;
+use std::hash::BuildHasher;
+use std::time::{Duration, Instant};
+
+use bagheera::classification::ClassificationDataset;
+use bagheera::utils::FxBuildHasher;
+
+const NUM_IMAGES: usize = 1_000_000;
+const NUM_CLASSES: u16 = 10;
+const NUM_RUNS: usize = 7;
+
+/// Returns the time taken to hash all `names`.
+fn hash_all<S: BuildHasher + Default>(names: &[String]) -> Duration {
+    let hasher = S::default();
+    let start = Instant::now();
+    let mut combined = 0u64;
+    for name in names {
+        combined ^= hasher.hash_one(name);
+    }
+    let elapsed = start.elapsed();
+    assert_ne!(combined, 0u64);
+    elapsed
+}
+
+/// Returns the time taken to insert all `names` and to look all of them up.
+fn insert_and_lookup<S: BuildHasher + Default>(names: &[String]) -> (Duration, Duration) {
+    let start = Instant::now();
+    let mut cls_db = ClassificationDataset::with_hasher(NUM_CLASSES, false, S::default());
+    for (i, name) in names.iter().enumerate() {
+        cls_db
+            .add(name, &vec![(i % NUM_CLASSES as usize) as u16])
+            .unwrap();
+    }
+    let insertion = start.elapsed();
+
+    let start = Instant::now();
+    assert!(names.iter().all(|name| cls_db.image_is_present(name)));
+    (insertion, start.elapsed())
+}
+
+fn report(operation: &str, sip: Duration, fx: Duration) {
+    println!("{} {} images (best of {} runs):", operation, NUM_IMAGES, NUM_RUNS);
+    println!("    RandomState:   {:?}", sip);
+    println!("    FxBuildHasher: {:?}", fx);
+    println!(
+        "    speedup:       {:.2}x",
+        sip.as_secs_f64() / fx.as_secs_f64()
+    );
+}
+
+fn main() {
+    let names = (0..NUM_IMAGES)
+        .map(|i| format!("n{:08}_{}.JPEG", i % 1000, i))
+        .collect::<Vec<String>>();
+
+    let mut sip = (Duration::MAX, Duration::MAX, Duration::MAX);
+    let mut fx = (Duration::MAX, Duration::MAX, Duration::MAX);
+    for _ in 0..NUM_RUNS {
+        sip.2 = sip.2.min(hash_all::<RandomState>(&names));
+        fx.2 = fx.2.min(hash_all::<FxBuildHasher>(&names));
+        let (insertion, lookup) = insert_and_lookup::<RandomState>(&names);
+        sip = (sip.0.min(insertion), sip.1.min(lookup), sip.2);
+        let (insertion, lookup) = insert_and_lookup::<FxBuildHasher>(&names);
+        fx = (fx.0.min(insertion), fx.1.min(lookup), fx.2);
+    }
+    report("Hashing the names of", sip.2, fx.2);
+    report("Inserting", sip.0, fx.0);
+    report("Looking up", sip.1, fx.1);
+}
@@ -4,29 +4,75 @@
 //! , reading image classification dataset groundtruth and evaluating
 //! single-class and multi-class classification techniques.
 
-use std::collections::HashMap;
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+#[cfg(feature = "bincode")]
+use std::convert::TryFrom;
+use std::fmt;
+use std::hash::BuildHasher;
 use std::io;
-use std::io::{BufRead, BufReader, Read, Seek, SeekFrom};
+use std::io::{BufRead, BufReader, Read, Seek, SeekFrom, Write};
+use std::ops::Index;
 use std::option::Option;
 
 use log;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
 
 use crate::errors;
+pub use crate::fixed::FixedClassOutput;
 use crate::utils;
-use crate::utils::{ToOneHot, TopK};
-use num_traits::ToPrimitive;
+#[cfg(feature = "bincode")]
+use crate::utils::LeBytes;
+use crate::utils::{FromOneHot, ToOneHot, TopK};
+
+/// Maximum number of images for which the [`fmt::Debug`] output of [`ClassificationOutput`]
+/// and [`ClassificationDataset`] lists every entry. Larger instances only show counts.
+const DEBUG_MAX_IMAGES: usize = 10usize;
+
+/// Hasher builder used by [`ClassificationOutput`] and [`ClassificationDataset`] unless
+/// another one is given.
+///
+/// This is the randomly seeded [`RandomState`](std::collections::hash_map::RandomState) of
+/// the standard library. With the `fxhash` feature, it is the faster
+/// [`utils::FxBuildHasher`] instead, which speeds up loading large numbers of short image
+/// names.
+#[cfg(not(feature = "fxhash"))]
+pub type DefaultHashBuilder = std::collections::hash_map::RandomState;
+
+/// Hasher builder used by [`ClassificationOutput`] and [`ClassificationDataset`] unless
+/// another one is given.
+///
+/// This is [`utils::FxBuildHasher`] as the `fxhash` feature is enabled. Without the
+/// feature, it is the randomly seeded `RandomState` of the standard library.
+#[cfg(feature = "fxhash")]
+pub type DefaultHashBuilder = utils::FxBuildHasher;
+
 /// Generic struct to store the image classification output for a number of images.
+///
+/// The confidences are usually floating point numbers, but any numeric type can be used.
+/// For instance, quantized classifiers emitting `u8` scores can be represented as
+/// `ClassificationOutput<T1, u8>` and evaluated with the top-k based metrics. Creating an
+/// instance from a CSV file requires floating point confidences.
+///
+/// The images are stored in a [`HashMap`] using the hasher built by `S`, which defaults to
+/// [`DefaultHashBuilder`]. Instances using another hasher are created with
+/// [`Self::with_hasher()`].
+#[derive(Clone)]
 pub struct ClassificationOutput<
     T1: num_traits::PrimInt + num_traits::Unsigned + num_traits::FromPrimitive,
-    T2: num_traits::Float + fast_float::FastFloat + num_traits::FromPrimitive,
+    T2: num_traits::Num + Copy + PartialOrd,
+    S: BuildHasher = DefaultHashBuilder,
 > {
     num_classes: T1,
-    data: HashMap<String, Vec<T2>>,
+    data: HashMap<String, Vec<T2>, S>,
+    infer_num_classes: bool,
 }
 
 impl<
         T1: num_traits::PrimInt + num_traits::Unsigned + num_traits::FromPrimitive,
-        T2: num_traits::Float + fast_float::FastFloat + num_traits::FromPrimitive,
+        T2: num_traits::Num + Copy + PartialOrd,
     > ClassificationOutput<T1, T2>
 {
     /// Creates a new empty instance of [`Self`]
@@ -53,14 +99,120 @@ impl<
     pub fn new(num_classes: T1) -> Self {
         ClassificationOutput {
             num_classes,
-            data: HashMap::<String, Vec<T2>>::new(),
+            data: HashMap::default(),
+            infer_num_classes: false,
+        }
+    }
+
+    /// Creates a new empty instance of [`Self`] whose number of classes is set by the first
+    /// call to [`Self::add()`].
+    ///
+    /// Until then, [`Self::try_num_classes()`] returns `None` and [`Self::num_classes()`]
+    /// returns zero. Once the first confidence vector is added, its length becomes the number
+    /// of classes and later vectors are checked against it as usual.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use bagheera::classification::ClassificationOutput;
+    ///
+    /// let mut cls_out = ClassificationOutput::<u8, f32>::new_inferred();
+    /// assert_eq!(cls_out.try_num_classes(), None);
+    /// cls_out.add("hello.jpg", vec![0.2f32, 0.7f32, 0.1f32]).unwrap();
+    /// assert_eq!(cls_out.try_num_classes(), Some(3u8));
+    /// assert!(cls_out.add("world.jpg", vec![0.5f32, 0.5f32]).is_err());
+    /// ```
+    pub fn new_inferred() -> Self {
+        ClassificationOutput {
+            num_classes: T1::zero(),
+            data: HashMap::default(),
+            infer_num_classes: true,
+        }
+    }
+
+    /// Creates a new instance of [`Self`] from a matrix holding one row of confidences per image.
+    ///
+    /// Row `i` of `matrix` is stored as the confidence vector of `names[i]`. An [io::Error]
+    /// instance is returned if `matrix` does not have `num_classes` columns, if it does not
+    /// have one row per name or if a name is repeated.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use bagheera::classification::ClassificationOutput;
+    /// use ndarray::array;
+    ///
+    /// let matrix = array![[0.1f32, 0.9f32], [0.6f32, 0.4f32]];
+    /// let cls_out =
+    ///     ClassificationOutput::from_ndarray(matrix.view(), &["india.jpg", "iran.jpg"], 2u8)
+    ///         .unwrap();
+    /// assert_eq!(cls_out.confidence_for_image("iran.jpg").unwrap(), &vec![0.6f32, 0.4f32]);
+    /// ```
+    #[cfg(feature = "ndarray")]
+    pub fn from_ndarray(
+        matrix: ndarray::ArrayView2<T2>,
+        names: &[&str],
+        num_classes: T1,
+    ) -> Result<Self, io::Error> {
+        if matrix.ncols() != num_classes.to_usize().unwrap() {
+            return Err(errors::matrix_columns_mismatch(
+                matrix.ncols(),
+                num_classes.to_usize().unwrap(),
+            ));
+        }
+        if matrix.nrows() != names.len() {
+            return Err(errors::matrix_rows_mismatch(matrix.nrows(), names.len()));
+        }
+        let mut cls_out = ClassificationOutput {
+            num_classes,
+            data: HashMap::with_capacity_and_hasher(names.len(), DefaultHashBuilder::default()),
+            infer_num_classes: false,
+        };
+        for (imagename, row) in names.iter().zip(matrix.rows()) {
+            if cls_out.image_is_present(imagename) {
+                return Err(errors::image_already_present(imagename));
+            }
+            cls_out.add(imagename, row.to_vec())?;
+        }
+        Ok(cls_out)
+    }
+}
+
+impl<
+        T1: num_traits::PrimInt + num_traits::Unsigned + num_traits::FromPrimitive,
+        T2: num_traits::Num + Copy + PartialOrd,
+        S: BuildHasher + Default,
+    > ClassificationOutput<T1, T2, S>
+{
+    /// Creates a new empty instance of [`Self`] whose images are stored using a hasher
+    /// built by `hasher`.
+    ///
+    /// With a hasher which does not depend on a random seed, two instances holding the same
+    /// images list them in the same order.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::collections::hash_map::RandomState;
+    /// use bagheera::classification::ClassificationOutput;
+    ///
+    /// let cls_out = ClassificationOutput::<u8, f32, _>::with_hasher(20u8, RandomState::new());
+    /// assert_eq!(cls_out.num_classes(), 20u8);
+    /// assert!(cls_out.is_empty());
+    /// ```
+    pub fn with_hasher(num_classes: T1, hasher: S) -> Self {
+        ClassificationOutput {
+            num_classes,
+            data: HashMap::with_hasher(hasher),
+            infer_num_classes: false,
         }
     }
 
     /// Adds a new entry to a [`Self`] instance.
     ///
     /// This returns an [`io::Error`] instance if the new entry has different number of classes
-    /// than that of the [`Self`] instance.
+    /// than that of the [`Self`] instance. For an instance created with
+    /// [`Self::new_inferred()`], the first entry sets the number of classes instead.
     ///
     /// # Examples
     ///
@@ -75,76 +227,291 @@ impl<
     /// assert_eq!(cls_out.num_images(), 3usize);
     /// ```
     pub fn add(&mut self, image_name: &str, confidence_vector: Vec<T2>) -> Result<(), io::Error> {
+        if self.infer_num_classes {
+            self.num_classes = T1::from_usize(confidence_vector.len())
+                .ok_or_else(|| errors::num_classes_overflow(confidence_vector.len()))?;
+            self.infer_num_classes = false;
+        }
         if confidence_vector.len() == T1::to_usize(&self.num_classes).unwrap() {
-            &self.data.insert(image_name.to_string(), confidence_vector);
+            self.data.insert(image_name.to_string(), confidence_vector);
             log::debug!("Added record to ClassificationOutput.");
             Ok(())
         } else {
-            Err(errors::image_not_present_error(image_name))
+            Err(errors::confidence_length_mismatch(
+                image_name,
+                confidence_vector.len(),
+                self.num_classes_usize(),
+            ))
         }
     }
 
-    /// Creates a new instance of [`Self`] from a CSV file.
-    pub fn from_csv_file(csv_filename: &str, num_classes: T1) -> Result<Self, io::Error> {
-        let fid = utils::open_file(csv_filename).unwrap();
-        let mut bufread = BufReader::new(fid);
-        let mut numlines = 0usize;
-        for _ in bufread.by_ref().lines() {
-            numlines += 1;
-        }
-        log::debug!(
-            "There are a total of {} lines in {}.",
-            numlines,
-            csv_filename
-        );
-        let mut data_hmap = HashMap::<String, Vec<T2>>::with_capacity(numlines);
-        bufread.seek(SeekFrom::Start(0u64)).unwrap();
-        log::debug!("Reading and parsing lines from the file.");
-        for (line_num, line) in bufread.lines().enumerate() {
-            let line = line.unwrap();
-            let line_trimmed = line.trim();
-            if line_trimmed.is_empty() {
-                return Err(io::Error::new(
-                    io::ErrorKind::InvalidData,
-                    format!(
-                        "Line {} of {} is empty. This is an error.",
-                        line_num, csv_filename
-                    ),
+    /// Checks every image of the [`Self`] instance before it is evaluated.
+    ///
+    /// An [io::Error] instance naming the first offending image in name order is returned if
+    /// a confidence vector does not have one entry per class, or if it holds a NaN or
+    /// infinite confidence.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use bagheera::classification::ClassificationOutput;
+    ///
+    /// let mut cls_out = ClassificationOutput::<u8, f32>::new(2u8);
+    /// cls_out.add("hello.jpg", vec![0.1f32, 0.9f32]).unwrap();
+    /// assert!(cls_out.validate().is_ok());
+    /// cls_out.add("world.jpg", vec![f32::NAN, 0.9f32]).unwrap();
+    /// assert!(cls_out.validate().is_err());
+    /// ```
+    pub fn validate(&self) -> Result<(), io::Error> {
+        let mut imagenames: Vec<&String> = self.data.keys().collect();
+        imagenames.sort_unstable();
+        for imagename in imagenames {
+            let confidences = &self.data[imagename];
+            if confidences.len() != self.num_classes_usize() {
+                return Err(errors::confidence_length_mismatch(
+                    imagename,
+                    confidences.len(),
+                    self.num_classes_usize(),
                 ));
             }
-            let mut imagename = String::new();
+            // `x - x` is zero for every finite value, but NaN for NaN and infinities.
+            #[allow(clippy::eq_op)]
+            if confidences
+                .iter()
+                .any(|confidence| *confidence - *confidence != T2::zero())
+            {
+                return Err(errors::non_finite_confidence(imagename));
+            }
+        }
+        Ok(())
+    }
 
-            for (token_num, token) in line_trimmed.split(",").enumerate() {
-                if token_num == 0usize {
-                    imagename = token.to_string();
-                    data_hmap.insert(token.to_string(), Vec::<T2>::new());
-                    continue;
-                }
+    /// Returns the confidence vector of `imagename` with every confidence clipped to
+    /// `[lo, hi]`.
+    ///
+    /// This is typically used with `[eps, 1 - eps]` before taking logarithms. NaN
+    /// confidences are left unchanged. An [io::Error] instance is returned if `lo > hi`, or
+    /// if `imagename` is not in the [`Self`] instance.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use bagheera::classification::ClassificationOutput;
+    ///
+    /// let mut cls_out = ClassificationOutput::<u8, f32>::new(3u8);
+    /// cls_out.add("hello.jpg", vec![0f32, 0.5f32, 1f32]).unwrap();
+    /// let clipped = cls_out.clip_for_image("hello.jpg", 0.25f32, 0.75f32).unwrap();
+    /// assert_eq!(clipped, vec![0.25f32, 0.5f32, 0.75f32]);
+    /// assert!(cls_out.clip_for_image("hello.jpg", 0.75f32, 0.25f32).is_err());
+    /// ```
+    pub fn clip_for_image(&self, imagename: &str, lo: T2, hi: T2) -> Result<Vec<T2>, io::Error> {
+        if lo > hi {
+            return Err(errors::invalid_clip_range());
+        }
+        Ok(self
+            .confidence_for_image(imagename)?
+            .iter()
+            .map(|confidence| clip(*confidence, lo, hi))
+            .collect())
+    }
 
-                data_hmap
-                    .get_mut(&imagename)
-                    .unwrap()
-                    .push(fast_float::parse::<T2, _>(token).unwrap());
+    /// Clips every confidence of the [`Self`] instance to `[lo, hi]` in place.
+    ///
+    /// NaN confidences are left unchanged. An [io::Error] instance is returned, and the
+    /// [`Self`] instance is left unchanged, if `lo > hi`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use bagheera::classification::ClassificationOutput;
+    ///
+    /// let mut cls_out = ClassificationOutput::<u8, f64>::new(2u8);
+    /// cls_out.add("hello.jpg", vec![0f64, 1f64]).unwrap();
+    /// cls_out.clip_all(1e-15f64, 1f64 - 1e-15f64).unwrap();
+    /// assert_eq!(cls_out["hello.jpg"], [1e-15f64, 1f64 - 1e-15f64]);
+    /// ```
+    pub fn clip_all(&mut self, lo: T2, hi: T2) -> Result<(), io::Error> {
+        if lo > hi {
+            return Err(errors::invalid_clip_range());
+        }
+        for confidences in self.data.values_mut() {
+            for confidence in confidences.iter_mut() {
+                *confidence = clip(*confidence, lo, hi);
             }
-            if T1::from_usize(data_hmap[&imagename].len()).unwrap() != num_classes {
-                return Err(io::Error::new(
-                    io::ErrorKind::InvalidData,
-                    format!(
-                        "Line {} of {} contains {} classes when num_classes is specified as {}",
-                        line_num,
-                        csv_filename,
-                        data_hmap[&imagename].len(),
-                        num_classes.to_usize().unwrap()
-                    ),
+        }
+        Ok(())
+    }
+
+    /// Returns the histogram of the confidences of every image for `class`.
+    ///
+    /// The confidences are assumed to be normalized scores, and are binned into `n_bins`
+    /// equal-width bins over `[0, 1]`. A confidence of exactly 1 falls in the last bin, and
+    /// confidences outside `[0, 1]` are counted in the first or last bin. An [io::Error]
+    /// instance is returned if `class` is not less than the number of classes, if `n_bins`
+    /// is zero, or if a confidence for `class` is NaN.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use bagheera::classification::ClassificationOutput;
+    ///
+    /// let mut cls_out = ClassificationOutput::<u8, f32>::new(2u8);
+    /// cls_out.add("hello.jpg", vec![0.9f32, 0.1f32]).unwrap();
+    /// cls_out.add("world.jpg", vec![0.3f32, 0.7f32]).unwrap();
+    /// cls_out.add("moon.jpg", vec![1f32, 0f32]).unwrap();
+    /// assert_eq!(cls_out.confidence_histogram(0usize, 2usize).unwrap(), vec![1usize, 2usize]);
+    /// assert!(cls_out.confidence_histogram(2usize, 2usize).is_err());
+    /// ```
+    pub fn confidence_histogram(&self, class: usize, n_bins: usize) -> Result<Vec<usize>, io::Error>
+    where
+        T2: num_traits::ToPrimitive,
+    {
+        let num_classes = self.num_classes_usize();
+        if class >= num_classes {
+            return Err(errors::label_out_of_range(class, num_classes));
+        }
+        if n_bins == 0usize {
+            return Err(errors::no_histogram_bins());
+        }
+        let mut histogram = vec![0usize; n_bins];
+        for (imagename, confidences) in self.data.iter() {
+            if confidences.len() != num_classes {
+                return Err(errors::confidence_length_mismatch(
+                    imagename,
+                    confidences.len(),
+                    num_classes,
                 ));
             }
+            let confidence = confidences[class].to_f64().unwrap_or(f64::NAN);
+            if confidence.is_nan() {
+                return Err(errors::non_finite_confidence(imagename));
+            }
+            let bin = (confidence.clamp(0f64, 1f64) * n_bins as f64) as usize;
+            histogram[bin.min(n_bins - 1usize)] += 1usize;
         }
-        log::debug!("Finished parsing the file.");
-        Ok(ClassificationOutput {
-            num_classes,
-            data: data_hmap,
+        Ok(histogram)
+    }
+
+    /// Returns `true` if the confidence vector of every image is a probability distribution.
+    ///
+    /// A confidence vector is a probability distribution if none of its confidences is
+    /// negative or NaN and if they sum to one within `tol`. This allows failing early when
+    /// logits are given to a metric expecting probabilities, such as the log-loss. An empty
+    /// [`Self`] instance returns `true`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use bagheera::classification::ClassificationOutput;
+    ///
+    /// let mut cls_out = ClassificationOutput::<u8, f32>::new(2u8);
+    /// cls_out.add("hello.jpg", vec![0.25f32, 0.75f32]).unwrap();
+    /// assert!(cls_out.is_probability_distribution(1e-6f32));
+    /// cls_out.add("world.jpg", vec![2.5f32, -1.5f32]).unwrap();
+    /// assert!(!cls_out.is_probability_distribution(1e-6f32));
+    /// ```
+    pub fn is_probability_distribution(&self, tol: T2) -> bool {
+        self.data.values().all(|confidences| {
+            confidences.iter().all(|x| *x >= T2::zero()) && {
+                let sum = confidences.iter().fold(T2::zero(), |acc, x| acc + *x);
+                sum <= T2::one() + tol && sum + tol >= T2::one()
+            }
         })
     }
+
+    /// Returns the top-1 confidence of every image, keyed by image name.
+    ///
+    /// The top-1 confidence is the maximum of the confidence vector. NaN confidences are
+    /// ignored unless every confidence of the image is NaN, in which case the top-1
+    /// confidence is NaN too. Images whose confidence vector is empty are skipped.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use bagheera::classification::ClassificationOutput;
+    ///
+    /// let mut cls_out = ClassificationOutput::<u8, f32>::new(3u8);
+    /// cls_out.add("hello.jpg", vec![0.2f32, 0.7f32, 0.1f32]).unwrap();
+    /// cls_out.add("world.jpg", vec![f32::NAN, 0.4f32, 0.6f32]).unwrap();
+    /// let top1 = cls_out.top1_confidences();
+    /// assert_eq!(top1["hello.jpg"], 0.7f32);
+    /// assert_eq!(top1["world.jpg"], 0.6f32);
+    /// ```
+    pub fn top1_confidences(&self) -> HashMap<String, T2> {
+        self.data
+            .iter()
+            .filter_map(|(imagename, confidences)| {
+                confidences
+                    .iter()
+                    .copied()
+                    .reduce(|best, x| {
+                        if x > best || best.partial_cmp(&best).is_none() {
+                            x
+                        } else {
+                            best
+                        }
+                    })
+                    .map(|best| (imagename.clone(), best))
+            })
+            .collect()
+    }
+
+    /// Adds every image of `other` to the [`Self`] instance.
+    ///
+    /// This is meant for recombining the outputs written by the shards of a distributed
+    /// inference, so the confidences are not averaged as in [`crate::ensemble`]. An
+    /// [io::Error] instance is returned, and the [`Self`] instance is left unchanged, if both
+    /// instances contain an image, or if they have different numbers of classes. An instance
+    /// created with [`Self::new_inferred()`] to which nothing was added yet takes the number
+    /// of classes of `other`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use bagheera::classification::ClassificationOutput;
+    ///
+    /// let mut cls_out = ClassificationOutput::<u8, f32>::new(2u8);
+    /// cls_out.add("hello.jpg", vec![0.25f32, 0.75f32]).unwrap();
+    /// let mut shard = ClassificationOutput::<u8, f32>::new(2u8);
+    /// shard.add("world.jpg", vec![0.5f32, 0.5f32]).unwrap();
+    /// cls_out.merge(shard.clone()).unwrap();
+    /// assert_eq!(cls_out.num_images(), 2usize);
+    /// assert!(cls_out.merge(shard).is_err());
+    /// ```
+    pub fn merge(&mut self, other: Self) -> Result<(), io::Error> {
+        match (self.try_num_classes(), other.try_num_classes()) {
+            (Some(num_classes), Some(other_num_classes)) if num_classes != other_num_classes => {
+                return Err(errors::merge_num_classes_mismatch(
+                    num_classes.to_usize().unwrap(),
+                    other_num_classes.to_usize().unwrap(),
+                ));
+            }
+            (None, Some(other_num_classes)) => {
+                self.num_classes = other_num_classes;
+                self.infer_num_classes = false;
+            }
+            _ => {}
+        }
+        let mut duplicates: Vec<&str> = other
+            .data
+            .keys()
+            .filter(|imagename| self.data.contains_key(imagename.as_str()))
+            .map(|imagename| imagename.as_str())
+            .collect();
+        if !duplicates.is_empty() {
+            duplicates.sort_unstable();
+            return Err(errors::merge_duplicate_images(&duplicates));
+        }
+        self.data.extend(other.data);
+        Ok(())
+    }
+
+    /// Returns the confidences of every image for modification by the tests.
+    #[cfg(test)]
+    pub(crate) fn data_mut(&mut self) -> &mut HashMap<String, Vec<T2>, S> {
+        &mut self.data
+    }
+
     /// Returns the number of object classes.
     ///
     /// # Examples
@@ -165,6 +532,38 @@ impl<
         self.num_classes
     }
 
+    /// Returns the number of object classes, or `None` for an instance created with
+    /// [`Self::new_inferred()`] to which nothing was added yet.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use bagheera::classification::ClassificationOutput;
+    ///  let cls_out = ClassificationOutput::<u32, f64>::new(20u32);
+    ///  assert_eq!(cls_out.try_num_classes(), Some(20u32));
+    /// ```
+    #[inline(always)]
+    pub fn try_num_classes(&self) -> Option<T1> {
+        match self.infer_num_classes {
+            true => None,
+            false => Some(self.num_classes),
+        }
+    }
+
+    /// Returns the number of object classes as a `usize`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use bagheera::classification::ClassificationOutput;
+    ///  let cls_out = ClassificationOutput::<u16, f32>::new(1000u16);
+    ///  assert_eq!(cls_out.num_classes_usize(), 1000usize);
+    /// ```
+    #[inline(always)]
+    pub fn num_classes_usize(&self) -> usize {
+        self.num_classes.to_usize().unwrap()
+    }
+
     /// Returns the number of images in a [`Self`] instance.
     ///
     /// # Examples
@@ -294,7 +693,7 @@ impl<
     #[inline(always)]
     pub fn confidence_for_image(&self, imagename: &str) -> Result<&Vec<T2>, io::Error> {
         if !self.image_is_present(imagename) {
-            return Err(errors::image_not_present_error(imagename));
+            Err(errors::image_not_present_error(imagename))
         } else {
             Ok(&self.data[imagename])
         }
@@ -345,166 +744,2647 @@ impl<
 
         Ok(topk_indices)
     }
-}
 
-/// Generic struct representing an image classification dataset.
-pub struct ClassificationDataset<
-    T1: num_traits::PrimInt + num_traits::Unsigned + num_traits::FromPrimitive,
-> {
-    num_classes: T1,
-    data: HashMap<String, Vec<bool>>,
-    is_multilabel: bool,
-}
+    /// Returns an iterator over the name of every image along with its Top-K class indices.
+    ///
+    /// The Top-K indices are only computed when the iterator is advanced, so results can be
+    /// streamed, for instance to a file, without holding those of every image in memory.
+    /// The images are yielded in the iteration order of the underlying [`HashMap`]. Every
+    /// item is an [io::Error] instance if `k` is more than the number of classes.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use bagheera::classification::ClassificationOutput;
+    ///
+    /// let mut cls_out = ClassificationOutput::<u8, f32>::new(3u8);
+    /// cls_out.add("hello.jpg", vec![0.2f32, 0.7f32, 0.1f32]).unwrap();
+    /// let mut topk = cls_out.topk_iter(2usize);
+    /// assert_eq!(topk.next().unwrap().unwrap(), ("hello.jpg", vec![1usize, 0usize]));
+    /// assert!(topk.next().is_none());
+    /// ```
+    pub fn topk_iter(
+        &self,
+        k: usize,
+    ) -> impl Iterator<Item = Result<(&str, Vec<usize>), io::Error>> + '_
+    where
+        Vec<T2>: utils::TopK,
+    {
+        self.data.iter().map(move |(imagename, confidences)| {
+            confidences
+                .top_k(k)
+                .map(|topk_indices| (imagename.as_str(), topk_indices))
+        })
+    }
 
-impl<T1: num_traits::PrimInt + num_traits::Unsigned + num_traits::FromPrimitive>
-    ClassificationDataset<T1>
-{
-    /// Returns a new empty instance of [`Self<T1>`].
+    /// Returns the Top-K class indices of every image, keyed by image name.
     ///
-    /// Images can be added to the instance using the [`Self::add()`] function.
+    /// This collects [`Self::topk_iter()`], which should be preferred for large instances.
+    /// An [io::Error] instance is returned if `k` is more than the number of classes.
     ///
     /// # Examples
     ///
     /// ```rust
-    /// use bagheera::classification::ClassificationDataset;
+    /// use bagheera::classification::ClassificationOutput;
     ///
-    /// let cls_db = ClassificationDataset::new(30u8, false);
-    /// assert_eq!(cls_db.num_classes(), 30u8);
+    /// let mut cls_out = ClassificationOutput::<u8, f32>::new(3u8);
+    /// cls_out.add("hello.jpg", vec![0.2f32, 0.7f32, 0.1f32]).unwrap();
+    /// cls_out.add("world.jpg", vec![0.5f32, 0.1f32, 0.4f32]).unwrap();
+    /// let topk = cls_out.topk_all(1usize).unwrap();
+    /// assert_eq!(topk["hello.jpg"], vec![1usize]);
+    /// assert_eq!(topk["world.jpg"], vec![0usize]);
     /// ```
-    pub fn new(num_classes: T1, is_multilabel: bool) -> Self {
-        ClassificationDataset {
-            num_classes,
-            data: HashMap::<String, Vec<bool>>::new(),
-            is_multilabel,
+    pub fn topk_all(&self, k: usize) -> Result<HashMap<String, Vec<usize>, S>, io::Error>
+    where
+        Vec<T2>: utils::TopK,
+    {
+        let mut topk = HashMap::with_capacity_and_hasher(self.data.len(), S::default());
+        for item in self.topk_iter(k) {
+            let (imagename, topk_indices) = item?;
+            topk.insert(imagename.to_string(), topk_indices);
         }
+        Ok(topk)
     }
 
-    /// Adds a new GT to the [`Self`] instance.
+    /// Returns the names of the Top-K classes of `imagename`, where `class_names[i]` is the
+    /// name of class `i`.
     ///
-    /// If `imagename` is already in [`Self`] instance, an [io::Error] instance is returned.
-    /// In case of issues during one-hot conversion, a panic happens.
+    /// The names are in the order of [`Self::topk_for_image()`]. An [io::Error] instance is
+    /// returned if `class_names` does not have one entry per class, if `imagename` is not
+    /// present or if `k` is more than the number of classes.
     ///
     /// # Examples
     ///
     /// ```rust
-    /// use bagheera::classification::ClassificationDataset;
+    /// use bagheera::classification::ClassificationOutput;
     ///
-    /// let mut cls_db = ClassificationDataset::new(5u16, false);
-    /// cls_db.add("hello.jpg", &vec![1u16]);
-    /// assert_eq!(cls_db.num_images(), 1usize);
+    /// let class_names = vec!["cat".to_string(), "dog".to_string(), "bird".to_string()];
+    /// let mut cls_out = ClassificationOutput::<u8, f32>::new(3u8);
+    /// cls_out.add("hello.jpg", vec![0.2f32, 0.7f32, 0.1f32]).unwrap();
+    /// assert_eq!(
+    ///     cls_out.topk_labels_for_image("hello.jpg", 2usize, &class_names).unwrap(),
+    ///     vec!["dog".to_string(), "cat".to_string()]
+    /// );
     /// ```
-    pub fn add(&mut self, imagename: &str, category_labels: &Vec<T1>) -> Result<(), io::Error> {
-        if self.image_is_present(imagename) {
-            return Err(io::Error::new(
-                io::ErrorKind::InvalidInput,
-                format!("Image {} was already present.", imagename),
-            ));
+    pub fn topk_labels_for_image(
+        &self,
+        imagename: &str,
+        k: usize,
+        class_names: &[String],
+    ) -> Result<Vec<String>, io::Error>
+    where
+        Vec<T2>: utils::TopK,
+    {
+        if class_names.len() != self.num_classes_usize() {
+            return Err(errors::class_mapping_length_mismatch(
+                class_names.len(),
+                self.num_classes_usize(),
+            ));
+        }
+        Ok(self
+            .confidence_for_image(imagename)?
+            .top_k(k)?
+            .into_iter()
+            .map(|class| class_names[class].clone())
+            .collect())
+    }
+
+    /// Returns the classes whose confidence for `imagename` is at least `threshold`, in
+    /// increasing order.
+    ///
+    /// This is the multi-label prediction of the classifier for a global threshold. An
+    /// [io::Error] instance is returned if `imagename` is not present.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use bagheera::classification::ClassificationOutput;
+    ///
+    /// let mut cls_out = ClassificationOutput::<u8, f32>::new(3u8);
+    /// cls_out.add("hello.jpg", vec![0.6f32, 0.2f32, 0.5f32]).unwrap();
+    /// let labels = cls_out.predicted_labels_thresholded("hello.jpg", 0.5f32).unwrap();
+    /// assert_eq!(labels, vec![0usize, 2usize]);
+    /// ```
+    pub fn predicted_labels_thresholded(
+        &self,
+        imagename: &str,
+        threshold: T2,
+    ) -> Result<Vec<usize>, io::Error> {
+        Ok(self
+            .confidence_for_image(imagename)?
+            .iter()
+            .enumerate()
+            .filter(|(_, confidence)| **confidence >= threshold)
+            .map(|(class, _)| class)
+            .collect())
+    }
+
+    /// Returns the classes whose confidence for `imagename` is at least their threshold in
+    /// `thresholds`, in increasing order.
+    ///
+    /// This is [`Self::predicted_labels_thresholded()`] with a threshold per class. An
+    /// [io::Error] instance is returned if `imagename` is not present or if `thresholds`
+    /// does not have one entry per class.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use bagheera::classification::ClassificationOutput;
+    ///
+    /// let mut cls_out = ClassificationOutput::<u8, f32>::new(3u8);
+    /// cls_out.add("hello.jpg", vec![0.6f32, 0.2f32, 0.5f32]).unwrap();
+    /// let thresholds = [0.7f32, 0.1f32, 0.5f32];
+    /// let labels = cls_out
+    ///     .predicted_labels_with_thresholds("hello.jpg", &thresholds)
+    ///     .unwrap();
+    /// assert_eq!(labels, vec![1usize, 2usize]);
+    /// ```
+    pub fn predicted_labels_with_thresholds(
+        &self,
+        imagename: &str,
+        thresholds: &[T2],
+    ) -> Result<Vec<usize>, io::Error> {
+        if thresholds.len() != self.num_classes_usize() {
+            return Err(errors::thresholds_length_mismatch(
+                thresholds.len(),
+                self.num_classes_usize(),
+            ));
+        }
+        Ok(self
+            .confidence_for_image(imagename)?
+            .iter()
+            .zip(thresholds.iter())
+            .enumerate()
+            .filter(|(_, (confidence, threshold))| *confidence >= *threshold)
+            .map(|(class, _)| class)
+            .collect())
+    }
+
+    /// Returns the `k` classes with the highest confidences for `imagename` as
+    /// [`TopKResult`] instances.
+    ///
+    /// Unlike [`Self::topk_for_image()`], every entry also holds its rank, starting at 0 for
+    /// the best class, and its confidence. An [io::Error] instance is returned if `imagename`
+    /// is not present or if `k` is more than the number of classes.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use bagheera::classification::ClassificationOutput;
+    ///
+    /// let mut cls_out = ClassificationOutput::<u8, f32>::new(3u8);
+    /// cls_out.add("hello.jpg", vec![0.2f32, 0.7f32, 0.1f32]).unwrap();
+    /// let topk = cls_out.topk_detailed_for_image("hello.jpg", 2usize).unwrap();
+    /// assert_eq!(topk[0].rank(), 0usize);
+    /// assert_eq!(topk[0].class(), 1usize);
+    /// assert_eq!(topk[1].confidence(), 0.2f32);
+    /// ```
+    pub fn topk_detailed_for_image(
+        &self,
+        imagename: &str,
+        k: usize,
+    ) -> Result<Vec<TopKResult<T2>>, io::Error>
+    where
+        Vec<T2>: utils::TopK,
+    {
+        let confidences = self.confidence_for_image(imagename)?;
+        Ok(confidences
+            .top_k(k)?
+            .into_iter()
+            .enumerate()
+            .map(|(rank, class)| TopKResult {
+                rank,
+                class,
+                confidence: confidences[class],
+            })
+            .collect())
+    }
+
+    /// Returns the 0-based position of `class` when the confidences of `imagename` are
+    /// sorted in descending order.
+    ///
+    /// Classes with equal confidences are ranked by increasing class index and NaN
+    /// confidences are ranked after every other confidence, so the rank is well defined for
+    /// any confidence vector. An [io::Error] instance is returned if `imagename` is not
+    /// present or if `class` is not less than the number of classes.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use bagheera::classification::ClassificationOutput;
+    ///
+    /// let mut cls_out = ClassificationOutput::<u8, f32>::new(4u8);
+    /// cls_out.add("hello.jpg", vec![0.3f32, 0.1f32, 0.3f32, f32::NAN]).unwrap();
+    /// assert_eq!(cls_out.rank_of_class("hello.jpg", 0usize).unwrap(), 0usize);
+    /// assert_eq!(cls_out.rank_of_class("hello.jpg", 2usize).unwrap(), 1usize);
+    /// assert_eq!(cls_out.rank_of_class("hello.jpg", 3usize).unwrap(), 3usize);
+    /// ```
+    pub fn rank_of_class(&self, imagename: &str, class: usize) -> Result<usize, io::Error> {
+        let confidences = self.confidence_for_image(imagename)?;
+        if class >= confidences.len() {
+            return Err(errors::label_out_of_range(class, confidences.len()));
+        }
+        let is_nan = |x: &T2| x.partial_cmp(x).is_none();
+        let target = &confidences[class];
+        Ok(confidences
+            .iter()
+            .enumerate()
+            .filter(
+                |(other, confidence)| match (is_nan(target), is_nan(confidence)) {
+                    (true, true) => *other < class,
+                    (true, false) => true,
+                    (false, true) => false,
+                    (false, false) => {
+                        *confidence > target || (*confidence == target && *other < class)
+                    }
+                },
+            )
+            .count())
+    }
+
+    /// Returns at most `k` classes with the highest confidences for `imagename`, keeping only
+    /// the classes whose confidence is at least `min_conf`.
+    ///
+    /// The classes are returned in descending order of confidence, so fewer than `k` classes
+    /// are returned when some of the Top-K confidences are below `min_conf`. An [io::Error]
+    /// instance is returned if `imagename` is not present or if `k` is more than the number
+    /// of classes.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use bagheera::classification::ClassificationOutput;
+    ///
+    /// let mut cls_out = ClassificationOutput::<u8, f32>::new(4u8);
+    /// cls_out.add("hello.jpg", vec![0.1f32, 0.6f32, 0.25f32, 0.05f32]).unwrap();
+    /// let classes = cls_out.topk_for_image_min_conf("hello.jpg", 3usize, 0.2f32).unwrap();
+    /// assert_eq!(classes, vec![1usize, 2usize]);
+    /// ```
+    pub fn topk_for_image_min_conf(
+        &self,
+        imagename: &str,
+        k: usize,
+        min_conf: T2,
+    ) -> Result<Vec<usize>, io::Error>
+    where
+        Vec<T2>: utils::TopK,
+    {
+        let confidences = self.confidence_for_image(imagename)?;
+        let topk_indices = confidences.top_k(k)?;
+        Ok(topk_indices
+            .into_iter()
+            .take_while(|class| confidences[*class] >= min_conf)
+            .collect())
+    }
+
+    /// Returns the confidences of every image, keyed by image name.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use bagheera::classification::ClassificationOutput;
+    ///
+    /// let mut cls_out = ClassificationOutput::<u8, f32>::new(2u8);
+    /// cls_out.add("hello.jpg", vec![0.1f32, 0.9f32]).unwrap();
+    /// assert_eq!(cls_out.data()["hello.jpg"], vec![0.1f32, 0.9f32]);
+    /// ```
+    pub fn data(&self) -> &HashMap<String, Vec<T2>, S> {
+        &self.data
+    }
+
+    /// Consumes the [`Self`] instance and returns the confidences of every image, keyed by
+    /// image name.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use bagheera::classification::ClassificationOutput;
+    ///
+    /// let mut cls_out = ClassificationOutput::<u8, f32>::new(2u8);
+    /// cls_out.add("hello.jpg", vec![0.1f32, 0.9f32]).unwrap();
+    /// let data = cls_out.into_data();
+    /// assert_eq!(data.len(), 1usize);
+    /// ```
+    pub fn into_data(self) -> HashMap<String, Vec<T2>, S> {
+        self.data
+    }
+
+    /// Replaces the name of every image in the [`Self`] instance by `f(name)`.
+    ///
+    /// This makes a classifier output match a groundtruth which names images differently,
+    /// for instance using [`utils::basename`] to strip directories. An [io::Error] instance
+    /// is returned if two names are mapped to the same one, in which case the [`Self`]
+    /// instance is left unchanged.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use bagheera::classification::ClassificationOutput;
+    /// use bagheera::utils::basename;
+    ///
+    /// let mut cls_out = ClassificationOutput::<u8, f32>::new(2u8);
+    /// cls_out.add("/data/val/hello.jpg", vec![0.1f32, 0.9f32]).unwrap();
+    /// cls_out.normalize_keys(basename).unwrap();
+    /// assert!(cls_out.image_is_present("hello.jpg"));
+    /// ```
+    pub fn normalize_keys(&mut self, f: impl Fn(&str) -> String) -> Result<(), io::Error> {
+        utils::normalize_map_keys(&mut self.data, f)
+    }
+
+    /// Writes the [`Self`] instance to `filename` in a compact binary format.
+    ///
+    /// Every confidence is stored with its own in-memory size in little-endian order, which
+    /// makes the files much smaller and faster to read than CSV or JSON ones. Image names
+    /// and confidence vectors are limited to `u32::MAX` bytes and entries respectively. The images
+    /// are written sorted by name, so two equal instances give the same file. The file is
+    /// read back with [`ClassificationOutput::from_bincode_file()`].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use bagheera::classification::ClassificationOutput;
+    ///
+    /// let mut cls_out = ClassificationOutput::<u8, f32>::new(2u8);
+    /// cls_out.add("hello.jpg", vec![0.1f32, 0.9f32]).unwrap();
+    /// let filename = std::env::temp_dir().join("bagheera_doc_to_bincode_file.bin");
+    /// cls_out.to_bincode_file(filename.to_str().unwrap()).unwrap();
+    /// let loaded = ClassificationOutput::<u8, f32>::from_bincode_file(filename.to_str().unwrap()).unwrap();
+    /// assert_eq!(loaded, cls_out);
+    /// ```
+    #[cfg(feature = "bincode")]
+    pub fn to_bincode_file(&self, filename: &str) -> Result<(), io::Error>
+    where
+        T2: LeBytes,
+    {
+        let mut writer = io::BufWriter::new(utils::create_file(filename)?);
+        let mut buffer = Vec::<u8>::with_capacity(BINCODE_HEADER_LEN);
+        buffer.extend_from_slice(BINCODE_MAGIC);
+        buffer.push(BINCODE_VERSION);
+        buffer.push(T2::SIZE as u8);
+        (self.num_classes_usize() as u64).write_le(&mut buffer);
+        (self.data.len() as u64).write_le(&mut buffer);
+        writer.write_all(&buffer)?;
+
+        let mut imagenames: Vec<&String> = self.data.keys().collect();
+        imagenames.sort();
+        for imagename in imagenames {
+            let confidences = &self.data[imagename];
+            buffer.clear();
+            (imagename.len() as u32).write_le(&mut buffer);
+            buffer.extend_from_slice(imagename.as_bytes());
+            (confidences.len() as u32).write_le(&mut buffer);
+            for confidence in confidences {
+                confidence.write_le(&mut buffer);
+            }
+            writer.write_all(&buffer)?;
+        }
+        writer.flush()?;
+        log::debug!("Wrote ClassificationOutput to {}.", filename);
+        Ok(())
+    }
+
+    /// Writes the top predictions of every image of the [`Self`] instance to the CSV file
+    /// `filename`.
+    ///
+    /// The file starts with the header `imagename,top1_class,top1_conf,topk_classes`. Each
+    /// following line holds the name of an image, its top-1 class and the confidence of that
+    /// class, and its Top-K classes separated by spaces. The images are written sorted by
+    /// name and ties are broken as in [`TopK::top_k()`]. An [io::Error] instance is returned
+    /// if the file cannot be written or if `k` is zero or more than the number of classes.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use bagheera::classification::ClassificationOutput;
+    ///
+    /// let mut cls_out = ClassificationOutput::<u8, f32>::new(3u8);
+    /// cls_out.add("hello.jpg", vec![0.25f32, 0.5f32, 0.25f32]).unwrap();
+    /// let filename = std::env::temp_dir().join("bagheera_doc_write_detailed_csv.csv");
+    /// cls_out.write_detailed_csv(filename.to_str().unwrap(), 2usize).unwrap();
+    /// assert_eq!(
+    ///     std::fs::read_to_string(&filename).unwrap(),
+    ///     "imagename,top1_class,top1_conf,topk_classes\nhello.jpg,1,0.5,1 0\n"
+    /// );
+    /// ```
+    pub fn write_detailed_csv(&self, filename: &str, k: usize) -> Result<(), io::Error>
+    where
+        T2: fmt::Display,
+        Vec<T2>: TopK,
+    {
+        self.write_detailed_csv_with_separators(filename, k, ',', ' ')
+    }
+
+    /// Writes the same file as [`Self::write_detailed_csv()`], with the fields separated by
+    /// the delimiter of `options` and the Top-K classes by its label separator.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use bagheera::classification::{ClassificationOutput, CsvOptions};
+    ///
+    /// let mut cls_out = ClassificationOutput::<u8, f32>::new(3u8);
+    /// cls_out.add("hello.jpg", vec![0.25f32, 0.5f32, 0.25f32]).unwrap();
+    /// let filename = std::env::temp_dir().join("bagheera_doc_write_detailed_csv_options.tsv");
+    /// let options = CsvOptions::new(b'\t', '|').unwrap();
+    /// cls_out
+    ///     .write_detailed_csv_with_options(filename.to_str().unwrap(), 2usize, &options)
+    ///     .unwrap();
+    /// assert_eq!(
+    ///     std::fs::read_to_string(&filename).unwrap(),
+    ///     "imagename\ttop1_class\ttop1_conf\ttopk_classes\nhello.jpg\t1\t0.5\t1|0\n"
+    /// );
+    /// ```
+    pub fn write_detailed_csv_with_options(
+        &self,
+        filename: &str,
+        k: usize,
+        options: &CsvOptions,
+    ) -> Result<(), io::Error>
+    where
+        T2: fmt::Display,
+        Vec<T2>: TopK,
+    {
+        self.write_detailed_csv_with_separators(
+            filename,
+            k,
+            options.delimiter() as char,
+            options.label_sep(),
+        )
+    }
+
+    /// Writes the file of [`Self::write_detailed_csv()`] with the fields separated by
+    /// `delimiter` and the Top-K classes by `class_sep`.
+    fn write_detailed_csv_with_separators(
+        &self,
+        filename: &str,
+        k: usize,
+        delimiter: char,
+        class_sep: char,
+    ) -> Result<(), io::Error>
+    where
+        T2: fmt::Display,
+        Vec<T2>: TopK,
+    {
+        if k == 0usize {
+            return Err(errors::topk_incorrect_k(
+                k,
+                self.num_classes.to_usize().unwrap(),
+            ));
+        }
+        let mut imagenames: Vec<&String> = self.data.keys().collect();
+        imagenames.sort_unstable();
+        let mut writer = io::BufWriter::new(utils::create_file(filename)?);
+        writeln!(
+            writer,
+            "imagename{0}top1_class{0}top1_conf{0}topk_classes",
+            delimiter
+        )?;
+        for imagename in imagenames {
+            let confidences = &self.data[imagename];
+            let ranked = confidences.top_k(k)?;
+            let topk_classes = ranked
+                .iter()
+                .map(|class| class.to_string())
+                .collect::<Vec<String>>()
+                .join(&class_sep.to_string());
+            writeln!(
+                writer,
+                "{1}{0}{2}{0}{3}{0}{4}",
+                delimiter, imagename, ranked[0], confidences[ranked[0]], topk_classes
+            )?;
+        }
+        writer.flush()?;
+        log::debug!("Wrote detailed predictions to {}.", filename);
+        Ok(())
+    }
+
+    /// Returns a single-label [`ClassificationDataset`] holding the top-1 class of every image.
+    ///
+    /// This allows predictions to be analysed with the same tooling as the groundtruth. Ties
+    /// are broken as in [`TopK::top_k()`]. An [io::Error] instance is returned if the
+    /// confidence vector of an image is empty, which is only possible with zero classes.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use bagheera::classification::ClassificationOutput;
+    ///
+    /// let mut cls_out = ClassificationOutput::<u8, f32>::new(3u8);
+    /// cls_out.add("hello.jpg", vec![0.2f32, 0.7f32, 0.1f32]).unwrap();
+    /// let predicted = cls_out.to_label_dataset().unwrap();
+    /// assert!(!predicted.is_multilabel());
+    /// assert_eq!(predicted.get_gt("hello.jpg").unwrap(), &vec![false, true, false]);
+    /// ```
+    pub fn to_label_dataset(&self) -> Result<ClassificationDataset<T1, S>, io::Error>
+    where
+        Vec<T2>: utils::TopK,
+    {
+        let mut dataset = ClassificationDataset::with_hasher(self.num_classes, false, S::default());
+        for (imagename, confidences) in self.data.iter() {
+            let top1 = confidences.top_k(1usize)?[0];
+            let predicted =
+                T1::from_usize(top1).ok_or_else(|| errors::num_classes_overflow(top1))?;
+            dataset.add(imagename, &vec![predicted])?;
+        }
+        Ok(dataset)
+    }
+
+    /// Returns a new [`Self`] instance with the confidence of every class `i` moved to class
+    /// `mapping[i]`.
+    ///
+    /// This keeps a classifier output consistent with a dataset remapped using
+    /// [`ClassificationDataset::remap_classes()`]. The confidences of classes mapped to the
+    /// same class are combined as given by `reduce`, and classes which no class is mapped to
+    /// get a zero confidence. An [io::Error] instance is returned if `mapping` does not have
+    /// one entry per class or if any of its entries is not less than `new_num_classes`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use bagheera::classification::{ClassificationOutput, Reduce};
+    ///
+    /// let mut cls_out = ClassificationOutput::<u8, f32>::new(3u8);
+    /// cls_out.add("hello.jpg", vec![0.5f32, 0.25f32, 0.25f32]).unwrap();
+    /// let summed = cls_out.remap_classes(&[0usize, 1usize, 1usize], 2usize, Reduce::Sum).unwrap();
+    /// assert_eq!(summed.confidence_for_image("hello.jpg").unwrap(), &vec![0.5f32, 0.5f32]);
+    /// ```
+    pub fn remap_classes(
+        &self,
+        mapping: &[usize],
+        new_num_classes: usize,
+        reduce: Reduce,
+    ) -> Result<ClassificationOutput<T1, T2, S>, io::Error> {
+        let num_classes = self.num_classes_usize();
+        if mapping.len() != num_classes {
+            return Err(errors::class_mapping_length_mismatch(
+                mapping.len(),
+                num_classes,
+            ));
+        }
+        if let Some(target) = mapping.iter().find(|x| **x >= new_num_classes) {
+            return Err(errors::label_out_of_range(*target, new_num_classes));
+        }
+        let new_num_classes_t1 = T1::from_usize(new_num_classes)
+            .ok_or_else(|| errors::num_classes_overflow(new_num_classes))?;
+
+        let mut data = HashMap::with_capacity_and_hasher(self.num_images(), S::default());
+        for (imagename, confidences) in self.data.iter() {
+            let mut remapped = vec![None; new_num_classes];
+            for (class, confidence) in confidences.iter().enumerate() {
+                let target = &mut remapped[mapping[class]];
+                *target = match (*target, reduce) {
+                    (None, _) => Some(*confidence),
+                    (Some(current), Reduce::Sum) => Some(current + *confidence),
+                    (Some(current), Reduce::Max) if *confidence > current => Some(*confidence),
+                    (Some(current), Reduce::Max) => Some(current),
+                };
+            }
+            data.insert(
+                imagename.clone(),
+                remapped
+                    .into_iter()
+                    .map(|x| x.unwrap_or_else(T2::zero))
+                    .collect(),
+            );
+        }
+        Ok(ClassificationOutput {
+            num_classes: new_num_classes_t1,
+            data,
+            infer_num_classes: false,
+        })
+    }
+}
+
+/// Returns `value` clipped to `[lo, hi]`, leaving NaN unchanged.
+#[inline(always)]
+fn clip<T2: PartialOrd>(value: T2, lo: T2, hi: T2) -> T2 {
+    if value < lo {
+        lo
+    } else if value > hi {
+        hi
+    } else {
+        value
+    }
+}
+
+/// Generic struct holding one entry of the Top-K classes of an image.
+///
+/// Instances are returned by [`ClassificationOutput::topk_detailed_for_image()`]. With the
+/// `serde` feature, they can be serialized.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct TopKResult<T2: num_traits::Num + Copy + PartialOrd> {
+    rank: usize,
+    class: usize,
+    confidence: T2,
+}
+
+impl<T2: num_traits::Num + Copy + PartialOrd> TopKResult<T2> {
+    /// Returns the rank of the entry, 0 being the class with the highest confidence.
+    pub fn rank(&self) -> usize {
+        self.rank
+    }
+
+    /// Returns the class index of the entry.
+    pub fn class(&self) -> usize {
+        self.class
+    }
+
+    /// Returns the confidence of the class.
+    pub fn confidence(&self) -> T2 {
+        self.confidence
+    }
+}
+
+/// Enum listing the ways confidences of merged classes are combined by
+/// [`ClassificationOutput::remap_classes()`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Reduce {
+    /// The confidences are added up.
+    Sum,
+    /// The largest confidence is kept.
+    Max,
+}
+
+impl<const N: usize, T2: num_traits::Num + Copy + PartialOrd> FixedClassOutput<N, T2> {
+    /// Consumes the [`FixedClassOutput`] instance and returns a [`ClassificationOutput`]
+    /// holding the same confidences, so that it can be evaluated with [`crate::metrics`].
+    ///
+    /// An [io::Error] instance is returned if `N` does not fit in `T1`.
+    pub fn into_classification_output<
+        T1: num_traits::PrimInt + num_traits::Unsigned + num_traits::FromPrimitive,
+    >(
+        self,
+    ) -> Result<ClassificationOutput<T1, T2>, io::Error> {
+        let num_classes = T1::from_usize(N).ok_or_else(|| errors::num_classes_overflow(N))?;
+        let mut cls_out = ClassificationOutput::new(num_classes);
+        for (imagename, confidences) in self.iter() {
+            cls_out.add(imagename, confidences.to_vec())?;
+        }
+        Ok(cls_out)
+    }
+}
+
+impl<
+        T1: num_traits::PrimInt + num_traits::Unsigned + num_traits::FromPrimitive,
+        T2: num_traits::Float + fast_float::FastFloat + num_traits::FromPrimitive,
+    > ClassificationOutput<T1, T2>
+{
+    /// Creates a new instance of [`Self`] from a CSV file.
+    pub fn from_csv_file(csv_filename: &str, num_classes: T1) -> Result<Self, io::Error> {
+        Self::from_csv_file_with_options(csv_filename, num_classes, &CsvOptions::default())
+    }
+
+    /// Creates a new instance of [`Self`] from a CSV file whose fields are separated by the
+    /// delimiter of `options`.
+    ///
+    /// An [io::Error] instance is returned if the file cannot be read, or if a line is
+    /// empty, holds a confidence which cannot be parsed or does not hold `num_classes`
+    /// confidences. Such errors report the 1-based number of the offending line.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use bagheera::classification::{ClassificationOutput, CsvOptions};
+    ///
+    /// let filename = std::env::temp_dir().join("bagheera_doc_from_csv_file_with_options.tsv");
+    /// std::fs::write(&filename, "hello.jpg\t0.25\t0.75\n").unwrap();
+    /// let options = CsvOptions::new(b'\t', '|').unwrap();
+    /// let cls_out = ClassificationOutput::<u8, f32>::from_csv_file_with_options(
+    ///     filename.to_str().unwrap(),
+    ///     2u8,
+    ///     &options,
+    /// )
+    /// .unwrap();
+    /// assert_eq!(&cls_out["hello.jpg"], &[0.25f32, 0.75f32]);
+    /// ```
+    pub fn from_csv_file_with_options(
+        csv_filename: &str,
+        num_classes: T1,
+        options: &CsvOptions,
+    ) -> Result<Self, io::Error> {
+        let delimiter = options.delimiter() as char;
+        let fid = utils::open_file(csv_filename)?;
+        let mut bufread = BufReader::new(fid);
+        let mut numlines = 0usize;
+        for line in bufread.by_ref().lines() {
+            line?;
+            numlines += 1;
+        }
+        log::debug!(
+            "There are a total of {} lines in {}.",
+            numlines,
+            csv_filename
+        );
+        let mut data_hmap =
+            HashMap::<String, Vec<T2>, DefaultHashBuilder>::with_capacity_and_hasher(
+                numlines,
+                DefaultHashBuilder::default(),
+            );
+        bufread.seek(SeekFrom::Start(0u64))?;
+        log::debug!("Reading and parsing lines from the file.");
+        for (line_index, line) in bufread.lines().enumerate() {
+            let line_num = line_index + 1usize;
+            let line = line?;
+            let line_trimmed = line.trim();
+            if line_trimmed.is_empty() {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!(
+                        "Line {} of {} is empty. This is an error.",
+                        line_num, csv_filename
+                    ),
+                ));
+            }
+            let mut imagename = String::new();
+
+            for (token_num, token) in line_trimmed.split(delimiter).enumerate() {
+                if token_num == 0usize {
+                    imagename = token.to_string();
+                    data_hmap.insert(token.to_string(), Vec::<T2>::new());
+                    continue;
+                }
+
+                let confidence = fast_float::parse::<T2, _>(token).map_err(|_| {
+                    errors::invalid_line(
+                        csv_filename,
+                        line_num,
+                        &errors::unparsable_confidence(token),
+                    )
+                })?;
+                data_hmap.get_mut(&imagename).unwrap().push(confidence);
+            }
+            if T1::from_usize(data_hmap[&imagename].len()).unwrap() != num_classes {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!(
+                        "Line {} of {} contains {} classes when num_classes is specified as {}",
+                        line_num,
+                        csv_filename,
+                        data_hmap[&imagename].len(),
+                        num_classes.to_usize().unwrap()
+                    ),
+                ));
+            }
+        }
+        log::debug!("Finished parsing the file.");
+        Ok(ClassificationOutput {
+            num_classes,
+            data: data_hmap,
+            infer_num_classes: false,
+        })
+    }
+}
+
+#[cfg(feature = "bincode")]
+impl<
+        T1: num_traits::PrimInt + num_traits::Unsigned + num_traits::FromPrimitive,
+        T2: num_traits::Num + Copy + PartialOrd + LeBytes,
+    > ClassificationOutput<T1, T2>
+{
+    /// Creates a new instance of [`Self`] from a binary file written by
+    /// [`Self::to_bincode_file()`].
+    ///
+    /// An [io::Error] instance is returned if the file cannot be read, if it was written
+    /// with confidences of a different size than `T2`, if its number of classes does not fit
+    /// in `T1`, or if it is truncated or otherwise corrupted. In particular, every confidence
+    /// vector must have one entry per class.
+    pub fn from_bincode_file(filename: &str) -> Result<Self, io::Error> {
+        let mut bytes = Vec::<u8>::new();
+        BufReader::new(utils::open_file(filename)?).read_to_end(&mut bytes)?;
+        let mut reader = BincodeReader {
+            bytes: &bytes,
+            position: 0usize,
+            filename,
+        };
+
+        if reader.take(BINCODE_MAGIC.len())? != BINCODE_MAGIC {
+            return Err(errors::invalid_binary_file(
+                filename,
+                "it was not written by bagheera",
+            ));
+        }
+        let header = reader.take(2usize)?;
+        if header[0] != BINCODE_VERSION {
+            return Err(errors::invalid_binary_file(
+                filename,
+                &format!("unsupported format version {}", header[0]),
+            ));
+        }
+        if header[1] as usize != T2::SIZE {
+            return Err(errors::invalid_binary_file(
+                filename,
+                &format!(
+                    "confidences are stored using {} bytes instead of {}",
+                    header[1],
+                    T2::SIZE
+                ),
+            ));
+        }
+        let num_classes_usize = reader.read_count()?;
+        let num_classes = T1::from_usize(num_classes_usize).ok_or_else(|| {
+            errors::invalid_binary_file(
+                filename,
+                &format!("{} classes do not fit in the label type", num_classes_usize),
+            )
+        })?;
+        let num_images = reader.read_count()?;
+
+        let mut data = HashMap::<String, Vec<T2>, DefaultHashBuilder>::with_capacity_and_hasher(
+            num_images.min(reader.remaining() / (2usize * 4usize)),
+            DefaultHashBuilder::default(),
+        );
+        for _ in 0..num_images {
+            let name_len = reader.read_len()?;
+            let imagename = std::str::from_utf8(reader.take(name_len)?)
+                .map_err(|_| errors::invalid_binary_file(filename, "an image name is not UTF-8"))?
+                .to_string();
+            let vector_len = reader.read_len()?;
+            if vector_len != num_classes_usize {
+                return Err(errors::invalid_binary_file(
+                    filename,
+                    &format!(
+                        "the image {} has {} confidences while there are {} classes",
+                        imagename, vector_len, num_classes_usize
+                    ),
+                ));
+            }
+            let values = reader.take(vector_len.checked_mul(T2::SIZE).ok_or_else(|| {
+                errors::invalid_binary_file(filename, "a confidence vector is too long")
+            })?)?;
+            let confidences: Vec<T2> = values.chunks_exact(T2::SIZE).map(T2::read_le).collect();
+            if data.insert(imagename.clone(), confidences).is_some() {
+                return Err(errors::invalid_binary_file(
+                    filename,
+                    &format!("the image {} is present more than once", imagename),
+                ));
+            }
+        }
+        if reader.remaining() != 0usize {
+            return Err(errors::invalid_binary_file(
+                filename,
+                "there are trailing bytes after the last image",
+            ));
+        }
+        log::debug!("Read ClassificationOutput from {}.", filename);
+        Ok(ClassificationOutput {
+            num_classes,
+            data,
+            infer_num_classes: false,
+        })
+    }
+}
+
+/// Bytes starting every file written by [`ClassificationOutput::to_bincode_file()`].
+#[cfg(feature = "bincode")]
+const BINCODE_MAGIC: &[u8] = b"BGHRCLSO";
+
+/// Version of the format written by [`ClassificationOutput::to_bincode_file()`].
+#[cfg(feature = "bincode")]
+const BINCODE_VERSION: u8 = 1u8;
+
+/// Length of the header of a file written by [`ClassificationOutput::to_bincode_file()`]:
+/// the magic bytes, the version, the size of a confidence and two `u64` counts.
+#[cfg(feature = "bincode")]
+const BINCODE_HEADER_LEN: usize = 8usize + 2usize + 2usize * 8usize;
+
+/// Cursor over the bytes of a file read by [`ClassificationOutput::from_bincode_file()`].
+#[cfg(feature = "bincode")]
+struct BincodeReader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+    filename: &'a str,
+}
+
+#[cfg(feature = "bincode")]
+impl<'a> BincodeReader<'a> {
+    /// Returns the number of bytes left to read.
+    fn remaining(&self) -> usize {
+        self.bytes.len() - self.position
+    }
+
+    /// Returns the next `n` bytes, or an [io::Error] instance if fewer are left.
+    fn take(&mut self, n: usize) -> Result<&'a [u8], io::Error> {
+        if n > self.remaining() {
+            return Err(errors::invalid_binary_file(
+                self.filename,
+                "it ends unexpectedly",
+            ));
+        }
+        let bytes = &self.bytes[self.position..self.position + n];
+        self.position += n;
+        Ok(bytes)
+    }
+
+    /// Reads a little-endian `u64` count of the header as a `usize`.
+    fn read_count(&mut self) -> Result<usize, io::Error> {
+        let count = u64::read_le(self.take(8usize)?);
+        usize::try_from(count).map_err(|_| {
+            errors::invalid_binary_file(self.filename, "a count does not fit in usize")
+        })
+    }
+
+    /// Reads the little-endian `u32` length of an image name or of a confidence vector.
+    fn read_len(&mut self) -> Result<usize, io::Error> {
+        Ok(u32::read_le(self.take(4usize)?) as usize)
+    }
+}
+
+impl<
+        T1: num_traits::PrimInt + num_traits::Unsigned + num_traits::FromPrimitive,
+        T2: num_traits::Num + Copy + PartialOrd,
+        S: BuildHasher,
+    > PartialEq for ClassificationOutput<T1, T2, S>
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.num_classes == other.num_classes
+            && self.infer_num_classes == other.infer_num_classes
+            && self.data == other.data
+    }
+}
+
+/// Returns the confidence vector of an image, as [`ClassificationOutput::confidence_for_image()`]
+/// does.
+///
+/// # Panics
+///
+/// Panics if the image is not present in the [`ClassificationOutput`] instance.
+///
+/// # Examples
+///
+/// ```rust
+/// use bagheera::classification::ClassificationOutput;
+///
+/// let mut cls_out = ClassificationOutput::<u8, f32>::new(2u8);
+/// cls_out.add("hello.jpg", vec![0.25f32, 0.75f32]).unwrap();
+/// assert_eq!(&cls_out["hello.jpg"], &[0.25f32, 0.75f32]);
+/// ```
+impl<
+        T1: num_traits::PrimInt + num_traits::Unsigned + num_traits::FromPrimitive,
+        T2: num_traits::Num + Copy + PartialOrd,
+        S: BuildHasher,
+    > Index<&str> for ClassificationOutput<T1, T2, S>
+{
+    type Output = [T2];
+
+    fn index(&self, imagename: &str) -> &Self::Output {
+        match self.data.get(imagename) {
+            Some(confidences) => confidences,
+            None => panic!(
+                "Image {} is not present in the ClassificationOutput instance.",
+                imagename
+            ),
+        }
+    }
+}
+
+impl<
+        T1: num_traits::PrimInt + num_traits::Unsigned + num_traits::FromPrimitive + fmt::Debug,
+        T2: num_traits::Num + Copy + PartialOrd + fmt::Debug,
+        S: BuildHasher,
+    > fmt::Debug for ClassificationOutput<T1, T2, S>
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut debug = f.debug_struct("ClassificationOutput");
+        debug
+            .field("num_classes", &self.num_classes)
+            .field("num_images", &self.data.len());
+        if self.data.len() <= DEBUG_MAX_IMAGES {
+            debug.field("data", &self.data);
+        }
+        debug.finish()
+    }
+}
+
+/// Generic struct representing an image classification dataset.
+///
+/// With the `serde` feature, instances can be serialized and deserialized. The groundtruth
+/// of each image is stored in one-hot notation.
+///
+/// The images are stored in a [`HashMap`] using the hasher built by `S`, which defaults to
+/// [`DefaultHashBuilder`]. Instances using another hasher are created with
+/// [`Self::with_hasher()`].
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(bound(
+        serialize = "T1: serde::Serialize",
+        deserialize = "T1: serde::Deserialize<'de>, S: Default"
+    ))
+)]
+pub struct ClassificationDataset<
+    T1: num_traits::PrimInt + num_traits::Unsigned + num_traits::FromPrimitive,
+    S: BuildHasher = DefaultHashBuilder,
+> {
+    num_classes: T1,
+    data: HashMap<String, Vec<bool>, S>,
+    is_multilabel: bool,
+    #[cfg_attr(feature = "serde", serde(default))]
+    case_insensitive_keys: bool,
+}
+
+impl<T1: num_traits::PrimInt + num_traits::Unsigned + num_traits::FromPrimitive>
+    ClassificationDataset<T1>
+{
+    /// Returns a new empty instance of [`Self<T1>`].
+    ///
+    /// Images can be added to the instance using the [`Self::add()`] function.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use bagheera::classification::ClassificationDataset;
+    ///
+    /// let cls_db = ClassificationDataset::new(30u8, false);
+    /// assert_eq!(cls_db.num_classes(), 30u8);
+    /// ```
+    pub fn new(num_classes: T1, is_multilabel: bool) -> Self {
+        ClassificationDataset {
+            num_classes,
+            data: HashMap::default(),
+            is_multilabel,
+            case_insensitive_keys: false,
+        }
+    }
+
+    /// Creates a new instance of [`Self`] holding the groundtruth of `entries`.
+    ///
+    /// Every entry is an image name with its labels, and is added with [`Self::add()`]. The
+    /// first entry which cannot be added, for instance because its image was already given,
+    /// makes the [io::Error] instance of [`Self::add()`] be returned.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use bagheera::classification::ClassificationDataset;
+    ///
+    /// let entries = vec![
+    ///     ("hello.jpg".to_string(), vec![0u8, 2u8]),
+    ///     ("world.jpg".to_string(), vec![1u8]),
+    /// ];
+    /// let cls_db = ClassificationDataset::from_entries(3u8, true, entries).unwrap();
+    /// assert_eq!(cls_db.num_images(), 2usize);
+    /// assert_eq!(cls_db.get_gt("hello.jpg").unwrap(), &vec![true, false, true]);
+    /// ```
+    pub fn from_entries(
+        num_classes: T1,
+        is_multilabel: bool,
+        entries: impl IntoIterator<Item = (String, Vec<T1>)>,
+    ) -> Result<Self, io::Error> {
+        let mut cls_db = Self::new(num_classes, is_multilabel);
+        for (imagename, category_labels) in entries {
+            cls_db.add(&imagename, &category_labels)?;
+        }
+        Ok(cls_db)
+    }
+
+    /// Creates a new instance of [`Self`] from a JSON-lines file.
+    ///
+    /// Every non-empty line of the file holds the groundtruth of one image as
+    /// `{"image": "hello.jpg", "labels": [1, 4]}`. The file is parsed one line at a time, so
+    /// only the current line is held in memory besides the instance itself. An [io::Error]
+    /// instance reporting the 1-based number of the offending line is returned if a line
+    /// cannot be parsed, if it has a label which is not less than `num_classes`, or if it
+    /// cannot be added using [`Self::add()`].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use bagheera::classification::ClassificationDataset;
+    ///
+    /// let filename = std::env::temp_dir().join("bagheera_doc_from_jsonl.jsonl");
+    /// std::fs::write(
+    ///     &filename,
+    ///     "{\"image\": \"hello.jpg\", \"labels\": [0, 2]}\n{\"image\": \"world.jpg\", \"labels\": []}\n",
+    /// )
+    /// .unwrap();
+    /// let cls_db = ClassificationDataset::<u8>::from_jsonl(filename.to_str().unwrap(), 3u8, true).unwrap();
+    /// assert_eq!(cls_db.num_images(), 2usize);
+    /// assert_eq!(cls_db.get_gt("hello.jpg").unwrap(), &vec![true, false, true]);
+    /// ```
+    #[cfg(feature = "serde")]
+    pub fn from_jsonl(
+        filename: &str,
+        num_classes: T1,
+        is_multilabel: bool,
+    ) -> Result<Self, io::Error>
+    where
+        T1: serde::de::DeserializeOwned,
+    {
+        let fid = utils::open_file(filename)?;
+        let mut cls_db = Self::new(num_classes, is_multilabel);
+        for (line_num, line) in BufReader::new(fid).lines().enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let record: JsonlRecord<T1> = serde_json::from_str(&line)
+                .map_err(|err| errors::invalid_line(filename, line_num + 1usize, &err))?;
+            cls_db
+                .add(&record.image, &record.labels)
+                .map_err(|err| errors::invalid_line(filename, line_num + 1usize, &err))?;
+        }
+        log::debug!("Read ClassificationDataset from {}.", filename);
+        Ok(cls_db)
+    }
+}
+
+/// Groundtruth of one image in a file read by [`ClassificationDataset::from_jsonl()`].
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct JsonlRecord<T1> {
+    image: String,
+    labels: Vec<T1>,
+}
+
+/// Delimiters of the CSV files read and written by the crate.
+///
+/// Fields are separated by [`Self::delimiter()`], which is a comma by default. The labels
+/// of an image may also be separated by [`Self::label_sep()`], which is a semicolon by
+/// default, so that `hello.jpg,1;4` and `hello.jpg,1,4` hold the same labels.
+///
+/// # Examples
+///
+/// ```rust
+/// use bagheera::classification::CsvOptions;
+///
+/// let options = CsvOptions::new(b'\t', '|').unwrap();
+/// assert_eq!(options.delimiter(), b'\t');
+/// assert_eq!(CsvOptions::default().label_sep(), ';');
+/// assert!(CsvOptions::new(b'|', '|').is_err());
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CsvOptions {
+    delimiter: u8,
+    label_sep: char,
+}
+
+impl CsvOptions {
+    /// Returns a new [`Self`] instance with the given delimiters.
+    ///
+    /// An [io::Error] instance is returned if `delimiter` is not an ASCII character or is
+    /// the same as `label_sep`.
+    pub fn new(delimiter: u8, label_sep: char) -> Result<Self, io::Error> {
+        if !delimiter.is_ascii() || delimiter as char == label_sep {
+            return Err(errors::invalid_csv_delimiters(delimiter, label_sep));
+        }
+        Ok(CsvOptions {
+            delimiter,
+            label_sep,
+        })
+    }
+
+    /// Returns the delimiter between the fields of a line.
+    #[inline(always)]
+    pub fn delimiter(&self) -> u8 {
+        self.delimiter
+    }
+
+    /// Returns the separator between the labels of an image.
+    #[inline(always)]
+    pub fn label_sep(&self) -> char {
+        self.label_sep
+    }
+}
+
+impl Default for CsvOptions {
+    fn default() -> Self {
+        CsvOptions {
+            delimiter: b',',
+            label_sep: ';',
+        }
+    }
+}
+
+/// Streaming reader of the groundtruth stored in a CSV file.
+///
+/// Every non-empty line of the file holds the groundtruth of one image as
+/// `hello.jpg,1,4`, i.e. the image name followed by its labels. The labels may also be
+/// separated by the label separator of the [`CsvOptions`], as in `hello.jpg,1;4`, and other
+/// delimiters are used with [`Self::with_options()`]. Lines are read and validated
+/// one at a time, so that files too large to be held in memory can be inserted into a
+/// [`ClassificationDataset`] or streamed into a [`crate::metrics::Evaluator`] record by
+/// record. A line is invalid if a label cannot be parsed or is not less than `num_classes`,
+/// or if an image of a single-label dataset does not have exactly one label. The first
+/// invalid line is reported as an [io::Error] instance giving its 1-based number, after
+/// which the iterator ends.
+///
+/// Duplicate image names are not detected by the reader. They are reported when the
+/// records are added to a [`ClassificationDataset`].
+///
+/// # Examples
+///
+/// ```rust
+/// use bagheera::classification::{ClassificationDataset, CsvDatasetReader};
+///
+/// let filename = std::env::temp_dir().join("bagheera_doc_csv_dataset_reader.csv");
+/// std::fs::write(&filename, "hello.jpg,0,2\nworld.jpg\n").unwrap();
+/// let reader = CsvDatasetReader::new(filename.to_str().unwrap(), 3u8, true).unwrap();
+/// let mut cls_db = ClassificationDataset::new(3u8, true);
+/// for record in reader {
+///     let (imagename, labels) = record.unwrap();
+///     cls_db.add(&imagename, &labels).unwrap();
+/// }
+/// assert_eq!(cls_db.num_images(), 2usize);
+/// assert_eq!(cls_db.get_gt("hello.jpg").unwrap(), &vec![true, false, true]);
+/// ```
+pub struct CsvDatasetReader<
+    T1: num_traits::PrimInt + num_traits::Unsigned + num_traits::FromPrimitive,
+> {
+    filename: String,
+    lines: std::io::Lines<BufReader<std::fs::File>>,
+    line_num: usize,
+    num_classes: T1,
+    is_multilabel: bool,
+    options: CsvOptions,
+    failed: bool,
+}
+
+impl<T1: num_traits::PrimInt + num_traits::Unsigned + num_traits::FromPrimitive>
+    CsvDatasetReader<T1>
+{
+    /// Returns a new [`Self`] instance reading `filename` with the default [`CsvOptions`].
+    ///
+    /// An [io::Error] instance is returned if the file cannot be opened.
+    pub fn new(filename: &str, num_classes: T1, is_multilabel: bool) -> Result<Self, io::Error> {
+        Self::with_options(filename, num_classes, is_multilabel, CsvOptions::default())
+    }
+
+    /// Returns a new [`Self`] instance reading `filename` with the delimiters of `options`.
+    ///
+    /// An [io::Error] instance is returned if the file cannot be opened.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use bagheera::classification::{CsvDatasetReader, CsvOptions};
+    ///
+    /// let filename = std::env::temp_dir().join("bagheera_doc_csv_dataset_reader_options.tsv");
+    /// std::fs::write(&filename, "hello.jpg\t0|2\n").unwrap();
+    /// let options = CsvOptions::new(b'\t', '|').unwrap();
+    /// let mut reader =
+    ///     CsvDatasetReader::with_options(filename.to_str().unwrap(), 3u8, true, options).unwrap();
+    /// assert_eq!(reader.next().unwrap().unwrap(), ("hello.jpg".to_string(), vec![0u8, 2u8]));
+    /// ```
+    pub fn with_options(
+        filename: &str,
+        num_classes: T1,
+        is_multilabel: bool,
+        options: CsvOptions,
+    ) -> Result<Self, io::Error> {
+        let fid = utils::open_file(filename)?;
+        Ok(CsvDatasetReader {
+            filename: filename.to_string(),
+            lines: BufReader::new(fid).lines(),
+            line_num: 0usize,
+            num_classes,
+            is_multilabel,
+            options,
+            failed: false,
+        })
+    }
+
+    /// Returns the 1-based number of the last line read.
+    #[inline(always)]
+    pub fn line_num(&self) -> usize {
+        self.line_num
+    }
+
+    /// Parses and validates the non-empty line `line`.
+    fn parse_line(&self, line: &str) -> Result<(String, Vec<T1>), io::Error> {
+        let delimiter = self.options.delimiter() as char;
+        let label_sep = self.options.label_sep();
+        let (imagename, rest) = line.split_once(delimiter).unwrap_or((line, ""));
+        let imagename = imagename.trim().to_string();
+        let mut labels = Vec::<T1>::new();
+        let tokens = if rest.trim().is_empty() {
+            None
+        } else {
+            Some(rest.split([delimiter, label_sep]))
+        };
+        for token in tokens.into_iter().flatten() {
+            let token = token.trim();
+            let label =
+                T1::from_str_radix(token, 10).map_err(|_| errors::unparsable_label(token))?;
+            if label >= self.num_classes {
+                return Err(errors::label_out_of_range(
+                    label.to_usize().unwrap(),
+                    self.num_classes.to_usize().unwrap(),
+                ));
+            }
+            labels.push(label);
+        }
+        if !self.is_multilabel && labels.len() != 1usize {
+            return Err(errors::single_label_count(&imagename, labels.len()));
+        }
+        Ok((imagename, labels))
+    }
+}
+
+impl<T1: num_traits::PrimInt + num_traits::Unsigned + num_traits::FromPrimitive> Iterator
+    for CsvDatasetReader<T1>
+{
+    type Item = Result<(String, Vec<T1>), io::Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+        loop {
+            let line = self.lines.next()?;
+            self.line_num += 1usize;
+            let record = line.and_then(|line| match line.trim().is_empty() {
+                true => Ok(None),
+                false => self.parse_line(line.trim()).map(Some),
+            });
+            match record {
+                Ok(None) => continue,
+                Ok(Some(record)) => return Some(Ok(record)),
+                Err(err) => {
+                    self.failed = true;
+                    return Some(Err(errors::invalid_line(
+                        &self.filename,
+                        self.line_num,
+                        &err,
+                    )));
+                }
+            }
+        }
+    }
+}
+
+impl<
+        T1: num_traits::PrimInt + num_traits::Unsigned + num_traits::FromPrimitive,
+        S: BuildHasher + Default,
+    > ClassificationDataset<T1, S>
+{
+    /// Returns a new empty instance of [`Self`] whose images are stored using a hasher
+    /// built by `hasher`.
+    ///
+    /// With a hasher which does not depend on a random seed, two instances holding the same
+    /// images list them in the same order.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::collections::hash_map::RandomState;
+    /// use bagheera::classification::ClassificationDataset;
+    ///
+    /// let cls_db = ClassificationDataset::with_hasher(30u8, false, RandomState::new());
+    /// assert_eq!(cls_db.num_classes(), 30u8);
+    /// ```
+    pub fn with_hasher(num_classes: T1, is_multilabel: bool, hasher: S) -> Self {
+        ClassificationDataset {
+            num_classes,
+            data: HashMap::with_hasher(hasher),
+            is_multilabel,
+            case_insensitive_keys: false,
+        }
+    }
+
+    /// Returns the [`Self`] instance with case-insensitive image names.
+    ///
+    /// The names of the images already present and of the images added later are
+    /// lowercased, and the lookups of [`Self::image_is_present()`] and [`Self::get_gt()`]
+    /// ignore the case of the name. Adding an image whose name only differs in case from
+    /// a name already present returns an [io::Error] instance. An [io::Error] instance is
+    /// also returned if two names already present only differ in case.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use bagheera::classification::ClassificationDataset;
+    ///
+    /// let mut cls_db = ClassificationDataset::new(3u8, false)
+    ///     .with_case_insensitive_keys()
+    ///     .unwrap();
+    /// cls_db.add("IMG.JPG", &vec![1u8]).unwrap();
+    /// assert!(cls_db.image_is_present("img.jpg"));
+    /// assert!(cls_db.add("img.jpg", &vec![2u8]).is_err());
+    /// ```
+    pub fn with_case_insensitive_keys(mut self) -> Result<Self, io::Error> {
+        utils::normalize_map_keys(&mut self.data, |imagename| imagename.to_lowercase())?;
+        self.case_insensitive_keys = true;
+        Ok(self)
+    }
+
+    /// Returns the key under which `imagename` is stored.
+    #[inline(always)]
+    fn key<'a>(&self, imagename: &'a str) -> Cow<'a, str> {
+        match self.case_insensitive_keys {
+            true => Cow::Owned(imagename.to_lowercase()),
+            false => Cow::Borrowed(imagename),
+        }
+    }
+
+    /// Adds a new GT to the [`Self`] instance.
+    ///
+    /// If `imagename` is already in [`Self`] instance, an [io::Error] instance is returned.
+    /// An [io::Error] instance is also returned if a single-label [`Self`] instance is given
+    /// no label or more than one label, or if a label is not less than the number of
+    /// classes. Nothing is added when an error is returned.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use bagheera::classification::ClassificationDataset;
+    ///
+    /// let mut cls_db = ClassificationDataset::new(5u16, false);
+    /// cls_db.add("hello.jpg", &vec![1u16]);
+    /// assert_eq!(cls_db.num_images(), 1usize);
+    /// assert!(cls_db.add("world.jpg", &vec![5u16]).is_err());
+    /// assert_eq!(cls_db.num_images(), 1usize);
+    /// ```
+    pub fn add(&mut self, imagename: &str, category_labels: &Vec<T1>) -> Result<(), io::Error> {
+        if self.image_is_present(imagename) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Image {} was already present.", imagename),
+            ));
+        }
+        self.check_labels(imagename, category_labels)?;
+        let key = self.key(imagename).into_owned();
+        self.data
+            .insert(key, category_labels.convert(self.num_classes()));
+        Ok(())
+    }
+
+    /// Adds the GTs of `entries` to the [`Self`] instance, all at once.
+    ///
+    /// Every entry is checked as in [`Self::add()`] before anything is added, and an image
+    /// repeated within `entries` is an error too. If an entry is invalid, the [io::Error]
+    /// instance returned names the first offending image and the [`Self`] instance is left
+    /// unchanged.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use bagheera::classification::ClassificationDataset;
+    ///
+    /// let mut cls_db = ClassificationDataset::new(3u8, false);
+    /// cls_db
+    ///     .add_batch(&[("hello.jpg", vec![0u8]), ("world.jpg", vec![2u8])])
+    ///     .unwrap();
+    /// assert_eq!(cls_db.num_images(), 2usize);
+    /// assert!(cls_db
+    ///     .add_batch(&[("moon.jpg", vec![1u8]), ("sun.jpg", vec![3u8])])
+    ///     .is_err());
+    /// assert_eq!(cls_db.num_images(), 2usize);
+    /// ```
+    pub fn add_batch(&mut self, entries: &[(&str, Vec<T1>)]) -> Result<(), io::Error> {
+        let mut keys = HashSet::with_capacity(entries.len());
+        for (imagename, category_labels) in entries.iter() {
+            let key = self.key(imagename);
+            if self.data.contains_key(key.as_ref()) {
+                return Err(errors::invalid_batch_entry(
+                    imagename,
+                    &errors::image_already_present(imagename),
+                ));
+            }
+            if !keys.insert(key) {
+                return Err(errors::invalid_batch_entry(
+                    imagename,
+                    &errors::duplicate_batch_image(imagename),
+                ));
+            }
+            self.check_labels(imagename, category_labels)
+                .map_err(|err| errors::invalid_batch_entry(imagename, &err))?;
+        }
+        self.data.reserve(entries.len());
+        for (imagename, category_labels) in entries.iter() {
+            let key = self.key(imagename).into_owned();
+            self.data
+                .insert(key, category_labels.convert(self.num_classes()));
+        }
+        Ok(())
+    }
+
+    /// Adds a new GT to the [`Self`] instance, replacing the GT of `imagename` if it is
+    /// already present.
+    ///
+    /// The previous labels of `imagename`, in increasing order, are returned if it was
+    /// present. The [io::Error] instances of [`Self::add()`] for the labels are returned
+    /// likewise, and the [`Self`] instance is left unchanged when an error is returned.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use bagheera::classification::ClassificationDataset;
+    ///
+    /// let mut cls_db = ClassificationDataset::new(5u16, false);
+    /// assert_eq!(cls_db.add_or_replace("hello.jpg", &vec![1u16]).unwrap(), None);
+    /// assert_eq!(cls_db.add_or_replace("hello.jpg", &vec![3u16]).unwrap(), Some(vec![1u16]));
+    /// assert_eq!(cls_db.get_gt("hello.jpg").unwrap()[3], true);
+    /// assert!(cls_db.add_or_replace("hello.jpg", &vec![5u16]).is_err());
+    /// ```
+    pub fn add_or_replace(
+        &mut self,
+        imagename: &str,
+        category_labels: &Vec<T1>,
+    ) -> Result<Option<Vec<T1>>, io::Error> {
+        self.check_labels(imagename, category_labels)?;
+        let key = self.key(imagename).into_owned();
+        Ok(self
+            .data
+            .insert(key, category_labels.convert(self.num_classes()))
+            .map(|one_hot| one_hot.from_one_hot()))
+    }
+
+    /// Returns an [io::Error] instance if a single-label [`Self`] instance is given no label
+    /// or more than one label for `imagename`, or if a label is not less than the number of
+    /// classes.
+    fn check_labels(&self, imagename: &str, category_labels: &[T1]) -> Result<(), io::Error> {
+        if !self.is_multilabel && category_labels.len() > 1 {
+            return Err(
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "Tried adding multi-label data to a ClassificationDataset instance that is not multilabel.",
+                )
+            );
+        }
+        if !self.is_multilabel && category_labels.is_empty() {
+            return Err(errors::single_label_count(imagename, 0usize));
+        }
+        if let Some(label) = category_labels
+            .iter()
+            .find(|label| **label >= self.num_classes)
+        {
+            return Err(errors::label_out_of_range(
+                label.to_usize().unwrap(),
+                self.num_classes_usize(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Returns the number of object classes in the [`Self`] instance.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use bagheera::classification::ClassificationDataset;
+    ///  let cls_db = ClassificationDataset::<u32>::new(20u32, false);
+    ///  assert_eq!(cls_db.num_classes(), 20u32);
+    /// ```
+    ///
+    /// ```rust
+    /// use bagheera::classification::ClassificationDataset;
+    ///  let cls_db = ClassificationDataset::<u128>::new(2000u128, true);
+    ///  assert_eq!(cls_db.num_classes(), 2000u128);
+    /// ```
+    #[inline(always)]
+    pub fn num_classes(&self) -> T1 {
+        self.num_classes
+    }
+
+    /// Returns the number of object classes in the [`Self`] instance as a `usize`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use bagheera::classification::ClassificationDataset;
+    ///  let cls_db = ClassificationDataset::<u8>::new(30u8, false);
+    ///  assert_eq!(cls_db.num_classes_usize(), 30usize);
+    /// ```
+    #[inline(always)]
+    pub fn num_classes_usize(&self) -> usize {
+        self.num_classes.to_usize().unwrap()
+    }
+
+    #[inline(always)]
+    pub fn num_images(&self) -> usize {
+        self.data.len()
+    }
+
+    #[inline(always)]
+    pub fn image_is_present(&self, imagename: &str) -> bool {
+        self.data.contains_key(self.key(imagename).as_ref())
+    }
+
+    #[inline(always)]
+    pub fn list_images(&self) -> Vec<&str> {
+        self.data.iter().map(|it| it.0.as_str()).collect()
+    }
+
+    /// Returns true if the [`Self`] instance represents a multi-label classification dataset.
+    /// Otherwise false is returned.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use bagheera::classification::ClassificationDataset;
+    ///
+    /// let cls_db = ClassificationDataset::new(30u16, true);
+    /// assert_eq!(cls_db.is_multilabel(), true);
+    /// ```
+    ///
+    /// ```rust
+    /// use bagheera::classification::ClassificationDataset;
+    ///
+    /// let cls_db = ClassificationDataset::new(1000u16, false);
+    /// assert_eq!(cls_db.is_multilabel(), false);
+    /// ```
+    #[inline(always)]
+    pub fn is_multilabel(&self) -> bool {
+        self.is_multilabel
+    }
+
+    /// Checks that the [`Self`] instance has the modality given by `other_is_multilabel`.
+    ///
+    /// This is used wherever a [`Self`] instance is combined with another dataset or with an
+    /// evaluation which expects a given modality. An [io::Error] instance is returned if the
+    /// modalities differ.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use bagheera::classification::ClassificationDataset;
+    ///
+    /// let cls_db = ClassificationDataset::new(3u8, true);
+    /// let other = ClassificationDataset::new(3u8, false);
+    /// assert!(cls_db.assert_same_modality(true).is_ok());
+    /// assert!(cls_db.assert_same_modality(other.is_multilabel()).is_err());
+    /// ```
+    pub fn assert_same_modality(&self, other_is_multilabel: bool) -> Result<(), io::Error> {
+        if self.is_multilabel != other_is_multilabel {
+            return Err(errors::modality_mismatch(
+                self.is_multilabel,
+                other_is_multilabel,
+            ));
+        }
+        Ok(())
+    }
+    /// Gets the groundtruth for `imagename` in [`Self`] instance.
+    ///
+    /// If `imagename` is not in the [`Self`] instance, an [io::Error]
+    /// instance is returned.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use bagheera::classification::ClassificationDataset;
+    ///
+    /// let mut cls_db = ClassificationDataset::new(5u16, false);
+    /// cls_db.add("hello.jpg", &vec![1u16]);
+    /// assert_eq!(cls_db.get_gt("hello.jpg").unwrap(), &vec![false, true, false, false, false]);
+    /// ```
+    ///
+    /// ```rust
+    /// use bagheera::classification::ClassificationDataset;
+    ///
+    /// let mut cls_db = ClassificationDataset::new(5u16, true);
+    /// cls_db.add("hello.jpg", &vec![1u16, 3u16]);
+    /// assert_eq!(cls_db.get_gt("hello.jpg").unwrap(), &vec![false, true, false, true, false]);
+    /// ```
+    #[inline]
+    pub fn get_gt(&self, imagename: &str) -> Result<&Vec<bool>, io::Error> {
+        if !self.image_is_present(imagename) {
+            Err(errors::image_not_present_error(imagename))
+        } else {
+            Ok(&self.data[self.key(imagename).as_ref()])
+        }
+    }
+
+    #[inline(always)]
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    /// Returns an [io::Error] instance naming the first image whose groundtruth is not
+    /// consistent with the number of classes and the modality of the [`Self`] instance.
+    ///
+    /// Instances built with [`Self::add()`] are always consistent, so this is only needed for
+    /// instances obtained from external data.
+    #[cfg(feature = "serde")]
+    fn validate(&self) -> Result<(), io::Error> {
+        for (imagename, one_hot) in &self.data {
+            if one_hot.len() != self.num_classes_usize() {
+                return Err(errors::groundtruth_length_mismatch(
+                    imagename,
+                    one_hot.len(),
+                    self.num_classes_usize(),
+                ));
+            }
+            let num_labels = one_hot.iter().filter(|x| **x).count();
+            if !self.is_multilabel && num_labels != 1usize {
+                return Err(errors::single_label_count(imagename, num_labels));
+            }
+        }
+        Ok(())
+    }
+
+    /// Writes the [`Self`] instance to `filename` as JSON.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use bagheera::classification::ClassificationDataset;
+    ///
+    /// let mut cls_db = ClassificationDataset::new(3u8, false);
+    /// cls_db.add("hello.jpg", &vec![2u8]).unwrap();
+    /// let filename = std::env::temp_dir().join("bagheera_doc_to_json_file.json");
+    /// cls_db.to_json_file(filename.to_str().unwrap()).unwrap();
+    /// let loaded = ClassificationDataset::<u8>::from_json_file(filename.to_str().unwrap()).unwrap();
+    /// assert_eq!(loaded, cls_db);
+    /// ```
+    #[cfg(feature = "serde")]
+    pub fn to_json_file(&self, filename: &str) -> Result<(), io::Error>
+    where
+        T1: serde::Serialize,
+    {
+        let fid = utils::create_file(filename)?;
+        serde_json::to_writer(io::BufWriter::new(fid), self)?;
+        log::debug!("Wrote ClassificationDataset to {}.", filename);
+        Ok(())
+    }
+
+    /// Creates a new instance of [`Self`] from a JSON file written by [`Self::to_json_file()`].
+    ///
+    /// An [io::Error] instance is returned if the file cannot be read or parsed, or if the
+    /// groundtruth of an image is not consistent with the number of classes and the modality
+    /// stored in the file. If the file marks the keys as case-insensitive, the image names
+    /// are lowercased as in [`Self::with_case_insensitive_keys()`], and an [io::Error]
+    /// instance is returned if two of them only differ in case.
+    #[cfg(feature = "serde")]
+    pub fn from_json_file(filename: &str) -> Result<Self, io::Error>
+    where
+        T1: serde::de::DeserializeOwned,
+    {
+        let fid = utils::open_file(filename)?;
+        let mut cls_db: Self = serde_json::from_reader(BufReader::new(fid))?;
+        if cls_db.case_insensitive_keys {
+            utils::normalize_map_keys(&mut cls_db.data, |imagename| imagename.to_lowercase())?;
+        }
+        cls_db.validate()?;
+        log::debug!("Read ClassificationDataset from {}.", filename);
+        Ok(cls_db)
+    }
+
+    /// Returns the number of images labelled with each class.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use bagheera::classification::ClassificationDataset;
+    ///
+    /// let mut cls_db = ClassificationDataset::new(3u8, true);
+    /// cls_db.add("hello.jpg", &vec![0u8, 2u8]).unwrap();
+    /// cls_db.add("world.jpg", &vec![2u8]).unwrap();
+    /// assert_eq!(cls_db.class_counts(), vec![1usize, 0usize, 2usize]);
+    /// ```
+    pub fn class_counts(&self) -> Vec<usize> {
+        let mut counts = vec![0usize; self.num_classes_usize()];
+        for one_hot in self.data.values() {
+            for (count, is_present) in counts.iter_mut().zip(one_hot.iter()) {
+                if *is_present {
+                    *count += 1;
+                }
+            }
+        }
+        counts
+    }
+
+    /// Returns the support of each class, which is the number of groundtruth images
+    /// labelled with it.
+    ///
+    /// This is the same as [`Self::class_counts()`], named after the support column of
+    /// [`crate::metrics::ClassificationReport`]. The support of a report equals that of its
+    /// groundtruth when every groundtruth image has a prediction.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use bagheera::classification::ClassificationDataset;
+    ///
+    /// let mut cls_db = ClassificationDataset::new(3u8, false);
+    /// cls_db.add("hello.jpg", &vec![2u8]).unwrap();
+    /// cls_db.add("world.jpg", &vec![2u8]).unwrap();
+    /// assert_eq!(cls_db.support(), vec![0usize, 0usize, 2usize]);
+    /// ```
+    #[inline(always)]
+    pub fn support(&self) -> Vec<usize> {
+        self.class_counts()
+    }
+
+    /// Returns the `n` classes labelling the most images, with their image counts, in
+    /// decreasing order of count.
+    ///
+    /// The counts are those of [`Self::class_counts()`], ranked with [`TopK::top_k()`] so
+    /// that ties are broken in favour of the smaller class index. If `n` is more than the
+    /// number of classes, every class is returned.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use bagheera::classification::ClassificationDataset;
+    ///
+    /// let mut cls_db = ClassificationDataset::new(3u8, true);
+    /// cls_db.add("hello.jpg", &vec![0u8, 2u8]).unwrap();
+    /// cls_db.add("world.jpg", &vec![2u8]).unwrap();
+    /// assert_eq!(cls_db.most_frequent_classes(2usize), vec![(2u8, 2usize), (0u8, 1usize)]);
+    /// ```
+    pub fn most_frequent_classes(&self, n: usize) -> Vec<(T1, usize)> {
+        let counts = self.class_counts();
+        counts
+            .top_k(n.min(counts.len()))
+            .unwrap()
+            .into_iter()
+            .map(|class| (T1::from_usize(class).unwrap(), counts[class]))
+            .collect()
+    }
+
+    /// Returns the number of images labelled with each class, counting the images in parallel.
+    ///
+    /// The result is identical to [`Self::class_counts()`], which should be preferred for small
+    /// datasets.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use bagheera::classification::ClassificationDataset;
+    ///
+    /// let mut cls_db = ClassificationDataset::new(3u8, false);
+    /// cls_db.add("hello.jpg", &vec![1u8]).unwrap();
+    /// cls_db.add("world.jpg", &vec![1u8]).unwrap();
+    /// assert_eq!(cls_db.par_class_counts(), cls_db.class_counts());
+    /// ```
+    #[cfg(feature = "rayon")]
+    pub fn par_class_counts(&self) -> Vec<usize>
+    where
+        T1: Send + Sync,
+    {
+        use rayon::prelude::*;
+
+        let num_classes = self.num_classes_usize();
+        self.data
+            .par_iter()
+            .fold(
+                || vec![0usize; num_classes],
+                |mut counts, (_, one_hot)| {
+                    for (count, is_present) in counts.iter_mut().zip(one_hot.iter()) {
+                        if *is_present {
+                            *count += 1;
+                        }
+                    }
+                    counts
+                },
+            )
+            .reduce(
+                || vec![0usize; num_classes],
+                |mut lhs, rhs| {
+                    for (count, other) in lhs.iter_mut().zip(rhs.iter()) {
+                        *count += *other;
+                    }
+                    lhs
+                },
+            )
+    }
+
+    /// Returns a class-balanced random sample of the images in a single-label [`Self`] instance.
+    ///
+    /// Up to `per_class` images are drawn without replacement from every class, using a
+    /// random number generator seeded with `seed`, so the same seed always gives the same
+    /// sample. All the images of a class with fewer than `per_class` images are returned.
+    /// The sample lists the images of class 0 first, then those of class 1 and so on. An
+    /// [io::Error] instance is returned if the [`Self`] instance is multi-label.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use bagheera::classification::ClassificationDataset;
+    ///
+    /// let mut cls_db = ClassificationDataset::new(2u8, false);
+    /// cls_db.add("a.jpg", &vec![0u8]).unwrap();
+    /// cls_db.add("b.jpg", &vec![0u8]).unwrap();
+    /// cls_db.add("c.jpg", &vec![1u8]).unwrap();
+    /// let sample = cls_db.sample_balanced(1usize, 42u64).unwrap();
+    /// assert_eq!(sample.len(), 2usize);
+    /// assert_eq!(sample[1], "c.jpg");
+    /// assert_eq!(sample, cls_db.sample_balanced(1usize, 42u64).unwrap());
+    /// ```
+    pub fn sample_balanced(&self, per_class: usize, seed: u64) -> Result<Vec<&str>, io::Error> {
+        self.assert_same_modality(false)
+            .map_err(|_| errors::requires_single_label("sample_balanced"))?;
+        let mut images_per_class = vec![Vec::<&str>::new(); self.num_classes_usize()];
+        for (imagename, one_hot) in &self.data {
+            if let Some(class) = one_hot.iter().position(|x| *x) {
+                images_per_class[class].push(imagename.as_str());
+            }
+        }
+
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut sample = Vec::<&str>::new();
+        for images in images_per_class.iter_mut() {
+            images.sort_unstable();
+            images.shuffle(&mut rng);
+            sample.extend(images.iter().take(per_class));
+        }
+        Ok(sample)
+    }
+
+    /// Returns the sorted names of the images which do not have any label.
+    ///
+    /// Images without labels can only be present in a multi-label [`Self`] instance and
+    /// usually point to an annotation bug.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use bagheera::classification::ClassificationDataset;
+    ///
+    /// let mut cls_db = ClassificationDataset::new(3u8, true);
+    /// cls_db.add("hello.jpg", &vec![0u8, 2u8]).unwrap();
+    /// cls_db.add("world.jpg", &vec![]).unwrap();
+    /// assert_eq!(cls_db.images_without_labels(), vec!["world.jpg"]);
+    /// ```
+    pub fn images_without_labels(&self) -> Vec<&str> {
+        let mut images = self
+            .data
+            .iter()
+            .filter(|(_, one_hot)| !one_hot.iter().any(|x| *x))
+            .map(|(imagename, _)| imagename.as_str())
+            .collect::<Vec<&str>>();
+        images.sort_unstable();
+        images
+    }
+
+    /// Returns the sorted names of the images labelled with `class_id`.
+    ///
+    /// An [io::Error] instance is returned if `class_id` is not less than the number of
+    /// classes.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use bagheera::classification::ClassificationDataset;
+    ///
+    /// let mut cls_db = ClassificationDataset::new(3u8, true);
+    /// cls_db.add("hello.jpg", &vec![0u8, 2u8]).unwrap();
+    /// cls_db.add("world.jpg", &vec![2u8]).unwrap();
+    /// assert_eq!(cls_db.images_for_class(2u8).unwrap(), vec!["hello.jpg", "world.jpg"]);
+    /// assert!(cls_db.images_for_class(1u8).unwrap().is_empty());
+    /// assert!(cls_db.images_for_class(3u8).is_err());
+    /// ```
+    pub fn images_for_class(&self, class_id: T1) -> Result<Vec<&str>, io::Error> {
+        if class_id >= self.num_classes {
+            return Err(errors::label_out_of_range(
+                class_id.to_usize().unwrap(),
+                self.num_classes_usize(),
+            ));
         }
-        if !self.is_multilabel && category_labels.len() > 1 {
-            return Err(
-                io::Error::new(
-                    io::ErrorKind::InvalidData,
-                    "Tried adding multi-label data to a ClassificationDataset instance that is not multilabel.",
-                )
-            );
+        let class = class_id.to_usize().unwrap();
+        let mut images = self
+            .data
+            .iter()
+            .filter(|(_, one_hot)| one_hot[class])
+            .map(|(imagename, _)| imagename.as_str())
+            .collect::<Vec<&str>>();
+        images.sort_unstable();
+        Ok(images)
+    }
+
+    /// Returns statistics on the number of labels of the images in the [`Self`] instance.
+    ///
+    /// The median is computed over the sorted per-image label counts, averaging the two
+    /// middle counts when there is an even number of images. Every statistic is zero for an
+    /// empty [`Self`] instance.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use bagheera::classification::ClassificationDataset;
+    ///
+    /// let mut cls_db = ClassificationDataset::new(3u8, true);
+    /// cls_db.add("hello.jpg", &vec![0u8, 2u8]).unwrap();
+    /// cls_db.add("world.jpg", &vec![]).unwrap();
+    /// cls_db.add("moon.jpg", &vec![0u8, 1u8, 2u8]).unwrap();
+    /// let stats = cls_db.cardinality_stats();
+    /// assert_eq!(stats.min(), 0usize);
+    /// assert_eq!(stats.max(), 3usize);
+    /// assert_eq!(stats.mean(), 5f64 / 3f64);
+    /// assert_eq!(stats.median(), 2f64);
+    /// ```
+    pub fn cardinality_stats(&self) -> CardinalityStats {
+        let mut counts = self
+            .data
+            .values()
+            .map(|one_hot| one_hot.iter().filter(|x| **x).count())
+            .collect::<Vec<usize>>();
+        if counts.is_empty() {
+            return CardinalityStats::default();
+        }
+        counts.sort_unstable();
+        let num_images = counts.len();
+        let median = match num_images % 2usize {
+            0 => (counts[num_images / 2usize - 1usize] + counts[num_images / 2usize]) as f64 / 2f64,
+            _ => counts[num_images / 2usize] as f64,
+        };
+        CardinalityStats {
+            min: counts[0],
+            max: counts[num_images - 1usize],
+            mean: counts.iter().sum::<usize>() as f64 / num_images as f64,
+            median,
         }
-        self.data.insert(
-            imagename.to_string(),
-            category_labels.convert(self.num_classes()),
-        );
-        Ok(())
     }
-    /// Returns the number of object classes in the [`Self`] instance.
+
+    /// Returns the label co-occurrence matrix of a multi-label [`Self`] instance.
+    ///
+    /// The returned matrix is symmetric with `num_classes` rows and columns. Entry `[i][j]`
+    /// is the number of images labelled with both classes `i` and `j`, so the diagonal holds
+    /// the number of images labelled with each class. An [io::Error] instance is returned
+    /// if the [`Self`] instance is not multi-label.
     ///
     /// # Examples
     ///
     /// ```rust
     /// use bagheera::classification::ClassificationDataset;
-    ///  let cls_db = ClassificationDataset::<u32>::new(20u32, false);
-    ///  assert_eq!(cls_db.num_classes(), 20u32);
+    ///
+    /// let mut cls_db = ClassificationDataset::new(3u8, true);
+    /// cls_db.add("hello.jpg", &vec![0u8, 2u8]).unwrap();
+    /// cls_db.add("world.jpg", &vec![2u8]).unwrap();
+    /// let cooccurrence = cls_db.label_cooccurrence().unwrap();
+    /// assert_eq!(cooccurrence[0], vec![1usize, 0usize, 1usize]);
+    /// assert_eq!(cooccurrence[2], vec![1usize, 0usize, 2usize]);
     /// ```
+    pub fn label_cooccurrence(&self) -> Result<Vec<Vec<usize>>, io::Error> {
+        if !self.is_multilabel {
+            return Err(errors::requires_multilabel("label_cooccurrence"));
+        }
+        let num_classes = self.num_classes_usize();
+        let mut cooccurrence = vec![vec![0usize; num_classes]; num_classes];
+        for one_hot in self.data.values() {
+            let labels = one_hot
+                .iter()
+                .enumerate()
+                .filter(|(_, x)| **x)
+                .map(|(class, _)| class)
+                .collect::<Vec<usize>>();
+            for i in &labels {
+                for j in &labels {
+                    cooccurrence[*i][*j] += 1;
+                }
+            }
+        }
+        Ok(cooccurrence)
+    }
+
+    /// Replaces the name of every image in the [`Self`] instance by `f(name)`.
+    ///
+    /// This makes a groundtruth match a classifier output which names images differently,
+    /// for instance using [`utils::basename`] to strip directories. With case-insensitive
+    /// image names, the normalized names are also lowercased. An [io::Error] instance is
+    /// returned if two names are mapped to the same one, in which case the [`Self`]
+    /// instance is left unchanged.
+    ///
+    /// # Examples
     ///
     /// ```rust
     /// use bagheera::classification::ClassificationDataset;
-    ///  let cls_db = ClassificationDataset::<u128>::new(2000u128, true);
-    ///  assert_eq!(cls_db.num_classes(), 2000u128);
+    ///
+    /// let mut cls_db = ClassificationDataset::new(2u8, false);
+    /// cls_db.add("Hello.jpg", &vec![1u8]).unwrap();
+    /// cls_db.add("hello.jpg", &vec![0u8]).unwrap();
+    /// assert!(cls_db.normalize_keys(|name| name.to_lowercase()).is_err());
+    /// assert!(cls_db.image_is_present("Hello.jpg"));
     /// ```
-    #[inline(always)]
-    pub fn num_classes(&self) -> T1 {
-        self.num_classes
+    pub fn normalize_keys(&mut self, f: impl Fn(&str) -> String) -> Result<(), io::Error> {
+        match self.case_insensitive_keys {
+            true => {
+                utils::normalize_map_keys(&mut self.data, |imagename| f(imagename).to_lowercase())
+            }
+            false => utils::normalize_map_keys(&mut self.data, f),
+        }
     }
 
-    #[inline(always)]
-    pub fn num_images(&self) -> usize {
-        self.data.len()
+    /// Returns the number of images for each distinct label combination in the [`Self`]
+    /// instance.
+    ///
+    /// Each key holds the labels of a combination in increasing order, so images without
+    /// labels are counted under an empty key. For a single-label [`Self`] instance this is
+    /// equivalent to the class counts.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use bagheera::classification::ClassificationDataset;
+    ///
+    /// let mut cls_db = ClassificationDataset::new(3u8, true);
+    /// cls_db.add("hello.jpg", &vec![2u8, 0u8]).unwrap();
+    /// cls_db.add("world.jpg", &vec![0u8, 2u8]).unwrap();
+    /// cls_db.add("foo.jpg", &vec![1u8]).unwrap();
+    /// let histogram = cls_db.label_set_histogram();
+    /// assert_eq!(histogram[&vec![0u8, 2u8]], 2usize);
+    /// assert_eq!(histogram[&vec![1u8]], 1usize);
+    /// ```
+    pub fn label_set_histogram(&self) -> HashMap<Vec<T1>, usize>
+    where
+        T1: std::hash::Hash,
+    {
+        let mut histogram = HashMap::<Vec<T1>, usize>::new();
+        for one_hot in self.data.values() {
+            *histogram.entry(one_hot.from_one_hot()).or_insert(0usize) += 1;
+        }
+        histogram
     }
 
-    #[inline(always)]
-    pub fn image_is_present(&self, imagename: &str) -> bool {
-        self.data.contains_key(imagename)
+    /// Returns the groundtruth of every image in one-hot notation, keyed by image name.
+    ///
+    /// This is the storage of the [`Self`] instance. Use [`Self::into_data()`] or
+    /// [`Self::iter()`] to get the labels as class IDs instead.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use bagheera::classification::ClassificationDataset;
+    ///
+    /// let mut cls_db = ClassificationDataset::new(3u8, false);
+    /// cls_db.add("hello.jpg", &vec![1u8]).unwrap();
+    /// assert_eq!(cls_db.one_hot_data()["hello.jpg"], vec![false, true, false]);
+    /// ```
+    pub fn one_hot_data(&self) -> &HashMap<String, Vec<bool>, S> {
+        &self.data
     }
 
-    #[inline(always)]
-    pub fn list_images(&self) -> Vec<&str> {
-        self.data.iter().map(|it| it.0.as_str()).collect()
+    /// Returns the image names sorted by name, along with the groundtruth as a matrix holding
+    /// one row per image in the same order.
+    ///
+    /// Row `i` is the one-hot vector of `names[i]`, as given by [`ToOneHot::convert()`], so
+    /// the rows align with external metadata sorted by image name.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use bagheera::classification::ClassificationDataset;
+    ///
+    /// let mut cls_db = ClassificationDataset::new(3u8, true);
+    /// cls_db.add("world.jpg", &vec![1u8]).unwrap();
+    /// cls_db.add("hello.jpg", &vec![0u8, 2u8]).unwrap();
+    /// let (names, matrix) = cls_db.to_one_hot_matrix();
+    /// assert_eq!(names, vec!["hello.jpg", "world.jpg"]);
+    /// assert_eq!(matrix, vec![vec![true, false, true], vec![false, true, false]]);
+    /// ```
+    pub fn to_one_hot_matrix(&self) -> (Vec<String>, Vec<Vec<bool>>) {
+        let mut entries = self.data.iter().collect::<Vec<(&String, &Vec<bool>)>>();
+        entries.sort_unstable_by(|a, b| a.0.cmp(b.0));
+        entries
+            .into_iter()
+            .map(|(imagename, one_hot)| (imagename.clone(), one_hot.clone()))
+            .unzip()
     }
 
-    /// Returns true if the [`Self`] instance represents a multi-label classification dataset.
-    /// Otherwise false is returned.
+    /// Consumes the [`Self`] instance and returns the labels of every image in increasing
+    /// order, keyed by image name.
     ///
     /// # Examples
     ///
     /// ```rust
     /// use bagheera::classification::ClassificationDataset;
     ///
-    /// let cls_db = ClassificationDataset::new(30u16, true);
-    /// assert_eq!(cls_db.is_multilabel(), true);
+    /// let mut cls_db = ClassificationDataset::new(3u8, true);
+    /// cls_db.add("hello.jpg", &vec![2u8, 0u8]).unwrap();
+    /// let data = cls_db.into_data();
+    /// assert_eq!(data["hello.jpg"], vec![0u8, 2u8]);
     /// ```
+    pub fn into_data(self) -> HashMap<String, Vec<T1>> {
+        self.data
+            .into_iter()
+            .map(|(imagename, one_hot)| (imagename, one_hot.from_one_hot()))
+            .collect()
+    }
+
+    /// Returns the images added, removed and relabelled in `other` compared to the
+    /// [`Self`] instance.
+    ///
+    /// This is meant for reviewing a new version of the annotations. An image is changed
+    /// when its set of labels differs between the two datasets. Every list of the returned
+    /// [`DatasetDiff`] is sorted by name.
+    ///
+    /// # Examples
     ///
     /// ```rust
     /// use bagheera::classification::ClassificationDataset;
     ///
-    /// let cls_db = ClassificationDataset::new(1000u16, false);
-    /// assert_eq!(cls_db.is_multilabel(), false);
+    /// let mut cls_db = ClassificationDataset::new(3u8, false);
+    /// cls_db.add("hello.jpg", &vec![1u8]).unwrap();
+    /// cls_db.add("world.jpg", &vec![2u8]).unwrap();
+    /// let mut relabelled = ClassificationDataset::new(3u8, false);
+    /// relabelled.add("hello.jpg", &vec![0u8]).unwrap();
+    /// relabelled.add("moon.jpg", &vec![2u8]).unwrap();
+    /// let diff = cls_db.diff(&relabelled);
+    /// assert_eq!(diff.added(), &["moon.jpg".to_string()]);
+    /// assert_eq!(diff.removed(), &["world.jpg".to_string()]);
+    /// assert_eq!(diff.changed(), &["hello.jpg".to_string()]);
     /// ```
-    #[inline(always)]
-    pub fn is_multilabel(&self) -> bool {
-        self.is_multilabel
+    pub fn diff<S2: BuildHasher + Default>(
+        &self,
+        other: &ClassificationDataset<T1, S2>,
+    ) -> DatasetDiff {
+        let mut diff = DatasetDiff::default();
+        for (imagename, one_hot) in self.data.iter() {
+            match other.get_gt(imagename) {
+                Ok(other_one_hot) => {
+                    if one_hot.from_one_hot::<T1>() != other_one_hot.from_one_hot::<T1>() {
+                        diff.changed.push(imagename.clone());
+                    }
+                }
+                Err(_) => diff.removed.push(imagename.clone()),
+            }
+        }
+        for imagename in other.data.keys() {
+            if !self.image_is_present(imagename) {
+                diff.added.push(imagename.clone());
+            }
+        }
+        diff.added.sort_unstable();
+        diff.removed.sort_unstable();
+        diff.changed.sort_unstable();
+        diff
     }
-    /// Gets the groundtruth for `imagename` in [`Self`] instance.
+
+    /// Returns an iterator over the labels of every image, each in increasing order.
     ///
-    /// If `imagename` is not in the [`Self`] instance, an [io::Error]
-    /// instance is returned.
+    /// The groundtruth is stored in one-hot notation, so the labels of an image are decoded
+    /// into a new vector as the iterator advances. The images are visited in arbitrary
+    /// order.
     ///
     /// # Examples
     ///
     /// ```rust
     /// use bagheera::classification::ClassificationDataset;
     ///
-    /// let mut cls_db = ClassificationDataset::new(5u16, false);
-    /// cls_db.add("hello.jpg", &vec![1u16]);
-    /// assert_eq!(cls_db.get_gt("hello.jpg").unwrap(), &vec![false, true, false, false, false]);
+    /// let mut cls_db = ClassificationDataset::new(3u8, true);
+    /// cls_db.add("hello.jpg", &vec![2u8, 0u8]).unwrap();
+    /// cls_db.add("world.jpg", &vec![1u8]).unwrap();
+    /// let num_labels = cls_db.label_sets().map(|labels| labels.len()).sum::<usize>();
+    /// assert_eq!(num_labels, 3usize);
+    /// ```
+    pub fn label_sets(&self) -> impl Iterator<Item = Vec<T1>> + '_ {
+        self.data.values().map(|one_hot| one_hot.from_one_hot())
+    }
+
+    /// Returns an iterator over the images of the [`Self`] instance and their labels, each in
+    /// increasing order.
+    ///
+    /// The iterator is also obtained by iterating over a reference to the [`Self`] instance.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use bagheera::classification::ClassificationDataset;
+    ///
+    /// let mut cls_db = ClassificationDataset::new(3u8, true);
+    /// cls_db.add("hello.jpg", &vec![2u8, 0u8]).unwrap();
+    /// cls_db.add("world.jpg", &vec![1u8]).unwrap();
+    /// let labelled = cls_db
+    ///     .iter()
+    ///     .filter(|(_, labels)| labels.contains(&0u8))
+    ///     .collect::<Vec<_>>();
+    /// assert_eq!(labelled, vec![("hello.jpg", vec![0u8, 2u8])]);
+    /// for (imagename, labels) in &cls_db {
+    ///     assert!(cls_db.image_is_present(imagename) && !labels.is_empty());
+    /// }
     /// ```
+    pub fn iter(&self) -> DatasetIter<'_, T1> {
+        DatasetIter {
+            inner: self.data.iter(),
+            marker: std::marker::PhantomData,
+        }
+    }
+
+    /// Retains only the images for which `f` returns true, given the image name and its
+    /// labels in increasing order.
+    ///
+    /// This mirrors [`HashMap::retain`] and prunes the [`Self`] instance in place.
+    ///
+    /// # Examples
     ///
     /// ```rust
     /// use bagheera::classification::ClassificationDataset;
     ///
-    /// let mut cls_db = ClassificationDataset::new(5u16, true);
-    /// cls_db.add("hello.jpg", &vec![1u16, 3u16]);
-    /// assert_eq!(cls_db.get_gt("hello.jpg").unwrap(), &vec![false, true, false, true, false]);
+    /// let mut cls_db = ClassificationDataset::new(3u8, true);
+    /// cls_db.add("hello.jpg", &vec![0u8, 2u8]).unwrap();
+    /// cls_db.add("world.jpg", &vec![1u8]).unwrap();
+    /// cls_db.retain(|_, labels| !labels.contains(&2u8));
+    /// assert_eq!(cls_db.list_images(), vec!["world.jpg"]);
     /// ```
-    #[inline]
-    pub fn get_gt(&self, imagename: &str) -> Result<&Vec<bool>, io::Error> {
-        if !self.image_is_present(imagename) {
-            Err(errors::image_not_present_error(imagename))
-        } else {
-            Ok(&self.data[imagename])
+    pub fn retain<F: FnMut(&str, &[T1]) -> bool>(&mut self, mut f: F) {
+        self.data
+            .retain(|imagename, one_hot| f(imagename, &one_hot.from_one_hot::<T1>()));
+    }
+
+    /// Returns a new [`Self`] instance with every class `i` replaced by `mapping[i]`.
+    ///
+    /// This merges fine-grained classes into coarser ones. The new instance has
+    /// `new_num_classes` classes and the same modality as the [`Self`] instance. Labels which
+    /// are mapped to the same class are merged into one label. An [io::Error] instance is
+    /// returned if `mapping` does not have one entry per class or if any of its entries is
+    /// not less than `new_num_classes`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use bagheera::classification::ClassificationDataset;
+    ///
+    /// let mut cls_db = ClassificationDataset::new(3u8, false);
+    /// cls_db.add("hello.jpg", &vec![1u8]).unwrap();
+    /// cls_db.add("world.jpg", &vec![2u8]).unwrap();
+    /// let coarse = cls_db.remap_classes(&[0u8, 1u8, 1u8], 2u8).unwrap();
+    /// assert_eq!(coarse.num_classes(), 2u8);
+    /// assert_eq!(coarse.get_gt("world.jpg").unwrap(), &vec![false, true]);
+    /// ```
+    pub fn remap_classes(
+        &self,
+        mapping: &[T1],
+        new_num_classes: T1,
+    ) -> Result<ClassificationDataset<T1, S>, io::Error> {
+        let num_classes = self.num_classes_usize();
+        if mapping.len() != num_classes {
+            return Err(errors::class_mapping_length_mismatch(
+                mapping.len(),
+                num_classes,
+            ));
+        }
+        let new_num_classes_usize = new_num_classes.to_usize().unwrap();
+        let mapping = mapping
+            .iter()
+            .map(|target| target.to_usize().unwrap())
+            .collect::<Vec<usize>>();
+        if let Some(target) = mapping.iter().find(|x| **x >= new_num_classes_usize) {
+            return Err(errors::label_out_of_range(*target, new_num_classes_usize));
         }
+
+        let mut data = HashMap::with_capacity_and_hasher(self.num_images(), S::default());
+        for (imagename, one_hot) in self.data.iter() {
+            let mut remapped = vec![false; new_num_classes_usize];
+            for (class, _) in one_hot.iter().enumerate().filter(|(_, x)| **x) {
+                remapped[mapping[class]] = true;
+            }
+            data.insert(imagename.clone(), remapped);
+        }
+        Ok(ClassificationDataset {
+            num_classes: new_num_classes,
+            data,
+            is_multilabel: self.is_multilabel,
+            case_insensitive_keys: self.case_insensitive_keys,
+        })
     }
+}
 
-    #[inline(always)]
+/// Statistics on the number of labels per image of a [`ClassificationDataset`].
+///
+/// Instances are returned by [`ClassificationDataset::cardinality_stats()`]. With the
+/// `serde` feature, they can be serialized.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct CardinalityStats {
+    min: usize,
+    max: usize,
+    mean: f64,
+    median: f64,
+}
+
+impl CardinalityStats {
+    /// Returns the smallest number of labels of an image.
+    pub fn min(&self) -> usize {
+        self.min
+    }
+
+    /// Returns the largest number of labels of an image.
+    pub fn max(&self) -> usize {
+        self.max
+    }
+
+    /// Returns the mean number of labels per image.
+    pub fn mean(&self) -> f64 {
+        self.mean
+    }
+
+    /// Returns the median number of labels per image.
+    pub fn median(&self) -> f64 {
+        self.median
+    }
+}
+
+/// Differences between two versions of a [`ClassificationDataset`].
+///
+/// Instances are returned by [`ClassificationDataset::diff()`]. With the `serde` feature,
+/// they can be serialized.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct DatasetDiff {
+    added: Vec<String>,
+    removed: Vec<String>,
+    changed: Vec<String>,
+}
+
+impl DatasetDiff {
+    /// Returns the images only present in the new version, sorted by name.
+    pub fn added(&self) -> &[String] {
+        &self.added
+    }
+
+    /// Returns the images only present in the old version, sorted by name.
+    pub fn removed(&self) -> &[String] {
+        &self.removed
+    }
+
+    /// Returns the images present in both versions with different labels, sorted by name.
+    pub fn changed(&self) -> &[String] {
+        &self.changed
+    }
+
+    /// Returns true if both versions hold the same images with the same labels.
     pub fn is_empty(&self) -> bool {
-        self.data.is_empty()
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+/// Iterator over the images of a [`ClassificationDataset`] and their labels.
+///
+/// Instances are returned by [`ClassificationDataset::iter()`]. The groundtruth is stored in
+/// one-hot notation, so the labels of an image are decoded into a new vector, in increasing
+/// order, as the iterator advances. The images are visited in arbitrary order.
+pub struct DatasetIter<
+    'a,
+    T1: num_traits::PrimInt + num_traits::Unsigned + num_traits::FromPrimitive,
+> {
+    inner: std::collections::hash_map::Iter<'a, String, Vec<bool>>,
+    marker: std::marker::PhantomData<T1>,
+}
+
+impl<'a, T1: num_traits::PrimInt + num_traits::Unsigned + num_traits::FromPrimitive> Iterator
+    for DatasetIter<'a, T1>
+{
+    type Item = (&'a str, Vec<T1>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner
+            .next()
+            .map(|(imagename, one_hot)| (imagename.as_str(), one_hot.from_one_hot()))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<'a, T1: num_traits::PrimInt + num_traits::Unsigned + num_traits::FromPrimitive>
+    ExactSizeIterator for DatasetIter<'a, T1>
+{
+}
+
+impl<
+        'a,
+        T1: num_traits::PrimInt + num_traits::Unsigned + num_traits::FromPrimitive,
+        S: BuildHasher + Default,
+    > IntoIterator for &'a ClassificationDataset<T1, S>
+{
+    type Item = (&'a str, Vec<T1>);
+    type IntoIter = DatasetIter<'a, T1>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// Generic builder for [`ClassificationDataset`] instances.
+///
+/// The number of classes must be set before calling [`Self::build()`]. By default, the
+/// dataset is single-label and no capacity is reserved.
+///
+/// # Examples
+///
+/// ```rust
+/// use bagheera::classification::ClassificationDatasetBuilder;
+///
+/// let cls_db = ClassificationDatasetBuilder::new()
+///     .num_classes(10u8)
+///     .multilabel(true)
+///     .capacity(1000usize)
+///     .build()
+///     .unwrap();
+/// assert_eq!(cls_db.num_classes(), 10u8);
+/// assert!(cls_db.is_multilabel());
+/// ```
+pub struct ClassificationDatasetBuilder<
+    T1: num_traits::PrimInt + num_traits::Unsigned + num_traits::FromPrimitive,
+> {
+    num_classes: Option<T1>,
+    is_multilabel: bool,
+    capacity: usize,
+}
+
+impl<T1: num_traits::PrimInt + num_traits::Unsigned + num_traits::FromPrimitive>
+    ClassificationDatasetBuilder<T1>
+{
+    /// Creates a new instance of [`Self`] with the default configuration.
+    pub fn new() -> Self {
+        ClassificationDatasetBuilder {
+            num_classes: None,
+            is_multilabel: false,
+            capacity: 0usize,
+        }
+    }
+
+    /// Sets the number of classes of the dataset.
+    pub fn num_classes(mut self, num_classes: T1) -> Self {
+        self.num_classes = Some(num_classes);
+        self
+    }
+
+    /// Sets whether the dataset is multi-label.
+    pub fn multilabel(mut self, is_multilabel: bool) -> Self {
+        self.is_multilabel = is_multilabel;
+        self
+    }
+
+    /// Sets the number of images for which memory is reserved up front.
+    pub fn capacity(mut self, capacity: usize) -> Self {
+        self.capacity = capacity;
+        self
+    }
+
+    /// Returns a new empty [`ClassificationDataset`] with the configured options.
+    ///
+    /// An [io::Error] instance is returned if the number of classes was not set.
+    pub fn build(self) -> Result<ClassificationDataset<T1>, io::Error> {
+        let num_classes = self
+            .num_classes
+            .ok_or_else(|| errors::missing_num_classes("ClassificationDataset"))?;
+        Ok(ClassificationDataset {
+            num_classes,
+            data: HashMap::with_capacity_and_hasher(self.capacity, DefaultHashBuilder::default()),
+            is_multilabel: self.is_multilabel,
+            case_insensitive_keys: false,
+        })
+    }
+}
+
+impl<T1: num_traits::PrimInt + num_traits::Unsigned + num_traits::FromPrimitive> Default
+    for ClassificationDatasetBuilder<T1>
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Two [`ClassificationDataset`] instances are equal if they have the same number of classes,
+/// the same modality, the same groundtruth for every image and if both or neither ignore the
+/// case of image names, since the lookups of the two would differ otherwise.
+///
+/// Neither the order in which the images were added nor the order of the labels of an
+/// image matter, since the labels are stored in one-hot notation.
+///
+/// # Examples
+///
+/// ```rust
+/// use bagheera::classification::ClassificationDataset;
+///
+/// let mut lhs = ClassificationDataset::new(5u8, true);
+/// lhs.add("hello.jpg", &vec![1u8, 3u8]).unwrap();
+/// lhs.add("world.jpg", &vec![0u8]).unwrap();
+///
+/// let mut rhs = ClassificationDataset::new(5u8, true);
+/// rhs.add("world.jpg", &vec![0u8]).unwrap();
+/// rhs.add("hello.jpg", &vec![3u8, 1u8]).unwrap();
+/// assert_eq!(lhs, rhs);
+/// ```
+impl<
+        T1: num_traits::PrimInt + num_traits::Unsigned + num_traits::FromPrimitive,
+        S: BuildHasher,
+    > PartialEq for ClassificationDataset<T1, S>
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.num_classes == other.num_classes
+            && self.is_multilabel == other.is_multilabel
+            && self.case_insensitive_keys == other.case_insensitive_keys
+            && self.data == other.data
+    }
+}
+
+impl<
+        T1: num_traits::PrimInt + num_traits::Unsigned + num_traits::FromPrimitive,
+        S: BuildHasher,
+    > Eq for ClassificationDataset<T1, S>
+{
+}
+
+impl<
+        T1: num_traits::PrimInt + num_traits::Unsigned + num_traits::FromPrimitive + fmt::Debug,
+        S: BuildHasher,
+    > fmt::Debug for ClassificationDataset<T1, S>
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut debug = f.debug_struct("ClassificationDataset");
+        debug
+            .field("num_classes", &self.num_classes)
+            .field("is_multilabel", &self.is_multilabel)
+            .field("num_images", &self.data.len());
+        if self.data.len() <= DEBUG_MAX_IMAGES {
+            debug.field("data", &self.data);
+        }
+        debug.finish()
     }
 }
 
@@ -515,7 +3395,8 @@ pub trait EvaluationOptions {
 }
 
 macro_rules! create_with_field_names {
-    (pub struct $name:ident { $($fname:ident : $ftype:ty),* }) => {
+    ($(#[$meta:meta])* pub struct $name:ident { $($fname:ident : $ftype:ty),* }) => {
+         $(#[$meta])*
          pub struct $name {
             $($fname : $ftype),*
         }
@@ -530,6 +3411,7 @@ macro_rules! create_with_field_names {
 }
 
 create_with_field_names! {
+    #[allow(dead_code)]
     pub struct SingleLabelEvaluationOptions{
         k_values : Vec<usize>,
         per_class_analysis : bool
@@ -537,6 +3419,7 @@ create_with_field_names! {
 }
 
 create_with_field_names! {
+    #[allow(dead_code)]
     pub struct MultipleLabelEvaluationOptions{
         per_class_analysis : bool
     }
@@ -560,6 +3443,7 @@ impl EvaluationOptions for SingleLabelEvaluationOptions {
 }
 
 impl SingleLabelEvaluationOptions {
+    #[allow(dead_code, clippy::ptr_arg, clippy::wrong_self_convention)]
     fn new(&self, k_values: &Vec<usize>, per_class_analysis: bool) -> Self {
         SingleLabelEvaluationOptions {
             k_values: k_values.to_vec(),
@@ -585,12 +3469,14 @@ impl EvaluationOptions for MultipleLabelEvaluationOptions {
 }
 
 impl MultipleLabelEvaluationOptions {
+    #[allow(dead_code)]
     fn new(per_class_analysis: bool) -> Self {
         MultipleLabelEvaluationOptions { per_class_analysis }
     }
 }
 
 /// Generic struct abstracting the data available to a judge for classification evaluation.
+#[allow(dead_code)]
 pub struct ClassificationJudge<
     'a,
     T1: num_traits::PrimInt + num_traits::Unsigned + num_traits::FromPrimitive,
@@ -602,6 +3488,7 @@ pub struct ClassificationJudge<
     evaluation_options: T3,
 }
 
+#[allow(dead_code, clippy::upper_case_acronyms)]
 enum DIFFERENCE {
     POSITIVE,
     NEGATIVE,
@@ -630,6 +3517,7 @@ impl<
         }
     }
 
+    #[allow(dead_code)]
     #[inline(always)]
     fn get_difference(&self, imagename: &str) -> Vec<DIFFERENCE>
     where
@@ -646,6 +3534,7 @@ impl<
             .collect::<Vec<DIFFERENCE>>()
     }
 
+    #[allow(clippy::ptr_arg)]
     pub fn threshold_confidence(&self, conf: &Vec<T2>, threshold: T2) -> Vec<T2> {
         conf.iter()
             .map(|x| match *x > threshold {
@@ -656,6 +3545,7 @@ impl<
     }
 }
 
+#[allow(dead_code)]
 pub struct ClassificationResult<
     T2: num_traits::Float + fast_float::FastFloat + num_traits::FromPrimitive,
 > {
@@ -0,0 +1,206 @@
+//! Ensembling of image classification outputs
+//!
+//! Provides functions for combining the outputs of several classifiers
+//! evaluated on the same set of images.
+
+use std::collections::HashMap;
+use std::io;
+
+use crate::classification::ClassificationOutput;
+use crate::errors;
+use crate::utils::TopK;
+
+/// Returns an [io::Error] instance unless `outputs` is non-empty and every output has the
+/// same number of classes and the same set of images as the first one.
+fn check_outputs<
+    T1: num_traits::PrimInt + num_traits::Unsigned + num_traits::FromPrimitive,
+    T2: num_traits::Num + Copy + PartialOrd,
+>(
+    outputs: &[&ClassificationOutput<T1, T2>],
+) -> Result<(), io::Error> {
+    let first = outputs.first().ok_or_else(errors::empty_ensemble)?;
+    for (index, output) in outputs.iter().enumerate().skip(1) {
+        if output.num_classes() != first.num_classes() {
+            return Err(errors::ensemble_num_classes_mismatch(
+                index,
+                first.num_classes_usize(),
+                output.num_classes_usize(),
+            ));
+        }
+        let mut missing = first
+            .list_images()
+            .into_iter()
+            .filter(|imagename| !output.image_is_present(imagename))
+            .collect::<Vec<&str>>();
+        let mut extra = output
+            .list_images()
+            .into_iter()
+            .filter(|imagename| !first.image_is_present(imagename))
+            .collect::<Vec<&str>>();
+        if !missing.is_empty() || !extra.is_empty() {
+            missing.sort_unstable();
+            extra.sort_unstable();
+            return Err(errors::ensemble_image_set_mismatch(index, &missing, &extra));
+        }
+    }
+    Ok(())
+}
+
+/// Returns a new [`ClassificationOutput`] whose confidences are the per-image, per-class
+/// mean of the confidences in `outputs`.
+///
+/// All the outputs must have the same number of classes and the same set of images.
+/// Otherwise, or if `outputs` is empty, an [io::Error] instance describing the
+/// discrepancy is returned.
+///
+/// # Examples
+///
+/// ```rust
+/// use bagheera::classification::ClassificationOutput;
+/// use bagheera::ensemble::ensemble_mean;
+///
+/// let mut first = ClassificationOutput::<u8, f64>::new(2u8);
+/// first.add("india.jpg", vec![0.25f64, 0.75f64]).unwrap();
+/// let mut second = ClassificationOutput::<u8, f64>::new(2u8);
+/// second.add("india.jpg", vec![0.75f64, 0.75f64]).unwrap();
+///
+/// let ensemble = ensemble_mean(&[&first, &second]).unwrap();
+/// assert_eq!(ensemble.confidence_for_image("india.jpg").unwrap(), &vec![0.5f64, 0.75f64]);
+/// ```
+pub fn ensemble_mean<
+    T1: num_traits::PrimInt + num_traits::Unsigned + num_traits::FromPrimitive,
+    T2: num_traits::Float + fast_float::FastFloat + num_traits::FromPrimitive,
+>(
+    outputs: &[&ClassificationOutput<T1, T2>],
+) -> Result<ClassificationOutput<T1, T2>, io::Error> {
+    check_outputs(outputs)?;
+    let weight = T2::one() / T2::from_usize(outputs.len()).unwrap();
+    combine(outputs, &vec![weight; outputs.len()])
+}
+
+/// Returns a new [`ClassificationOutput`] whose confidences are the per-image, per-class
+/// weighted mean of the confidences in `outputs`.
+///
+/// `weights[i]` is the weight of `outputs[i]`. The weights are normalized to sum to one,
+/// so only their ratios matter. They must be non-negative with a positive sum. The
+/// outputs are validated as in [`ensemble_mean`]. An [io::Error] instance is returned if
+/// any of these conditions does not hold or if `weights.len() != outputs.len()`.
+///
+/// # Examples
+///
+/// ```rust
+/// use bagheera::classification::ClassificationOutput;
+/// use bagheera::ensemble::ensemble_weighted;
+///
+/// let mut first = ClassificationOutput::<u8, f64>::new(2u8);
+/// first.add("india.jpg", vec![0.25f64, 0.75f64]).unwrap();
+/// let mut second = ClassificationOutput::<u8, f64>::new(2u8);
+/// second.add("india.jpg", vec![1f64, 0f64]).unwrap();
+///
+/// let ensemble = ensemble_weighted(&[&first, &second], &[3f64, 1f64]).unwrap();
+/// assert_eq!(
+///     ensemble.confidence_for_image("india.jpg").unwrap(),
+///     &vec![0.4375f64, 0.5625f64]
+/// );
+/// ```
+pub fn ensemble_weighted<
+    T1: num_traits::PrimInt + num_traits::Unsigned + num_traits::FromPrimitive,
+    T2: num_traits::Float + fast_float::FastFloat + num_traits::FromPrimitive,
+>(
+    outputs: &[&ClassificationOutput<T1, T2>],
+    weights: &[T2],
+) -> Result<ClassificationOutput<T1, T2>, io::Error> {
+    check_outputs(outputs)?;
+    if weights.len() != outputs.len() {
+        return Err(errors::ensemble_weights_mismatch(
+            outputs.len(),
+            weights.len(),
+        ));
+    }
+    let total = weights.iter().fold(T2::zero(), |acc, x| acc + *x);
+    if weights.iter().any(|x| x.is_nan() || *x < T2::zero()) || total <= T2::zero() {
+        return Err(errors::ensemble_invalid_weights());
+    }
+    combine(
+        outputs,
+        &weights.iter().map(|x| *x / total).collect::<Vec<T2>>(),
+    )
+}
+
+/// Returns a new [`ClassificationOutput`] whose confidences are the per-image, per-class
+/// sum of the confidences in `outputs` scaled by `weights`.
+///
+/// The outputs are expected to have been validated with [`check_outputs`].
+fn combine<
+    T1: num_traits::PrimInt + num_traits::Unsigned + num_traits::FromPrimitive,
+    T2: num_traits::Float + fast_float::FastFloat + num_traits::FromPrimitive,
+>(
+    outputs: &[&ClassificationOutput<T1, T2>],
+    weights: &[T2],
+) -> Result<ClassificationOutput<T1, T2>, io::Error> {
+    let num_classes = outputs[0].num_classes();
+    let mut ensemble = ClassificationOutput::<T1, T2>::new(num_classes);
+    for imagename in outputs[0].list_images() {
+        let mut combined = vec![T2::zero(); outputs[0].num_classes_usize()];
+        for (output, weight) in outputs.iter().zip(weights.iter()) {
+            for (lhs, rhs) in combined
+                .iter_mut()
+                .zip(output.confidence_for_image(imagename)?.iter())
+            {
+                *lhs = *lhs + *rhs * *weight;
+            }
+        }
+        ensemble.add(imagename, combined)?;
+    }
+    Ok(ensemble)
+}
+
+/// Returns the per-image majority vote of the top-1 predictions in `outputs`.
+///
+/// Every output votes for the class with its highest confidence and each image is
+/// assigned the class with the most votes. Ties are broken in favour of the smallest
+/// class index. The outputs are validated as in [`ensemble_mean`] and an [io::Error]
+/// instance is returned if the validation fails.
+///
+/// # Examples
+///
+/// ```rust
+/// use bagheera::classification::ClassificationOutput;
+/// use bagheera::ensemble::ensemble_vote;
+///
+/// let mut first = ClassificationOutput::<u8, f32>::new(3u8);
+/// first.add("india.jpg", vec![0.1f32, 0.7f32, 0.2f32]).unwrap();
+/// let mut second = ClassificationOutput::<u8, f32>::new(3u8);
+/// second.add("india.jpg", vec![0.1f32, 0.2f32, 0.7f32]).unwrap();
+///
+/// let votes = ensemble_vote(&[&first, &second]).unwrap();
+/// assert_eq!(votes["india.jpg"], 1usize);
+/// ```
+pub fn ensemble_vote<
+    T1: num_traits::PrimInt + num_traits::Unsigned + num_traits::FromPrimitive,
+    T2: num_traits::Num + Copy + PartialOrd,
+>(
+    outputs: &[&ClassificationOutput<T1, T2>],
+) -> Result<HashMap<String, usize>, io::Error>
+where
+    Vec<T2>: TopK,
+{
+    check_outputs(outputs)?;
+    let num_classes = outputs[0].num_classes_usize();
+
+    let mut labels = HashMap::<String, usize>::with_capacity(outputs[0].num_images());
+    for imagename in outputs[0].list_images() {
+        let mut votes = vec![0usize; num_classes];
+        for output in outputs {
+            votes[output.topk_for_image(imagename, 1usize)?[0]] += 1;
+        }
+        let mut majority = 0usize;
+        for (class, count) in votes.iter().enumerate() {
+            if *count > votes[majority] {
+                majority = class;
+            }
+        }
+        labels.insert(imagename.to_string(), majority);
+    }
+    Ok(labels)
+}
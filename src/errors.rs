@@ -46,6 +46,28 @@ pub fn topk_incorrect_k(k: usize, v_length: usize) -> io::Error {
     )
 }
 
+/// Returns an `io::Error` instance with a custom string when the rank K requested from a
+/// vector is not between 1 and the length of the vector.
+pub fn kth_incorrect_k(k: usize, v_length: usize) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidInput,
+        format!(
+            "The K-th largest value of a vector v requires 1 <= K <= v.len().\
+    Here K = {} and v.len() = {}.",
+            k, v_length
+        ),
+    )
+}
+
+/// Returns an `io::Error` instance with a custom string when an image is added while it is
+/// already present.
+pub fn image_already_present(image_name: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidInput,
+        format!("Image {} was already present.", image_name),
+    )
+}
+
 /// Returns an `io::Error` instance with a custom string for a file not found.
 pub fn file_not_found(filename: &str) -> io::Error {
     io::Error::new(
@@ -53,3 +75,519 @@ pub fn file_not_found(filename: &str) -> io::Error {
         format!("The file {} was not found.", filename),
     )
 }
+
+/// Returns an `io::Error` instance with a custom string when an operation which is only
+/// defined for single-label classification is called with a multi-label dataset.
+pub fn requires_single_label(operation: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidInput,
+        format!(
+            "The operation {} requires a single-label dataset.",
+            operation
+        ),
+    )
+}
+
+/// Returns an `io::Error` instance with a custom string when the groundtruth and the
+/// classifier output have different number of classes.
+///
+/// When the counts differ by one, the message points at a background class, which is the
+/// usual cause of such an off-by-one.
+pub fn num_classes_mismatch(gt_num_classes: usize, pred_num_classes: usize) -> io::Error {
+    let hint = match gt_num_classes.max(pred_num_classes) - gt_num_classes.min(pred_num_classes) {
+        1 => " Check whether one of them has an extra background class.",
+        _ => "",
+    };
+    io::Error::new(
+        io::ErrorKind::InvalidInput,
+        format!(
+            "The groundtruth has {} classes while the classifier output has {} classes.{}",
+            gt_num_classes, pred_num_classes, hint
+        ),
+    )
+}
+
+/// Returns an `io::Error` instance with a custom string when an image in the groundtruth
+/// does not have any label.
+pub fn image_without_label(image_name: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("The image {} does not have any label.", image_name),
+    )
+}
+
+/// Returns an `io::Error` instance with a custom string when a class label is more than
+/// or equal to the number of classes.
+pub fn label_out_of_range(label: usize, num_classes: usize) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidInput,
+        format!(
+            "The label {} is out of range for {} classes.",
+            label, num_classes
+        ),
+    )
+}
+
+/// Returns an `io::Error` instance with a custom string when an ensemble is requested over
+/// an empty list of classifier outputs.
+pub fn empty_ensemble() -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidInput,
+        "An ensemble requires at least one classifier output.",
+    )
+}
+
+/// Returns an `io::Error` instance with a custom string when the classifier output at
+/// `output_index` in an ensemble differs from the first one in number of classes.
+pub fn ensemble_num_classes_mismatch(
+    output_index: usize,
+    expected: usize,
+    found: usize,
+) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidInput,
+        format!(
+            "Output {} of the ensemble has {} classes while output 0 has {} classes.",
+            output_index, found, expected
+        ),
+    )
+}
+
+/// Returns an `io::Error` instance with a custom string when the classifier output at
+/// `output_index` in an ensemble does not contain the same images as the first one.
+pub fn ensemble_image_set_mismatch(
+    output_index: usize,
+    missing: &[&str],
+    extra: &[&str],
+) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidInput,
+        format!(
+            "Output {} of the ensemble is missing the images {:?} and has the extra images {:?} \
+    compared to output 0.",
+            output_index, missing, extra
+        ),
+    )
+}
+
+/// Returns an `io::Error` instance with a custom string when the number of weights of a
+/// weighted ensemble differs from the number of classifier outputs.
+pub fn ensemble_weights_mismatch(num_outputs: usize, num_weights: usize) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidInput,
+        format!(
+            "The ensemble has {} outputs but {} weights were provided.",
+            num_outputs, num_weights
+        ),
+    )
+}
+
+/// Returns an `io::Error` instance with a custom string when the weights of a weighted
+/// ensemble contain a negative value or do not have a positive sum.
+pub fn ensemble_invalid_weights() -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidInput,
+        "The weights of an ensemble must be non-negative with a positive sum.",
+    )
+}
+
+/// Returns an `io::Error` instance with a custom string when an operation which is only
+/// defined for multi-label classification is called with a single-label dataset.
+pub fn requires_multilabel(operation: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidInput,
+        format!(
+            "The operation {} requires a multi-label dataset.",
+            operation
+        ),
+    )
+}
+
+/// Returns an `io::Error` instance with a custom string when a metric is computed over a
+/// groundtruth and a classifier output which do not have any image in common.
+pub fn no_common_images() -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidInput,
+        "The groundtruth and the classifier output do not have any image in common.",
+    )
+}
+
+/// Returns an `io::Error` instance with a custom string when a class mapping does not have
+/// one entry per class.
+pub fn class_mapping_length_mismatch(mapping_len: usize, num_classes: usize) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidInput,
+        format!(
+            "The class mapping has {} entries while there are {} classes.",
+            mapping_len, num_classes
+        ),
+    )
+}
+
+/// Returns an `io::Error` instance with a custom string when a single-label instance is
+/// combined with a multi-label one.
+pub fn modality_mismatch(is_multilabel: bool, other_is_multilabel: bool) -> io::Error {
+    let modality = |multilabel: bool| match multilabel {
+        true => "multi-label",
+        false => "single-label",
+    };
+    io::Error::new(
+        io::ErrorKind::InvalidInput,
+        format!(
+            "A {} dataset cannot be combined with a {} one.",
+            modality(is_multilabel),
+            modality(other_is_multilabel)
+        ),
+    )
+}
+
+/// Returns an `io::Error` instance with a custom string when a bootstrap estimate is
+/// requested with no resample or with a significance level outside of (0, 1).
+pub fn invalid_bootstrap_parameters(n_bootstrap: usize, alpha: f64) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidInput,
+        format!(
+            "Bootstrap requires at least one resample and a significance level in (0, 1), got {} resamples and alpha = {}.",
+            n_bootstrap, alpha
+        ),
+    )
+}
+
+/// Returns an `io::Error` instance with a custom string when an image does not have a
+/// sample weight.
+pub fn missing_sample_weight(image_name: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidInput,
+        format!("The image {} does not have a sample weight.", image_name),
+    )
+}
+
+/// Returns an `io::Error` instance with a custom string when sample weights contain a
+/// negative value or do not have a positive sum.
+pub fn invalid_sample_weights() -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidInput,
+        "Sample weights must be non-negative with a positive sum.",
+    )
+}
+
+/// Returns an `io::Error` instance with a custom string when normalizing the image names
+/// maps two different names to the same key.
+pub fn normalized_key_collision(first: &str, second: &str, key: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidInput,
+        format!(
+            "The images {} and {} are both normalized to {}.",
+            first, second, key
+        ),
+    )
+}
+
+/// Returns an `io::Error` instance with a custom string when a one-hot vector does not have
+/// one entry per class.
+pub fn one_hot_length_mismatch(length: usize, num_classes: usize) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!(
+            "The one-hot vector has {} entries while there are {} classes.",
+            length, num_classes
+        ),
+    )
+}
+
+/// Returns an `io::Error` instance with a custom string when per-class thresholds do not
+/// have one entry per class.
+pub fn thresholds_length_mismatch(num_thresholds: usize, num_classes: usize) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidInput,
+        format!(
+            "{} thresholds were given while there are {} classes.",
+            num_thresholds, num_classes
+        ),
+    )
+}
+
+/// Returns an `io::Error` instance with a custom string when a threshold search is given no
+/// candidate threshold.
+pub fn no_threshold_candidates() -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidInput,
+        "At least one candidate threshold is required.",
+    )
+}
+
+/// Returns an `io::Error` instance with a custom string when the cutoff K of a ranking
+/// metric is not between 1 and the number of classes.
+pub fn cutoff_incorrect_k(k: usize, num_classes: usize) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidInput,
+        format!(
+            "A ranking metric at cutoff K requires 1 <= K <= {}. Here K = {}.",
+            num_classes, k
+        ),
+    )
+}
+
+/// Returns an `io::Error` instance with a custom string when a metric only defined over
+/// labelled images is computed over images which all lack labels.
+pub fn no_labelled_images() -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        "None of the images in common has a label in the groundtruth.",
+    )
+}
+
+/// Returns an `io::Error` instance with a custom string when a binary file is truncated or
+/// does not hold what it is expected to.
+pub fn invalid_binary_file(filename: &str, reason: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("The binary file {} is invalid: {}.", filename, reason),
+    )
+}
+
+/// Returns an `io::Error` instance with a custom string when line `line_num` of a text file
+/// cannot be read, giving the underlying `reason`.
+pub fn invalid_line(filename: &str, line_num: usize, reason: &dyn std::fmt::Display) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("Line {} of {} is invalid: {}", line_num, filename, reason),
+    )
+}
+
+/// Returns an `io::Error` instance with a custom string when the entry of `image_name` in a
+/// batch is invalid, giving the underlying `reason` and keeping its kind.
+pub fn invalid_batch_entry(image_name: &str, reason: &io::Error) -> io::Error {
+    io::Error::new(
+        reason.kind(),
+        format!(
+            "The batch entry of image {} is invalid: {}",
+            image_name, reason
+        ),
+    )
+}
+
+/// Returns an `io::Error` instance with a custom string when `image_name` appears more than once
+/// in a batch.
+pub fn duplicate_batch_image(image_name: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidInput,
+        format!(
+            "The image {} appears more than once in the batch.",
+            image_name
+        ),
+    )
+}
+
+/// Returns an `io::Error` instance with a custom string when a curve is requested with no
+/// point.
+pub fn no_curve_steps() -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidInput,
+        "A curve requires at least one step.",
+    )
+}
+
+/// Returns an `io::Error` instance with a custom string when the confidence vector of an
+/// image does not have one entry per class.
+pub fn confidence_length_mismatch(
+    image_name: &str,
+    length: usize,
+    num_classes: usize,
+) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!(
+            "The image {} has {} confidences while there are {} classes.",
+            image_name, length, num_classes
+        ),
+    )
+}
+
+/// Returns an `io::Error` instance with a custom string when the confidence vector of an
+/// image holds a NaN or infinite value.
+pub fn non_finite_confidence(image_name: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("The image {} has a NaN or infinite confidence.", image_name),
+    )
+}
+
+/// Returns an `io::Error` instance with a custom string when a number of classes does not fit
+/// in the class index type.
+pub fn num_classes_overflow(num_classes: usize) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidInput,
+        format!(
+            "{} classes do not fit in the class index type.",
+            num_classes
+        ),
+    )
+}
+
+/// Returns an `io::Error` instance with a custom string when a class is listed more than
+/// once.
+pub fn duplicate_class(class: usize) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidInput,
+        format!("The class {} is listed more than once.", class),
+    )
+}
+
+/// Returns an `io::Error` instance with a custom string when the groundtruth of an image
+/// does not have one entry per class.
+pub fn groundtruth_length_mismatch(
+    image_name: &str,
+    length: usize,
+    num_classes: usize,
+) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!(
+            "The groundtruth of image {} has {} entries while there are {} classes.",
+            image_name, length, num_classes
+        ),
+    )
+}
+
+/// Returns an `io::Error` instance with a custom string when a label cannot be parsed.
+pub fn unparsable_label(token: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("{:?} is not a valid class label.", token),
+    )
+}
+
+/// Returns an `io::Error` instance with a custom string when a builder is built before its
+/// number of classes is set.
+pub fn missing_num_classes(built_type: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidInput,
+        format!(
+            "The number of classes must be set to build a {}.",
+            built_type
+        ),
+    )
+}
+
+/// Returns an `io::Error` instance with a custom string when a matrix of confidences does
+/// not have one column per class.
+pub fn matrix_columns_mismatch(num_columns: usize, num_classes: usize) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidInput,
+        format!(
+            "The matrix has {} columns when num_classes is specified as {}.",
+            num_columns, num_classes
+        ),
+    )
+}
+
+/// Returns an `io::Error` instance with a custom string when a matrix of confidences does
+/// not have one row per image name.
+pub fn matrix_rows_mismatch(num_rows: usize, num_names: usize) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidInput,
+        format!(
+            "The matrix has {} rows but {} image names were provided.",
+            num_rows, num_names
+        ),
+    )
+}
+
+/// Returns an `io::Error` instance with a custom string when a confidence cannot be parsed.
+pub fn unparsable_confidence(token: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("{:?} is not a valid confidence.", token),
+    )
+}
+
+/// Returns an `io::Error` instance with a custom string when an image of a single-label
+/// dataset does not have exactly one label.
+pub fn single_label_count(image_name: &str, count: usize) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!(
+            "Image {} has {} labels while the dataset is single-label.",
+            image_name, count
+        ),
+    )
+}
+
+/// Returns an `io::Error` instance with a custom string when an image evaluated one at a
+/// time in a single-label evaluation does not have exactly one label.
+pub fn evaluator_label_count(count: usize) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!(
+            "Single-label evaluation expects exactly one label per image, got {}.",
+            count
+        ),
+    )
+}
+
+/// Returns an `io::Error` instance with a custom string when two classifier outputs which
+/// are merged differ in number of classes.
+pub fn merge_num_classes_mismatch(num_classes: usize, other_num_classes: usize) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidInput,
+        format!(
+            "Cannot merge a classifier output with {} classes into one with {} classes.",
+            other_num_classes, num_classes
+        ),
+    )
+}
+
+/// Returns an `io::Error` instance with a custom string when two classifier outputs which
+/// are merged have images in common.
+pub fn merge_duplicate_images(duplicates: &[&str]) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidInput,
+        format!(
+            "The merged classifier outputs both contain the images {:?}. Check whether the shards overlap.",
+            duplicates
+        ),
+    )
+}
+
+/// Returns an `io::Error` instance with a custom string when two classifier outputs which
+/// are compared differ in number of classes.
+pub fn outputs_num_classes_mismatch(num_classes: usize, other_num_classes: usize) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidInput,
+        format!(
+            "The first classifier output has {} classes while the second one has {} classes.",
+            num_classes, other_num_classes
+        ),
+    )
+}
+
+/// Returns an `io::Error` instance with a custom string when a histogram is requested with
+/// no bin.
+pub fn no_histogram_bins() -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidInput,
+        "A histogram requires at least one bin.",
+    )
+}
+
+/// Returns an `io::Error` instance with a custom string when confidences are clipped to a
+/// range whose lower bound is more than its upper bound.
+pub fn invalid_clip_range() -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidInput,
+        "The lower bound of the clipping range is more than its upper bound.",
+    )
+}
+
+/// Returns an `io::Error` instance with a custom string when the CSV field delimiter is not
+/// an ASCII character or is the same as the label separator.
+pub fn invalid_csv_delimiters(delimiter: u8, label_sep: char) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidInput,
+        format!(
+            "The CSV delimiter {:?} must be an ASCII character different from the label separator {:?}.",
+            delimiter as char, label_sep
+        ),
+    )
+}
@@ -0,0 +1,177 @@
+//! Image classification output of a fixed number of classes
+//!
+//! Provides [`FixedClassOutput`], which stores the confidences of a classifier whose number
+//! of classes is known at compile time without building a `HashMap`. The module only needs
+//! `alloc`, so it is available without the `std` feature on embedded targets.
+
+#[cfg(not(feature = "std"))]
+use alloc::{
+    string::{String, ToString},
+    vec::Vec,
+};
+
+use crate::utils;
+use crate::utils::{Error, RankKey};
+
+/// Generic struct to store the image classification output of a classifier whose number of
+/// classes `N` is known at compile time.
+///
+/// Unlike `ClassificationOutput`, the confidences of every image are stored inline as a
+/// `[T2; N]` array in a single [`Vec`], next to a [`Vec`] of image names, so adding an image
+/// does not allocate a confidence vector and no `HashMap` is built. The images are kept
+/// sorted by name, so looking an image up is a binary search. Adding an image shifts the
+/// images whose names come after it, which costs nothing when the images are added in name
+/// order. With the `std` feature, `into_classification_output()` converts an instance for
+/// use with the metrics.
+///
+/// # Examples
+///
+/// ```rust
+/// use bagheera::fixed::FixedClassOutput;
+///
+/// let mut cls_out = FixedClassOutput::<3, f32>::new();
+/// cls_out.add("hello.jpg", [0.2f32, 0.7f32, 0.1f32]).unwrap();
+/// assert_eq!(cls_out.argmax("hello.jpg").unwrap(), 1usize);
+/// assert_eq!(cls_out.top_k("hello.jpg", 2usize).unwrap(), vec![1usize, 0usize]);
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct FixedClassOutput<const N: usize, T2: num_traits::Num + Copy + PartialOrd> {
+    names: Vec<String>,
+    confidences: Vec<[T2; N]>,
+}
+
+impl<const N: usize, T2: num_traits::Num + Copy + PartialOrd> FixedClassOutput<N, T2> {
+    /// Creates a new empty instance of [`Self`].
+    pub fn new() -> Self {
+        FixedClassOutput {
+            names: Vec::new(),
+            confidences: Vec::new(),
+        }
+    }
+
+    /// Creates a new empty instance of [`Self`] with room for `num_images` images.
+    pub fn with_capacity(num_images: usize) -> Self {
+        FixedClassOutput {
+            names: Vec::with_capacity(num_images),
+            confidences: Vec::with_capacity(num_images),
+        }
+    }
+
+    /// Adds the confidences of `imagename` to the [`Self`] instance.
+    ///
+    /// An [`Error`] instance is returned if `imagename` is already present.
+    pub fn add(&mut self, imagename: &str, confidences: [T2; N]) -> Result<(), Error> {
+        match self.position(imagename) {
+            Ok(_) => Err(utils::image_already_present(imagename)),
+            Err(index) => {
+                self.names.insert(index, imagename.to_string());
+                self.confidences.insert(index, confidences);
+                Ok(())
+            }
+        }
+    }
+
+    /// Returns the index of `imagename` in the sorted names, or the index at which it would
+    /// be inserted.
+    #[inline(always)]
+    fn position(&self, imagename: &str) -> Result<usize, usize> {
+        self.names
+            .binary_search_by(|name| name.as_str().cmp(imagename))
+    }
+
+    /// Returns the number of object classes, `N`.
+    #[inline(always)]
+    pub fn num_classes(&self) -> usize {
+        N
+    }
+
+    /// Returns the number of images in the [`Self`] instance.
+    #[inline(always)]
+    pub fn num_images(&self) -> usize {
+        self.names.len()
+    }
+
+    /// Returns true if the [`Self`] instance does not hold any image.
+    #[inline(always)]
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+
+    /// Returns true if `imagename` is present in the [`Self`] instance. False otherwise.
+    pub fn image_is_present(&self, imagename: &str) -> bool {
+        self.position(imagename).is_ok()
+    }
+
+    /// Returns an iterator over the name and the confidences of every image, sorted by
+    /// name.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &[T2; N])> + '_ {
+        self.names
+            .iter()
+            .map(|name| name.as_str())
+            .zip(self.confidences.iter())
+    }
+
+    /// Returns the confidences of `imagename`.
+    ///
+    /// An [`Error`] instance is returned if `imagename` is not present.
+    pub fn confidence_for_image(&self, imagename: &str) -> Result<&[T2; N], Error> {
+        self.position(imagename)
+            .map(|index| &self.confidences[index])
+            .map_err(|_| utils::image_not_present(imagename))
+    }
+
+    /// Returns the Top-K class indices of `imagename`, sorted by descending confidence and
+    /// equal confidences by increasing index as in [`utils::TopK::top_k()`].
+    ///
+    /// The classes are ranked in place on the stack, only the returned vector is allocated.
+    /// An [`Error`] instance is returned if `imagename` is not present or if `k` is more
+    /// than `N`. Panics if a floating point confidence is NaN.
+    pub fn top_k(&self, imagename: &str, k: usize) -> Result<Vec<usize>, Error>
+    where
+        T2: RankKey,
+    {
+        let confidences = self.confidence_for_image(imagename)?;
+        if k > N {
+            return Err(utils::topk_incorrect_k(k, N));
+        }
+        let mut classes = [0usize; N];
+        for (index, class) in classes.iter_mut().enumerate() {
+            *class = index;
+        }
+        classes.sort_unstable_by(|lhs, rhs| {
+            confidences[*rhs]
+                .rank_key()
+                .cmp(&confidences[*lhs].rank_key())
+                .then_with(|| lhs.cmp(rhs))
+        });
+        Ok(classes[..k].to_vec())
+    }
+
+    /// Returns the class with the highest confidence for `imagename`, the smallest index
+    /// winning ties.
+    ///
+    /// An [`Error`] instance is returned if `imagename` is not present or if `N` is zero.
+    /// Panics if a floating point confidence is NaN.
+    pub fn argmax(&self, imagename: &str) -> Result<usize, Error>
+    where
+        T2: RankKey,
+    {
+        let confidences = self.confidence_for_image(imagename)?;
+        let mut best = match N {
+            0 => return Err(utils::topk_incorrect_k(1usize, N)),
+            _ => 0usize,
+        };
+        for class in 1..N {
+            if confidences[class].rank_key() > confidences[best].rank_key() {
+                best = class;
+            }
+        }
+        Ok(best)
+    }
+}
+
+impl<const N: usize, T2: num_traits::Num + Copy + PartialOrd> Default for FixedClassOutput<N, T2> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
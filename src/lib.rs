@@ -1,14 +1,91 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+#[cfg(feature = "std")]
 pub mod classification;
+#[cfg(feature = "std")]
+pub mod ensemble;
+#[cfg(feature = "std")]
 pub mod errors;
+pub mod fixed;
+#[cfg(feature = "std")]
+pub mod metrics;
 pub mod utils;
 
-#[cfg(test)]
+#[cfg(all(test, not(feature = "std")))]
+mod no_std_tests {
+    use alloc::vec;
+    use alloc::vec::Vec;
+
+    use crate::fixed::FixedClassOutput;
+    use crate::utils::{Error, FromOneHot, ToOneHot, TopK};
+
+    #[test]
+    fn utils_topk_without_std() {
+        let v = vec![0.25f32, 0.75, 0.5, 0.75];
+        assert_eq!(v.top_k(3).unwrap(), vec![1, 3, 2]);
+        assert_eq!(
+            v.top_k(5).err().unwrap(),
+            Error::TopKIncorrectK { k: 5, len: 4 }
+        );
+    }
+
+    #[test]
+    fn utils_one_hot_without_std() {
+        let one_hot = vec![2u8, 0].convert(4u8);
+        assert_eq!(one_hot, vec![true, false, true, false]);
+        assert_eq!(one_hot.from_one_hot_checked(4u8).unwrap(), vec![0u8, 2]);
+        assert_eq!(
+            one_hot.from_one_hot_checked(3u8).err().unwrap(),
+            Error::OneHotLengthMismatch {
+                length: 4,
+                num_classes: 3
+            }
+        );
+    }
+
+    #[test]
+    fn fixed_class_output_without_std() {
+        let mut cls_out = FixedClassOutput::<3, f32>::new();
+        cls_out.add("b.jpg", [0.2f32, 0.7, 0.1]).unwrap();
+        cls_out.add("a.jpg", [0.5f32, 0.25, 0.25]).unwrap();
+        assert_eq!(
+            cls_out.add("a.jpg", [0f32; 3]).err().unwrap(),
+            Error::ImageAlreadyPresent
+        );
+        assert_eq!(cls_out.top_k("b.jpg", 2).unwrap(), vec![1, 0]);
+        assert_eq!(cls_out.argmax("a.jpg").unwrap(), 0);
+        assert_eq!(
+            cls_out.confidence_for_image("c.jpg").err().unwrap(),
+            Error::ImageNotPresent
+        );
+        assert_eq!(
+            cls_out.iter().map(|(name, _)| name).collect::<Vec<&str>>(),
+            vec!["a.jpg", "b.jpg"]
+        );
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+// The original tests predate the clippy gate and are kept as they were written.
+#[allow(
+    clippy::bool_assert_comparison,
+    clippy::explicit_auto_deref,
+    clippy::single_component_path_imports,
+    clippy::useless_vec,
+    unused_variables
+)]
 mod tests {
     use float_cmp::approx_eq;
     use rand;
     use rand::Rng;
 
-    use crate::classification::{ClassificationDataset, ClassificationOutput};
+    use crate::classification::{
+        ClassificationDataset, ClassificationDatasetBuilder, ClassificationOutput,
+    };
+    use crate::ensemble;
+    use crate::metrics;
 
     #[test]
     fn it_works() {
@@ -193,4 +270,3537 @@ mod tests {
         let cls_db = ClassificationDataset::<u128>::new(2000u128, true);
         assert_eq!(cls_db.num_classes(), 2000u128);
     }
+
+    #[test]
+    fn metrics_misclassified_single_wrong_image() {
+        let mut cls_db = ClassificationDataset::new(4u8, false);
+        let mut cls_out = ClassificationOutput::<u8, f32>::new(4u8);
+        let images = ["india.jpg", "germany.png", "iran.jpg", "canada.png"];
+        for (index, img) in images.iter().enumerate() {
+            cls_db.add(img, &vec![index as u8]).unwrap();
+            let mut v = vec![0.1f32; 4];
+            v[index] = 0.7f32;
+            cls_out.add(img, v).unwrap();
+        }
+        cls_out
+            .add("iran.jpg", vec![0.1f32, 0.1f32, 0.1f32, 0.7f32])
+            .unwrap();
+
+        let wrong = metrics::misclassified(&cls_db, &cls_out).unwrap();
+        assert_eq!(wrong, vec![("iran.jpg".to_string(), 2usize, 3usize)]);
+    }
+
+    #[test]
+    fn metrics_misclassified_multilabel_error() {
+        let cls_db = ClassificationDataset::new(4u8, true);
+        let cls_out = ClassificationOutput::<u8, f32>::new(4u8);
+        assert!(metrics::misclassified(&cls_db, &cls_out).is_err());
+    }
+
+    #[test]
+    fn metrics_top_confusions_excludes_diagonal() {
+        let mut cm = metrics::ConfusionMatrix::new(4usize);
+        let records = [
+            (0usize, 0usize, 10usize),
+            (1usize, 1usize, 7usize),
+            (0usize, 1usize, 3usize),
+            (2usize, 3usize, 5usize),
+            (3usize, 2usize, 1usize),
+            (1usize, 0usize, 3usize),
+        ];
+        for (true_class, predicted_class, count) in records.iter() {
+            for _ in 0..*count {
+                cm.add(*true_class, *predicted_class).unwrap();
+            }
+        }
+        assert_eq!(
+            cm.top_confusions(3usize),
+            vec![
+                (2usize, 3usize, 5usize),
+                (0usize, 1usize, 3usize),
+                (1usize, 0usize, 3usize)
+            ]
+        );
+        assert_eq!(cm.top_confusions(10usize).len(), 4usize);
+    }
+
+    #[test]
+    fn metrics_confusion_matrix_from_classification() {
+        let mut cls_db = ClassificationDataset::new(3u16, false);
+        let mut cls_out = ClassificationOutput::<u16, f64>::new(3u16);
+        cls_db.add("a.jpg", &vec![0u16]).unwrap();
+        cls_db.add("b.jpg", &vec![1u16]).unwrap();
+        cls_db.add("c.jpg", &vec![1u16]).unwrap();
+        cls_out
+            .add("a.jpg", vec![0.9f64, 0.05f64, 0.05f64])
+            .unwrap();
+        cls_out.add("b.jpg", vec![0.1f64, 0.2f64, 0.7f64]).unwrap();
+        cls_out.add("c.jpg", vec![0.1f64, 0.2f64, 0.7f64]).unwrap();
+        let cm = metrics::confusion_matrix(&cls_db, &cls_out).unwrap();
+        assert_eq!(cm.total(), 3usize);
+        assert_eq!(cm.top_confusions(1usize), vec![(1usize, 2usize, 2usize)]);
+    }
+
+    #[test]
+    fn metrics_precision_recall_curve_monotonic() {
+        let mut cls_db = ClassificationDataset::new(2u8, false);
+        let mut cls_out = ClassificationOutput::<u8, f64>::new(2u8);
+        let confidences = [
+            0.95f64, 0.85f64, 0.7f64, 0.6f64, 0.4f64, 0.3f64, 0.2f64, 0.1f64,
+        ];
+        let labels = [1u8, 1u8, 0u8, 1u8, 1u8, 0u8, 0u8, 0u8];
+        for (index, (conf, label)) in confidences.iter().zip(labels.iter()).enumerate() {
+            let imagename = format!("{}.jpg", index);
+            cls_db.add(&imagename, &vec![*label]).unwrap();
+            cls_out.add(&imagename, vec![1f64 - *conf, *conf]).unwrap();
+        }
+        let thresholds = [0f64, 0.25f64, 0.5f64, 0.75f64, 1f64];
+        let curve =
+            metrics::precision_recall_curve(&cls_db, &cls_out, 1usize, &thresholds).unwrap();
+        assert_eq!(curve.len(), thresholds.len());
+        for window in curve.windows(2) {
+            assert!(window[1].0 > window[0].0);
+            assert!(window[1].2 <= window[0].2);
+            assert!(window[1].1 >= window[0].1);
+        }
+        assert!(metrics::metrics_approx_eq(curve[0].1, 0.5f64, 2));
+        assert!(metrics::metrics_approx_eq(curve[0].2, 1f64, 2));
+        assert!(metrics::metrics_approx_eq(curve[4].1, 1f64, 2));
+        assert!(metrics::metrics_approx_eq(curve[4].2, 0f64, 2));
+    }
+
+    #[test]
+    fn metrics_precision_recall_curve_no_positives() {
+        let mut cls_db = ClassificationDataset::new(3u8, true);
+        let mut cls_out = ClassificationOutput::<u8, f32>::new(3u8);
+        cls_db.add("a.jpg", &vec![0u8]).unwrap();
+        cls_out.add("a.jpg", vec![0.9f32, 0.1f32, 0.5f32]).unwrap();
+        let curve = metrics::precision_recall_curve(&cls_db, &cls_out, 2usize, &[0.4f32]).unwrap();
+        assert_eq!(curve, vec![(0.4f32, 0f64, 0f64)]);
+        assert!(metrics::precision_recall_curve(&cls_db, &cls_out, 3usize, &[0.4f32]).is_err());
+    }
+
+    #[test]
+    fn ensemble_mean_of_identical_outputs() {
+        let mut cls_out = ClassificationOutput::<u16, f32>::new(10u16);
+        for i in 0..20 {
+            let v = (0..10).map(|_| rand::random::<f32>()).collect::<Vec<f32>>();
+            cls_out.add(&format!("{}.jpg", i), v).unwrap();
+        }
+        let ensemble = ensemble::ensemble_mean(&[&cls_out, &cls_out]).unwrap();
+        assert_eq!(ensemble.num_classes(), 10u16);
+        assert_eq!(ensemble.num_images(), cls_out.num_images());
+        for imagename in cls_out.list_images() {
+            for (lhs, rhs) in ensemble
+                .confidence_for_image(imagename)
+                .unwrap()
+                .iter()
+                .zip(cls_out.confidence_for_image(imagename).unwrap().iter())
+            {
+                assert!(approx_eq!(f32, *lhs, *rhs, ulps = 2));
+            }
+        }
+    }
+
+    #[test]
+    fn ensemble_mean_mismatched_images() {
+        let mut first = ClassificationOutput::<u8, f64>::new(2u8);
+        first.add("india.jpg", vec![0.5f64, 0.5f64]).unwrap();
+        first.add("iran.jpg", vec![0.5f64, 0.5f64]).unwrap();
+        let mut second = ClassificationOutput::<u8, f64>::new(2u8);
+        second.add("india.jpg", vec![0.5f64, 0.5f64]).unwrap();
+        second.add("peru.jpg", vec![0.5f64, 0.5f64]).unwrap();
+
+        let err = ensemble::ensemble_mean(&[&first, &second]).err().unwrap();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+        assert!(err.to_string().contains("iran.jpg"));
+        assert!(err.to_string().contains("peru.jpg"));
+        assert!(ensemble::ensemble_mean::<u8, f64>(&[]).is_err());
+    }
+
+    #[test]
+    fn ensemble_weighted_selects_first_output() {
+        let mut first = ClassificationOutput::<u8, f64>::new(5u8);
+        let mut second = ClassificationOutput::<u8, f64>::new(5u8);
+        for i in 0..10 {
+            let imagename = format!("{}.jpg", i);
+            first
+                .add(&imagename, (0..5).map(|_| rand::random::<f64>()).collect())
+                .unwrap();
+            second
+                .add(&imagename, (0..5).map(|_| rand::random::<f64>()).collect())
+                .unwrap();
+        }
+        let ensemble = ensemble::ensemble_weighted(&[&first, &second], &[1f64, 0f64]).unwrap();
+        for imagename in first.list_images() {
+            assert_eq!(
+                ensemble.confidence_for_image(imagename).unwrap(),
+                first.confidence_for_image(imagename).unwrap()
+            );
+        }
+        assert!(ensemble::ensemble_weighted(&[&first, &second], &[1f64]).is_err());
+        assert!(ensemble::ensemble_weighted(&[&first, &second], &[0f64, 0f64]).is_err());
+        assert!(ensemble::ensemble_weighted(&[&first, &second], &[2f64, -1f64]).is_err());
+    }
+
+    #[test]
+    fn ensemble_vote_majority_and_tie() {
+        let confidences = [
+            (vec![0.1f32, 0.6f32, 0.3f32], vec![0.1f32, 0.2f32, 0.7f32]),
+            (vec![0.2f32, 0.5f32, 0.3f32], vec![0.8f32, 0.1f32, 0.1f32]),
+            (vec![0.2f32, 0.2f32, 0.6f32], vec![0.1f32, 0.6f32, 0.3f32]),
+        ];
+        let mut outputs = Vec::<ClassificationOutput<u8, f32>>::new();
+        for (clear, tie) in confidences.iter() {
+            let mut cls_out = ClassificationOutput::<u8, f32>::new(3u8);
+            cls_out.add("clear.jpg", clear.clone()).unwrap();
+            cls_out.add("tie.jpg", tie.clone()).unwrap();
+            outputs.push(cls_out);
+        }
+        let refs = outputs
+            .iter()
+            .collect::<Vec<&ClassificationOutput<u8, f32>>>();
+        let votes = ensemble::ensemble_vote(&refs).unwrap();
+        assert_eq!(votes.len(), 2usize);
+        assert_eq!(votes["clear.jpg"], 1usize);
+        assert_eq!(votes["tie.jpg"], 0usize);
+    }
+
+    #[test]
+    fn classification_dataset_label_cooccurrence() {
+        let mut cls_db = ClassificationDataset::new(4u8, true);
+        cls_db.add("a.jpg", &vec![0u8, 1u8]).unwrap();
+        cls_db.add("b.jpg", &vec![0u8, 1u8, 3u8]).unwrap();
+        cls_db.add("c.jpg", &vec![1u8]).unwrap();
+        cls_db.add("d.jpg", &vec![]).unwrap();
+        let cooccurrence = cls_db.label_cooccurrence().unwrap();
+        assert_eq!(
+            cooccurrence,
+            vec![
+                vec![2usize, 2usize, 0usize, 1usize],
+                vec![2usize, 3usize, 0usize, 1usize],
+                vec![0usize, 0usize, 0usize, 0usize],
+                vec![1usize, 1usize, 0usize, 1usize],
+            ]
+        );
+
+        let cls_db = ClassificationDataset::new(4u8, false);
+        assert!(cls_db.label_cooccurrence().is_err());
+    }
+
+    #[test]
+    fn classification_dataset_images_without_labels() {
+        let mut cls_db = ClassificationDataset::new(5u8, true);
+        cls_db.add("a.jpg", &vec![0u8, 4u8]).unwrap();
+        cls_db.add("b.jpg", &vec![]).unwrap();
+        cls_db.add("c.jpg", &vec![3u8]).unwrap();
+        assert_eq!(cls_db.images_without_labels(), vec!["b.jpg"]);
+
+        let mut cls_db = ClassificationDataset::new(5u8, false);
+        assert!(cls_db.add("a.jpg", &vec![]).is_err());
+        assert!(cls_db.is_empty());
+        assert!(cls_db.images_without_labels().is_empty());
+    }
+
+    #[test]
+    fn metrics_top1_accuracy_u8_confidences() {
+        let mut cls_db = ClassificationDataset::new(4u8, false);
+        let mut cls_out = ClassificationOutput::<u8, u8>::new(4u8);
+        let records = [
+            ("a.jpg", 0u8, vec![200u8, 10u8, 20u8, 25u8]),
+            ("b.jpg", 1u8, vec![0u8, 255u8, 0u8, 0u8]),
+            ("c.jpg", 2u8, vec![90u8, 10u8, 80u8, 75u8]),
+            ("d.jpg", 3u8, vec![1u8, 2u8, 3u8, 4u8]),
+        ];
+        for (imagename, label, confidences) in records.iter() {
+            cls_db.add(imagename, &vec![*label]).unwrap();
+            cls_out.add(imagename, confidences.clone()).unwrap();
+        }
+        assert!(metrics::metrics_approx_eq(
+            metrics::top1_accuracy(&cls_db, &cls_out).unwrap(),
+            0.75f64,
+            2
+        ));
+        assert!(metrics::metrics_approx_eq(
+            metrics::topk_accuracy(&cls_db, &cls_out, 2usize).unwrap(),
+            1f64,
+            2
+        ));
+        let cm = metrics::confusion_matrix(&cls_db, &cls_out).unwrap();
+        assert_eq!(cm.top_confusions(1usize), vec![(2usize, 0usize, 1usize)]);
+    }
+
+    #[test]
+    fn classification_debug_is_compact() {
+        let mut cls_db = ClassificationDataset::new(7u8, false);
+        cls_db.add("hello.jpg", &vec![3u8]).unwrap();
+        cls_db.add("world.jpg", &vec![5u8]).unwrap();
+        let debug = format!("{:?}", cls_db);
+        assert!(debug.contains("num_classes: 7"));
+        assert!(debug.contains("num_images: 2"));
+        assert!(debug.contains("hello.jpg"));
+
+        let mut cls_out = ClassificationOutput::<u8, f32>::new(2u8);
+        for i in 0..100 {
+            cls_out
+                .add(&format!("{}.jpg", i), vec![0.5f32, 0.5f32])
+                .unwrap();
+        }
+        let debug = format!("{:?}", cls_out);
+        assert!(debug.contains("num_images: 100"));
+        assert!(!debug.contains(".jpg"));
+
+        let cloned = cls_out.clone();
+        assert_eq!(cloned, cls_out);
+    }
+
+    #[test]
+    fn classification_dataset_eq_ignores_order() {
+        let images = ["india.jpg", "germany.png", "iran.jpg", "canada.png"];
+        let labels = [vec![0u16, 2u16], vec![1u16], vec![3u16, 1u16, 0u16], vec![]];
+
+        let mut lhs = ClassificationDataset::new(4u16, true);
+        for (img, label) in images.iter().zip(labels.iter()) {
+            lhs.add(img, label).unwrap();
+        }
+        let mut rhs = ClassificationDataset::new(4u16, true);
+        for (img, label) in images.iter().zip(labels.iter()).rev() {
+            let mut label = label.clone();
+            label.reverse();
+            rhs.add(img, &label).unwrap();
+        }
+        assert_eq!(lhs, rhs);
+
+        rhs = lhs.clone();
+        rhs.add("japan.jpg", &vec![2u16]).unwrap();
+        assert_ne!(lhs, rhs);
+        assert_ne!(
+            ClassificationDataset::new(4u16, true),
+            ClassificationDataset::new(4u16, false)
+        );
+    }
+
+    #[test]
+    fn metrics_evaluator_matches_classification_report() {
+        let mut cls_db = ClassificationDataset::new(10u32, false);
+        let mut cls_out = ClassificationOutput::<u32, f32>::new(10u32);
+        let mut evaluator = metrics::Evaluator::new(10u32, false);
+        for i in 0..500 {
+            let imagename = format!("{}.jpg", i);
+            let label = rand::thread_rng().gen_range(0u32..10u32);
+            let confidences = (0..10).map(|_| rand::random::<f32>()).collect::<Vec<f32>>();
+            cls_db.add(&imagename, &vec![label]).unwrap();
+            evaluator.update(&[label], &confidences).unwrap();
+            cls_out.add(&imagename, confidences).unwrap();
+        }
+        assert_eq!(evaluator.num_images(), 500usize);
+
+        let batch = metrics::classification_report(&cls_db, &cls_out).unwrap();
+        let streamed = evaluator.finalize();
+        assert_eq!(streamed.num_images(), batch.num_images());
+        assert_eq!(streamed.accuracy(), batch.accuracy());
+        assert_eq!(streamed.precision(), batch.precision());
+        assert_eq!(streamed.recall(), batch.recall());
+        assert_eq!(streamed.f1(), batch.f1());
+        assert_eq!(streamed.support(), batch.support());
+        assert_eq!(streamed.macro_f1(), batch.macro_f1());
+        assert_eq!(streamed.weighted_f1(), batch.weighted_f1());
+    }
+
+    #[test]
+    fn metrics_evaluator_rejects_invalid_updates() {
+        let mut evaluator = metrics::Evaluator::new(3u8, false);
+        assert!(evaluator
+            .update(&[0u8, 1u8], &[0.1f64, 0.2f64, 0.7f64])
+            .is_err());
+        assert!(evaluator.update(&[0u8], &[0.1f64, 0.9f64]).is_err());
+        assert!(evaluator.update(&[3u8], &[0.1f64, 0.2f64, 0.7f64]).is_err());
+        assert_eq!(evaluator.num_images(), 0usize);
+
+        let mut evaluator = metrics::Evaluator::new(3u8, true);
+        assert!(evaluator.update(&[0u8], &[0.1f64, 0.2f64, 0.7f64]).is_err());
+    }
+
+    #[cfg(feature = "half")]
+    #[test]
+    fn classification_test_topk_for_image_f16() {
+        use half::f16;
+
+        let mut cls_out = ClassificationOutput::<u16, f16>::new(100u16);
+        for i in 0..20 {
+            let v = (0..100)
+                .map(|x| f16::from_f32(((x * 7 + i) % 100) as f32))
+                .collect::<Vec<f16>>();
+            cls_out.add(&format!("{}.jpg", i), v).unwrap();
+        }
+        cls_out
+            .add(
+                "test.jpg",
+                (0..100).map(|x| f16::from_f32(x as f32)).collect(),
+            )
+            .unwrap();
+        assert_eq!(
+            cls_out.topk_for_image("test.jpg", 3usize).unwrap(),
+            vec![99usize, 98usize, 97usize]
+        );
+    }
+
+    #[cfg(feature = "ndarray")]
+    #[test]
+    fn utils_topk_ndarray_row_matches_vec() {
+        use crate::utils::TopK;
+        use ndarray::Array2;
+
+        let matrix = Array2::<f64>::from_shape_fn((4, 50), |_| rand::random::<f64>());
+        for row in matrix.rows() {
+            let as_vec = row.to_vec();
+            assert_eq!(row.top_k(5usize).unwrap(), as_vec.top_k(5usize).unwrap());
+        }
+        let matrix = Array2::<u8>::from_shape_fn((3, 20), |(i, j)| (i * 20 + j) as u8);
+        assert_eq!(
+            matrix.row(1).top_k(3usize).unwrap(),
+            vec![19usize, 18usize, 17usize]
+        );
+        assert!(matrix.row(0).top_k(21usize).is_err());
+    }
+
+    #[cfg(feature = "ndarray")]
+    #[test]
+    fn classification_output_from_ndarray() {
+        use ndarray::Array2;
+
+        let names = ["a.jpg", "b.jpg", "c.jpg"];
+        let matrix = Array2::<f64>::from_shape_fn((3, 4), |(i, j)| (i * 4 + j) as f64);
+        let cls_out = ClassificationOutput::from_ndarray(matrix.view(), &names, 4u8).unwrap();
+        assert_eq!(cls_out.num_images(), 3usize);
+        assert_eq!(
+            cls_out.confidence_for_image("b.jpg").unwrap(),
+            &vec![4f64, 5f64, 6f64, 7f64]
+        );
+
+        assert!(ClassificationOutput::from_ndarray(matrix.view(), &names, 5u8).is_err());
+        assert!(ClassificationOutput::from_ndarray(matrix.view(), &names[..2], 4u8).is_err());
+        assert!(ClassificationOutput::from_ndarray(
+            matrix.view(),
+            &["a.jpg", "b.jpg", "a.jpg"],
+            4u8
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn classification_dataset_class_counts() {
+        let mut cls_db = ClassificationDataset::new(4u8, true);
+        cls_db.add("a.jpg", &vec![0u8, 1u8]).unwrap();
+        cls_db.add("b.jpg", &vec![1u8]).unwrap();
+        cls_db.add("c.jpg", &vec![]).unwrap();
+        assert_eq!(cls_db.class_counts(), vec![1usize, 2usize, 0usize, 0usize]);
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn classification_dataset_par_class_counts_parity() {
+        let mut cls_db = ClassificationDataset::new(50u16, true);
+        for i in 0..20000 {
+            let labels = (0..rand::thread_rng().gen_range(0usize..5usize))
+                .map(|_| rand::thread_rng().gen_range(0u16..50u16))
+                .collect::<Vec<u16>>();
+            cls_db.add(&format!("{}.jpg", i), &labels).unwrap();
+        }
+        assert_eq!(cls_db.par_class_counts(), cls_db.class_counts());
+    }
+
+    #[test]
+    fn metrics_mean_true_class_confidence() {
+        let mut cls_db = ClassificationDataset::new(3u8, false);
+        let mut cls_out = ClassificationOutput::<u8, f64>::new(3u8);
+        cls_db.add("a.jpg", &vec![0u8]).unwrap();
+        cls_db.add("b.jpg", &vec![1u8]).unwrap();
+        cls_db.add("c.jpg", &vec![2u8]).unwrap();
+        cls_db.add("d.jpg", &vec![2u8]).unwrap();
+        cls_out
+            .add("a.jpg", vec![0.9f64, 0.05f64, 0.05f64])
+            .unwrap();
+        cls_out.add("b.jpg", vec![0.5f64, 0.3f64, 0.2f64]).unwrap();
+        cls_out.add("c.jpg", vec![0.2f64, 0.2f64, 0.6f64]).unwrap();
+        assert!(metrics::metrics_approx_eq(
+            metrics::mean_true_class_confidence(&cls_db, &cls_out).unwrap(),
+            0.6f64,
+            4
+        ));
+
+        let cls_out = ClassificationOutput::<u8, f64>::new(3u8);
+        assert!(metrics::mean_true_class_confidence(&cls_db, &cls_out).is_err());
+    }
+
+    #[test]
+    fn utils_kth_largest_sorted_vector() {
+        use crate::utils::TopK;
+
+        let v = (0..100).map(|x| x as f32 * 0.5f32).collect::<Vec<f32>>();
+        for k in 1..=100usize {
+            assert_eq!(v.kth_largest(k).unwrap(), (100 - k) as f32 * 0.5f32);
+        }
+        assert!(v.kth_largest(0usize).is_err());
+        assert!(v.kth_largest(101usize).is_err());
+
+        let v = vec![4u64, 9u64, 1u64, 9u64, 7u64];
+        assert_eq!(v.kth_largest(2usize).unwrap(), 9u64);
+        assert_eq!(v.kth_largest(3usize).unwrap(), 7u64);
+        assert!(Vec::<u8>::new().kth_largest(1usize).is_err());
+    }
+
+    #[test]
+    fn utils_topk_zero_and_empty() {
+        use crate::utils::TopK;
+
+        assert_eq!(
+            vec![0.3f64, 0.1f64].top_k(0usize).unwrap(),
+            Vec::<usize>::new()
+        );
+        assert_eq!(
+            Vec::<f64>::new().top_k(0usize).unwrap(),
+            Vec::<usize>::new()
+        );
+        assert_eq!(
+            Vec::<u32>::new().top_k(0usize).unwrap(),
+            Vec::<usize>::new()
+        );
+
+        let err = Vec::<f64>::new().top_k(1usize).err().unwrap();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+        assert_eq!(
+            err.to_string(),
+            crate::errors::topk_incorrect_k(1usize, 0usize).to_string()
+        );
+        assert!(Vec::<u32>::new().top_k(3usize).is_err());
+    }
+
+    #[test]
+    fn classification_dataset_builder_matches_new() {
+        let built = ClassificationDatasetBuilder::new()
+            .num_classes(20u16)
+            .multilabel(true)
+            .capacity(64usize)
+            .build()
+            .unwrap();
+        assert_eq!(built, ClassificationDataset::new(20u16, true));
+
+        let built = ClassificationDatasetBuilder::new()
+            .num_classes(5u8)
+            .build()
+            .unwrap();
+        assert_eq!(built, ClassificationDataset::new(5u8, false));
+
+        assert!(ClassificationDatasetBuilder::<u8>::new()
+            .multilabel(true)
+            .build()
+            .is_err());
+    }
+
+    #[test]
+    fn classification_num_classes_usize() {
+        use num_traits::ToPrimitive;
+
+        let cls_db = ClassificationDataset::new(200u8, true);
+        assert_eq!(
+            cls_db.num_classes_usize(),
+            cls_db.num_classes().to_usize().unwrap()
+        );
+        let cls_out = ClassificationOutput::<u128, f64>::new(21000u128);
+        assert_eq!(
+            cls_out.num_classes_usize(),
+            cls_out.num_classes().to_usize().unwrap()
+        );
+    }
+
+    #[test]
+    fn metrics_evaluate_shared_class_type() {
+        let mut cls_db = ClassificationDataset::new(3u32, false);
+        let mut cls_out = ClassificationOutput::<u32, f32>::new(3u32);
+        cls_db.add("a.jpg", &vec![0u32]).unwrap();
+        cls_db.add("b.jpg", &vec![2u32]).unwrap();
+        cls_out.add("a.jpg", vec![0.5f32, 0.3f32, 0.2f32]).unwrap();
+        cls_out.add("b.jpg", vec![0.5f32, 0.3f32, 0.2f32]).unwrap();
+
+        let evaluation = metrics::evaluate(&cls_db, &cls_out).unwrap();
+        assert_eq!(evaluation.gt().num_images(), 2usize);
+        assert_eq!(evaluation.pred().num_images(), 2usize);
+        assert_eq!(
+            evaluation.topk_accuracy(1usize).unwrap(),
+            metrics::top1_accuracy(&cls_db, &cls_out).unwrap()
+        );
+        assert_eq!(
+            evaluation.report().unwrap().support(),
+            &[1usize, 0usize, 1usize]
+        );
+
+        let cls_out = ClassificationOutput::<u32, f32>::new(4u32);
+        assert!(metrics::evaluate(&cls_db, &cls_out).is_err());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn classification_dataset_json_file_round_trip() {
+        let mut cls_db = ClassificationDataset::new(6u16, true);
+        cls_db.add("a.jpg", &vec![0u16, 5u16]).unwrap();
+        cls_db.add("b.jpg", &vec![3u16]).unwrap();
+        cls_db.add("c.jpg", &vec![]).unwrap();
+        let filename = std::env::temp_dir().join("bagheera_test_json_round_trip.json");
+        let filename = filename.to_str().unwrap();
+        cls_db.to_json_file(filename).unwrap();
+        let loaded = ClassificationDataset::<u16>::from_json_file(filename).unwrap();
+        assert_eq!(loaded, cls_db);
+
+        std::fs::write(
+            filename,
+            r#"{"num_classes":2,"data":{"a.jpg":[true,true]},"is_multilabel":false}"#,
+        )
+        .unwrap();
+        assert!(ClassificationDataset::<u16>::from_json_file(filename).is_err());
+        std::fs::write(
+            filename,
+            r#"{"num_classes":3,"data":{"a.jpg":[true,false]},"is_multilabel":true}"#,
+        )
+        .unwrap();
+        assert!(ClassificationDataset::<u16>::from_json_file(filename).is_err());
+        std::fs::remove_file(filename).unwrap();
+    }
+
+    #[test]
+    fn classification_dataset_sample_balanced() {
+        let mut cls_db = ClassificationDataset::new(3u8, false);
+        for i in 0..50 {
+            cls_db.add(&format!("zero_{}.jpg", i), &vec![0u8]).unwrap();
+        }
+        for i in 0..2 {
+            cls_db.add(&format!("one_{}.jpg", i), &vec![1u8]).unwrap();
+        }
+        for i in 0..10 {
+            cls_db.add(&format!("two_{}.jpg", i), &vec![2u8]).unwrap();
+        }
+        let sample = cls_db.sample_balanced(5usize, 7u64).unwrap();
+        assert_eq!(sample.len(), 12usize);
+        assert_eq!(
+            sample.iter().filter(|x| x.starts_with("zero")).count(),
+            5usize
+        );
+        assert_eq!(
+            sample.iter().filter(|x| x.starts_with("one")).count(),
+            2usize
+        );
+        assert_eq!(
+            sample.iter().filter(|x| x.starts_with("two")).count(),
+            5usize
+        );
+
+        let mut rebuilt = ClassificationDataset::new(3u8, false);
+        let mut images = cls_db.list_images();
+        images.reverse();
+        for img in images {
+            rebuilt
+                .add(
+                    img,
+                    &vec![cls_db.get_gt(img).unwrap().iter().position(|x| *x).unwrap() as u8],
+                )
+                .unwrap();
+        }
+        assert_eq!(rebuilt.sample_balanced(5usize, 7u64).unwrap(), sample);
+        assert_ne!(cls_db.sample_balanced(5usize, 8u64).unwrap(), sample);
+
+        let err = ClassificationDataset::new(3u8, true)
+            .sample_balanced(5usize, 7u64)
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "The operation sample_balanced requires a single-label dataset."
+        );
+    }
+
+    #[test]
+    fn metrics_topk_accuracies_match_topk_accuracy() {
+        let mut cls_db = ClassificationDataset::new(20u8, false);
+        let mut cls_out = ClassificationOutput::<u8, f64>::new(20u8);
+        for i in 0..300 {
+            let imagename = format!("{}.jpg", i);
+            cls_db
+                .add(&imagename, &vec![rand::thread_rng().gen_range(0u8..20u8)])
+                .unwrap();
+            cls_out
+                .add(&imagename, (0..20).map(|_| rand::random::<f64>()).collect())
+                .unwrap();
+        }
+        let ks = [5usize, 1usize, 10usize, 20usize];
+        let accuracies = metrics::topk_accuracies(&cls_db, &cls_out, &ks).unwrap();
+        for (k, accuracy) in ks.iter().zip(accuracies.iter()) {
+            assert_eq!(
+                *accuracy,
+                metrics::topk_accuracy(&cls_db, &cls_out, *k).unwrap()
+            );
+        }
+        assert_eq!(accuracies[3], 1f64);
+        assert!(metrics::topk_accuracies(&cls_db, &cls_out, &[21usize]).is_err());
+    }
+
+    #[test]
+    fn metrics_single_label_metrics_reject_multilabel() {
+        let mut cls_db = ClassificationDataset::new(3u8, true);
+        cls_db.add("a.jpg", &vec![0u8, 2u8]).unwrap();
+        let mut cls_out = ClassificationOutput::<u8, f32>::new(3u8);
+        cls_out.add("a.jpg", vec![0.5f32, 0.2f32, 0.3f32]).unwrap();
+
+        let errors = vec![
+            (
+                "misclassified",
+                metrics::misclassified(&cls_db, &cls_out).err(),
+            ),
+            (
+                "confusion_matrix",
+                metrics::confusion_matrix(&cls_db, &cls_out).err(),
+            ),
+            (
+                "topk_accuracy",
+                metrics::topk_accuracy(&cls_db, &cls_out, 1usize).err(),
+            ),
+            (
+                "topk_accuracies",
+                metrics::topk_accuracies(&cls_db, &cls_out, &[1usize]).err(),
+            ),
+            (
+                "top1_accuracy",
+                metrics::top1_accuracy(&cls_db, &cls_out).err(),
+            ),
+            (
+                "classification_report",
+                metrics::classification_report(&cls_db, &cls_out).err(),
+            ),
+            (
+                "mean_true_class_confidence",
+                metrics::mean_true_class_confidence(&cls_db, &cls_out).err(),
+            ),
+        ];
+        for (metric, err) in errors {
+            let err = err.unwrap();
+            assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+            assert_eq!(
+                err.to_string(),
+                format!("The operation {} requires a single-label dataset.", metric)
+            );
+        }
+    }
+
+    #[test]
+    fn utils_topk_scratch_matches_trait() {
+        use crate::utils::{TopK, TopKScratch};
+
+        let mut scratch = TopKScratch::<f64>::with_capacity(100usize);
+        for k in [1usize, 5usize, 50usize, 100usize].iter() {
+            let v = (0..100)
+                .map(|_| rand::random::<f64>())
+                .collect::<Vec<f64>>();
+            assert_eq!(
+                scratch.top_k(&v, *k).unwrap(),
+                v.top_k(*k).unwrap().as_slice()
+            );
+        }
+        let mut scratch = TopKScratch::<u16>::new();
+        for _ in 0..10 {
+            let v = (0..30).map(|_| rand::random::<u16>()).collect::<Vec<u16>>();
+            assert_eq!(
+                scratch.top_k(&v, 3usize).unwrap(),
+                v.top_k(3usize).unwrap().as_slice()
+            );
+        }
+        assert!(scratch.top_k(&[1u16, 2u16], 3usize).is_err());
+    }
+
+    #[test]
+    fn classification_dataset_remap_classes() {
+        let mut cls_db = ClassificationDataset::new(4u8, true);
+        cls_db.add("a.jpg", &vec![0u8, 1u8]).unwrap();
+        cls_db.add("b.jpg", &vec![1u8, 3u8]).unwrap();
+        cls_db.add("c.jpg", &vec![2u8]).unwrap();
+
+        // Classes 0 and 1 are merged into class 0.
+        let remapped = cls_db.remap_classes(&[0u8, 0u8, 1u8, 2u8], 3u8).unwrap();
+        assert_eq!(remapped.num_classes(), 3u8);
+        assert!(remapped.is_multilabel());
+        assert_eq!(remapped.num_images(), 3usize);
+        assert_eq!(remapped.get_gt("a.jpg").unwrap(), &vec![true, false, false]);
+        assert_eq!(remapped.get_gt("b.jpg").unwrap(), &vec![true, false, true]);
+        assert_eq!(remapped.get_gt("c.jpg").unwrap(), &vec![false, true, false]);
+        assert_eq!(remapped.class_counts(), vec![2usize, 1usize, 1usize]);
+
+        assert!(cls_db.remap_classes(&[0u8, 0u8, 1u8], 3u8).is_err());
+        assert!(cls_db.remap_classes(&[0u8, 0u8, 1u8, 3u8], 3u8).is_err());
+    }
+
+    #[test]
+    fn classification_output_remap_classes() {
+        use crate::classification::Reduce;
+
+        let mut cls_out = ClassificationOutput::<u8, f64>::new(4u8);
+        cls_out
+            .add("a.jpg", vec![0.125f64, 0.5f64, 0.25f64, 0.125f64])
+            .unwrap();
+        cls_out
+            .add("b.jpg", vec![0.5f64, 0.25f64, 0.0f64, 0.25f64])
+            .unwrap();
+        let mapping = [0usize, 0usize, 1usize, 2usize];
+
+        let summed = cls_out
+            .remap_classes(&mapping, 4usize, Reduce::Sum)
+            .unwrap();
+        assert_eq!(summed.num_classes(), 4u8);
+        for imagename in cls_out.list_images() {
+            let total = cls_out
+                .confidence_for_image(imagename)
+                .unwrap()
+                .iter()
+                .sum::<f64>();
+            let remapped = summed.confidence_for_image(imagename).unwrap();
+            assert!(metrics::metrics_approx_eq(
+                remapped.iter().sum::<f64>(),
+                total,
+                2
+            ));
+            assert_eq!(remapped[3], 0.0f64);
+        }
+        assert_eq!(
+            summed.confidence_for_image("a.jpg").unwrap()[..3],
+            [0.625f64, 0.25f64, 0.125f64]
+        );
+
+        let maxed = cls_out
+            .remap_classes(&mapping, 3usize, Reduce::Max)
+            .unwrap();
+        assert_eq!(
+            maxed.confidence_for_image("a.jpg").unwrap(),
+            &vec![0.5f64, 0.25f64, 0.125f64]
+        );
+        assert_eq!(
+            maxed.confidence_for_image("b.jpg").unwrap(),
+            &vec![0.5f64, 0.0f64, 0.25f64]
+        );
+
+        assert!(cls_out
+            .remap_classes(&mapping[..3], 3usize, Reduce::Sum)
+            .is_err());
+        assert!(cls_out
+            .remap_classes(&mapping, 2usize, Reduce::Max)
+            .is_err());
+    }
+
+    #[test]
+    fn classification_dataset_assert_same_modality() {
+        let single = ClassificationDataset::new(3u8, false);
+        let multi = ClassificationDataset::new(3u8, true);
+        assert!(single.assert_same_modality(single.is_multilabel()).is_ok());
+        assert!(multi.assert_same_modality(multi.is_multilabel()).is_ok());
+
+        let err = single
+            .assert_same_modality(multi.is_multilabel())
+            .unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+        assert!(multi.assert_same_modality(false).is_err());
+    }
+
+    #[test]
+    fn classification_dataset_label_set_histogram() {
+        let mut cls_db = ClassificationDataset::new(4u16, true);
+        cls_db.add("a.jpg", &vec![0u16, 1u16]).unwrap();
+        cls_db.add("b.jpg", &vec![1u16, 0u16]).unwrap();
+        cls_db.add("c.jpg", &vec![1u16, 0u16, 1u16]).unwrap();
+        cls_db.add("d.jpg", &vec![3u16]).unwrap();
+        cls_db.add("e.jpg", &vec![]).unwrap();
+
+        let histogram = cls_db.label_set_histogram();
+        assert_eq!(histogram.len(), 3usize);
+        assert_eq!(histogram[&vec![0u16, 1u16]], 3usize);
+        assert_eq!(histogram[&vec![3u16]], 1usize);
+        assert_eq!(histogram[&Vec::<u16>::new()], 1usize);
+        assert_eq!(histogram.values().sum::<usize>(), cls_db.num_images());
+    }
+
+    #[test]
+    fn classification_output_to_label_dataset() {
+        use crate::utils::TopK;
+
+        let mut cls_out = ClassificationOutput::<u16, f64>::new(50u16);
+        for i in 0..100 {
+            let name = format!("image_{}.jpg", i);
+            cls_out
+                .add(&name, (0..50).map(|_| rand::random::<f64>()).collect())
+                .unwrap();
+        }
+        let predicted = cls_out.to_label_dataset().unwrap();
+        assert_eq!(predicted.num_classes(), 50u16);
+        assert_eq!(predicted.num_images(), 100usize);
+        assert!(!predicted.is_multilabel());
+        for imagename in cls_out.list_images() {
+            let argmax = cls_out
+                .confidence_for_image(imagename)
+                .unwrap()
+                .top_k(1usize)
+                .unwrap()[0];
+            let gt = predicted.get_gt(imagename).unwrap();
+            assert_eq!(gt.iter().filter(|x| **x).count(), 1usize);
+            assert!(gt[argmax]);
+        }
+    }
+
+    #[test]
+    fn classification_output_topk_for_image_min_conf() {
+        let mut cls_out = ClassificationOutput::<u8, f64>::new(5u8);
+        cls_out
+            .add("a.jpg", vec![0.05f64, 0.4f64, 0.3f64, 0.15f64, 0.1f64])
+            .unwrap();
+
+        let low_floor = cls_out
+            .topk_for_image_min_conf("a.jpg", 3usize, 0.0f64)
+            .unwrap();
+        assert_eq!(low_floor, cls_out.topk_for_image("a.jpg", 3usize).unwrap());
+        let high_floor = cls_out
+            .topk_for_image_min_conf("a.jpg", 3usize, 0.3f64)
+            .unwrap();
+        assert_eq!(high_floor, vec![1usize, 2usize]);
+        assert!(cls_out
+            .topk_for_image_min_conf("a.jpg", 3usize, 0.5f64)
+            .unwrap()
+            .is_empty());
+
+        assert!(cls_out
+            .topk_for_image_min_conf("b.jpg", 1usize, 0.0f64)
+            .is_err());
+        assert!(cls_out
+            .topk_for_image_min_conf("a.jpg", 6usize, 0.0f64)
+            .is_err());
+    }
+
+    #[test]
+    fn classification_output_topk_detailed_for_image() {
+        let mut cls_out = ClassificationOutput::<u8, f32>::new(100u8);
+        let confidences = (0..100)
+            .map(|_| rand::random::<f32>())
+            .collect::<Vec<f32>>();
+        cls_out.add("a.jpg", confidences.clone()).unwrap();
+
+        let topk = cls_out.topk_detailed_for_image("a.jpg", 10usize).unwrap();
+        let indices = cls_out.topk_for_image("a.jpg", 10usize).unwrap();
+        assert_eq!(topk.len(), 10usize);
+        for (i, result) in topk.iter().enumerate() {
+            assert_eq!(result.rank(), i);
+            assert_eq!(result.class(), indices[i]);
+            assert_eq!(result.confidence(), confidences[indices[i]]);
+        }
+        assert!(topk
+            .windows(2)
+            .all(|pair| pair[0].confidence() >= pair[1].confidence()));
+
+        assert!(cls_out.topk_detailed_for_image("b.jpg", 1usize).is_err());
+        assert!(cls_out.topk_detailed_for_image("a.jpg", 101usize).is_err());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn classification_output_topk_result_serialize() {
+        let mut cls_out = ClassificationOutput::<u8, f64>::new(2u8);
+        cls_out.add("a.jpg", vec![0.25f64, 0.75f64]).unwrap();
+        let topk = cls_out.topk_detailed_for_image("a.jpg", 1usize).unwrap();
+        assert_eq!(
+            serde_json::to_string(&topk).unwrap(),
+            r#"[{"rank":0,"class":1,"confidence":0.75}]"#
+        );
+    }
+
+    #[test]
+    fn metrics_correctness_vector() {
+        let mut cls_db = ClassificationDataset::new(10u8, false);
+        let mut cls_out = ClassificationOutput::<u8, f64>::new(10u8);
+        let mut rng = rand::thread_rng();
+        for i in 0..200 {
+            let name = format!("image_{}.jpg", i);
+            cls_db.add(&name, &vec![rng.gen_range(0u8..10u8)]).unwrap();
+            cls_out
+                .add(&name, (0..10).map(|_| rand::random::<f64>()).collect())
+                .unwrap();
+        }
+
+        let correctness = metrics::correctness_vector(&cls_db, &cls_out).unwrap();
+        assert_eq!(correctness.len(), 200usize);
+        assert!(correctness.windows(2).all(|pair| pair[0].0 < pair[1].0));
+        let mean = correctness.iter().filter(|(_, correct)| *correct).count() as f64
+            / correctness.len() as f64;
+        assert!(metrics::metrics_approx_eq(
+            mean,
+            metrics::top1_accuracy(&cls_db, &cls_out).unwrap(),
+            2
+        ));
+
+        let multi = ClassificationDataset::new(10u8, true);
+        assert!(metrics::correctness_vector(&multi, &cls_out).is_err());
+    }
+
+    #[test]
+    fn metrics_accuracy_confidence_interval() {
+        let mut cls_db = ClassificationDataset::new(3u8, false);
+        let mut cls_out = ClassificationOutput::<u8, f64>::new(3u8);
+        for i in 0..300 {
+            let name = format!("image_{}.jpg", i);
+            cls_db.add(&name, &vec![(i % 3) as u8]).unwrap();
+            let mut confidences = vec![0.1f64; 3usize];
+            confidences[(i % 5) % 3] = 0.8f64;
+            cls_out.add(&name, confidences).unwrap();
+        }
+
+        let (point, lower, upper) =
+            metrics::accuracy_confidence_interval(&cls_db, &cls_out, 500usize, 0.1f64, 7u64)
+                .unwrap();
+        assert!(metrics::metrics_approx_eq(
+            point,
+            metrics::top1_accuracy(&cls_db, &cls_out).unwrap(),
+            2
+        ));
+        assert!(lower <= point && point <= upper);
+        assert!(lower < upper);
+        assert_eq!(
+            metrics::accuracy_confidence_interval(&cls_db, &cls_out, 500usize, 0.1f64, 7u64)
+                .unwrap(),
+            (point, lower, upper)
+        );
+
+        assert!(
+            metrics::accuracy_confidence_interval(&cls_db, &cls_out, 0usize, 0.1f64, 7u64).is_err()
+        );
+        assert!(
+            metrics::accuracy_confidence_interval(&cls_db, &cls_out, 10usize, 1f64, 7u64).is_err()
+        );
+    }
+
+    #[test]
+    fn metrics_weighted_accuracy() {
+        use std::collections::HashMap;
+
+        let mut cls_db = ClassificationDataset::new(5u8, false);
+        let mut cls_out = ClassificationOutput::<u8, f32>::new(5u8);
+        let mut weights = HashMap::<String, f64>::new();
+        let mut rng = rand::thread_rng();
+        for i in 0..100 {
+            let name = format!("image_{}.jpg", i);
+            cls_db.add(&name, &vec![rng.gen_range(0u8..5u8)]).unwrap();
+            cls_out
+                .add(&name, (0..5).map(|_| rand::random::<f32>()).collect())
+                .unwrap();
+            weights.insert(name, 2.5f64);
+        }
+        assert!(metrics::metrics_approx_eq(
+            metrics::weighted_accuracy(&cls_db, &cls_out, &weights).unwrap(),
+            metrics::top1_accuracy(&cls_db, &cls_out).unwrap(),
+            4
+        ));
+
+        weights.insert("image_0.jpg".to_string(), -1f64);
+        assert!(metrics::weighted_accuracy(&cls_db, &cls_out, &weights).is_err());
+        weights.remove("image_0.jpg");
+        assert!(metrics::weighted_accuracy(&cls_db, &cls_out, &weights).is_err());
+        for weight in weights.values_mut() {
+            *weight = 0f64;
+        }
+        weights.insert("image_0.jpg".to_string(), 0f64);
+        assert!(metrics::weighted_accuracy(&cls_db, &cls_out, &weights).is_err());
+    }
+
+    #[test]
+    fn classification_normalize_keys() {
+        use crate::utils::basename;
+
+        let mut cls_db = ClassificationDataset::new(3u8, false);
+        cls_db.add("india.jpg", &vec![0u8]).unwrap();
+        cls_db.add("iran.jpg", &vec![2u8]).unwrap();
+        let mut cls_out = ClassificationOutput::<u8, f32>::new(3u8);
+        cls_out
+            .add("/data/val/india.jpg", vec![0.8f32, 0.1f32, 0.1f32])
+            .unwrap();
+        cls_out
+            .add("/data/val/iran.jpg", vec![0.1f32, 0.1f32, 0.8f32])
+            .unwrap();
+        assert!(metrics::top1_accuracy(&cls_db, &cls_out).is_err());
+
+        cls_out.normalize_keys(basename).unwrap();
+        cls_db.normalize_keys(basename).unwrap();
+        assert_eq!(cls_out.num_images(), 2usize);
+        assert_eq!(metrics::top1_accuracy(&cls_db, &cls_out).unwrap(), 1f64);
+
+        cls_out
+            .add("/data/test/india.jpg", vec![0.8f32, 0.1f32, 0.1f32])
+            .unwrap();
+        let before = cls_out.clone();
+        assert!(cls_out.normalize_keys(basename).is_err());
+        assert!(cls_out == before);
+    }
+
+    #[test]
+    fn classification_dataset_case_insensitive_keys() {
+        let mut cls_db = ClassificationDataset::new(3u8, false);
+        cls_db.add("India.JPG", &vec![0u8]).unwrap();
+        assert!(!cls_db.image_is_present("india.jpg"));
+
+        let mut cls_db = cls_db.with_case_insensitive_keys().unwrap();
+        cls_db.add("IRAN.jpg", &vec![2u8]).unwrap();
+        for name in [
+            "india.jpg",
+            "INDIA.JPG",
+            "India.jpg",
+            "iran.jpg",
+            "Iran.JPG",
+        ]
+        .iter()
+        {
+            assert!(cls_db.image_is_present(name));
+        }
+        assert_eq!(
+            cls_db.get_gt("iRaN.jPg").unwrap(),
+            &vec![false, false, true]
+        );
+        assert!(cls_db.add("iran.JPG", &vec![1u8]).is_err());
+        assert_eq!(cls_db.num_images(), 2usize);
+        let mut images = cls_db.list_images();
+        images.sort_unstable();
+        assert_eq!(images, vec!["india.jpg", "iran.jpg"]);
+
+        let mut colliding = ClassificationDataset::new(3u8, false);
+        colliding.add("a.jpg", &vec![0u8]).unwrap();
+        colliding.add("A.jpg", &vec![1u8]).unwrap();
+        assert!(colliding.with_case_insensitive_keys().is_err());
+    }
+
+    #[test]
+    fn classification_dataset_retain() {
+        let mut cls_db = ClassificationDataset::new(5u8, true);
+        cls_db.add("a.jpg", &vec![0u8]).unwrap();
+        cls_db.add("b.jpg", &vec![1u8, 4u8]).unwrap();
+        cls_db.add("c.jpg", &vec![3u8]).unwrap();
+        cls_db.add("d.jpg", &vec![0u8, 2u8, 3u8]).unwrap();
+        cls_db.add("e.jpg", &vec![]).unwrap();
+
+        cls_db.retain(|_, labels| labels.len() == 1usize);
+        let mut images = cls_db.list_images();
+        images.sort_unstable();
+        assert_eq!(images, vec!["a.jpg", "c.jpg"]);
+        assert!(cls_db.is_multilabel());
+
+        cls_db.retain(|imagename, labels| imagename != "a.jpg" && labels == [3u8]);
+        assert_eq!(cls_db.list_images(), vec!["c.jpg"]);
+        assert_eq!(
+            cls_db.get_gt("c.jpg").unwrap(),
+            &vec![false, false, false, true, false]
+        );
+    }
+
+    #[test]
+    fn classification_dataset_label_sets() {
+        let mut cls_db = ClassificationDataset::new(6u8, true);
+        cls_db.add("a.jpg", &vec![0u8, 5u8]).unwrap();
+        cls_db.add("b.jpg", &vec![4u8, 1u8, 2u8]).unwrap();
+        cls_db.add("c.jpg", &vec![3u8]).unwrap();
+        cls_db.add("d.jpg", &vec![]).unwrap();
+
+        assert_eq!(cls_db.label_sets().count(), cls_db.num_images());
+        assert!(cls_db
+            .label_sets()
+            .all(|labels| labels.windows(2).all(|pair| pair[0] < pair[1])));
+        let cardinalities = cls_db
+            .label_sets()
+            .map(|labels| labels.len())
+            .collect::<Vec<usize>>();
+        let mean = cardinalities.iter().sum::<usize>() as f64 / cardinalities.len() as f64;
+        assert_eq!(mean, 1.5f64);
+        assert_eq!(cardinalities.iter().max(), Some(&3usize));
+        assert_eq!(cardinalities.iter().min(), Some(&0usize));
+    }
+
+    #[test]
+    fn classification_into_data() {
+        let mut cls_db = ClassificationDataset::new(4u16, true);
+        let mut cls_out = ClassificationOutput::<u16, f32>::new(4u16);
+        for i in 0u16..20u16 {
+            let name = format!("image_{}.jpg", i);
+            cls_db
+                .add(&name, &vec![i % 4u16, (i + 1u16) % 4u16])
+                .unwrap();
+            cls_out.add(&name, vec![i as f32; 4usize]).unwrap();
+        }
+        assert_eq!(cls_db.one_hot_data().len(), 20usize);
+        assert_eq!(cls_out.data().len(), 20usize);
+
+        let gt = cls_db.clone().into_data();
+        assert_eq!(gt.len(), 20usize);
+        for (imagename, labels) in &gt {
+            assert_eq!(labels.len(), 2usize);
+            for label in labels {
+                assert!(cls_db.get_gt(imagename).unwrap()[*label as usize]);
+            }
+        }
+        let confidences = cls_out.clone().into_data();
+        assert_eq!(confidences.len(), 20usize);
+        for (imagename, values) in &confidences {
+            assert_eq!(cls_out.confidence_for_image(imagename).unwrap(), values);
+        }
+    }
+
+    #[test]
+    fn utils_from_one_hot_checked() {
+        use crate::utils::{FromOneHot, ToOneHot};
+
+        let labels = vec![1u16, 7u16, 3u16];
+        let one_hot = labels.convert(8u16);
+        assert_eq!(
+            one_hot.from_one_hot_checked(8u16).unwrap(),
+            vec![1u16, 3u16, 7u16]
+        );
+        assert_eq!(
+            one_hot.from_one_hot_checked::<u16>(8u16).unwrap(),
+            one_hot.from_one_hot::<u16>()
+        );
+
+        let err = one_hot.from_one_hot_checked(9u16).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+        assert!(one_hot[..7].from_one_hot_checked(8u16).is_err());
+        assert!(Vec::<bool>::new()
+            .from_one_hot_checked(0u8)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn metrics_subset_accuracy_per_class_thresholds() {
+        let mut cls_db = ClassificationDataset::new(3u8, true);
+        cls_db.add("a.jpg", &vec![0u8]).unwrap();
+        cls_db.add("b.jpg", &vec![0u8, 2u8]).unwrap();
+        cls_db.add("c.jpg", &vec![1u8]).unwrap();
+        let mut cls_out = ClassificationOutput::<u8, f64>::new(3u8);
+        cls_out.add("a.jpg", vec![0.9f64, 0.1f64, 0.4f64]).unwrap();
+        cls_out.add("b.jpg", vec![0.7f64, 0.2f64, 0.3f64]).unwrap();
+        cls_out.add("c.jpg", vec![0.6f64, 0.8f64, 0.1f64]).unwrap();
+
+        // A global threshold of 0.5 misses the weak class 2 of b.jpg and keeps class 0 of
+        // c.jpg, while one of 0.25 still keeps class 0 of c.jpg and also fires class 2 on
+        // a.jpg.
+        assert_eq!(
+            cls_out
+                .predicted_labels_thresholded("b.jpg", 0.5f64)
+                .unwrap(),
+            vec![0usize]
+        );
+        assert!(metrics::metrics_approx_eq(
+            metrics::subset_accuracy(&cls_db, &cls_out, 0.5f64).unwrap(),
+            1f64 / 3f64,
+            2
+        ));
+        assert!(metrics::metrics_approx_eq(
+            metrics::subset_accuracy(&cls_db, &cls_out, 0.25f64).unwrap(),
+            1f64 / 3f64,
+            2
+        ));
+
+        let thresholds = [0.65f64, 0.5f64, 0.25f64];
+        assert_eq!(
+            cls_out
+                .predicted_labels_with_thresholds("b.jpg", &thresholds)
+                .unwrap(),
+            vec![0usize, 2usize]
+        );
+        assert_eq!(
+            cls_out
+                .predicted_labels_with_thresholds("c.jpg", &thresholds)
+                .unwrap(),
+            vec![1usize]
+        );
+        assert_eq!(
+            cls_out
+                .predicted_labels_with_thresholds("a.jpg", &thresholds)
+                .unwrap(),
+            vec![0usize, 2usize]
+        );
+        let thresholds = [0.65f64, 0.5f64, 0.3f64];
+        assert_eq!(
+            metrics::subset_accuracy_with_thresholds(&cls_db, &cls_out, &thresholds).unwrap(),
+            2f64 / 3f64
+        );
+
+        assert!(cls_out
+            .predicted_labels_with_thresholds("a.jpg", &thresholds[..2])
+            .is_err());
+        assert!(
+            metrics::subset_accuracy_with_thresholds(&cls_db, &cls_out, &thresholds[..2]).is_err()
+        );
+        let single = ClassificationDataset::new(3u8, false);
+        assert!(metrics::subset_accuracy(&single, &cls_out, 0.5f64).is_err());
+    }
+
+    #[test]
+    fn metrics_optimal_thresholds_f1() {
+        let mut cls_db = ClassificationDataset::new(2u8, true);
+        let mut cls_out = ClassificationOutput::<u8, f64>::new(2u8);
+        // Class 0 is present exactly when its confidence is at least 0.6, whereas class 1
+        // is present on every image.
+        for i in 0..20 {
+            let name = format!("image_{}.jpg", i);
+            let confidence = i as f64 / 20f64;
+            let labels = match confidence >= 0.6f64 {
+                true => vec![0u8, 1u8],
+                false => vec![1u8],
+            };
+            cls_db.add(&name, &labels).unwrap();
+            cls_out.add(&name, vec![confidence, 0.5f64]).unwrap();
+        }
+
+        let candidates = [0.2f64, 0.4f64, 0.6f64, 0.8f64];
+        let thresholds = metrics::optimal_thresholds_f1(&cls_db, &cls_out, &candidates).unwrap();
+        assert_eq!(thresholds, vec![0.6f64, 0.2f64]);
+        assert_eq!(
+            metrics::subset_accuracy_with_thresholds(&cls_db, &cls_out, &thresholds).unwrap(),
+            1f64
+        );
+
+        assert!(metrics::optimal_thresholds_f1(&cls_db, &cls_out, &[]).is_err());
+        let other = ClassificationDataset::new(3u8, true);
+        assert!(metrics::optimal_thresholds_f1(&other, &cls_out, &candidates).is_err());
+    }
+
+    #[test]
+    fn metrics_top1_accuracy_with_unknown() {
+        let mut cls_db = ClassificationDataset::new(3u8, false);
+        cls_db.add("a.jpg", &vec![1u8]).unwrap();
+        cls_db.add("b.jpg", &vec![2u8]).unwrap();
+        cls_db.add("c.jpg", &vec![0u8]).unwrap();
+        cls_db.add("d.jpg", &vec![0u8]).unwrap();
+        let mut cls_out = ClassificationOutput::<u8, f32>::new(3u8);
+        cls_out
+            .add("a.jpg", vec![0.05f32, 0.9f32, 0.05f32])
+            .unwrap();
+        cls_out
+            .add("b.jpg", vec![0.3f32, 0.15f32, 0.55f32])
+            .unwrap();
+        cls_out.add("c.jpg", vec![0.2f32, 0.4f32, 0.4f32]).unwrap();
+        cls_out.add("d.jpg", vec![0.7f32, 0.2f32, 0.1f32]).unwrap();
+
+        // Without rejection, this is the plain top-1 accuracy.
+        assert_eq!(
+            metrics::top1_accuracy_with_unknown(&cls_db, &cls_out, 0f32, 0usize).unwrap(),
+            metrics::top1_accuracy(&cls_db, &cls_out).unwrap()
+        );
+        // The unknown c.jpg is now rejected.
+        assert_eq!(
+            metrics::top1_accuracy_with_unknown(&cls_db, &cls_out, 0.5f32, 0usize).unwrap(),
+            1f64
+        );
+        // The borderline b.jpg is also rejected, while its label is known.
+        assert_eq!(
+            metrics::top1_accuracy_with_unknown(&cls_db, &cls_out, 0.6f32, 0usize).unwrap(),
+            0.75f64
+        );
+
+        assert!(metrics::top1_accuracy_with_unknown(&cls_db, &cls_out, 0.5f32, 3usize).is_err());
+    }
+
+    #[test]
+    fn classification_output_rank_of_class() {
+        let mut cls_out = ClassificationOutput::<u16, f64>::new(100u16);
+        let confidences = (0..100)
+            .map(|_| rand::random::<f64>())
+            .collect::<Vec<f64>>();
+        cls_out.add("a.jpg", confidences).unwrap();
+        let ranking = cls_out.topk_for_image("a.jpg", 100usize).unwrap();
+        assert_eq!(cls_out.rank_of_class("a.jpg", ranking[0]).unwrap(), 0usize);
+        for (rank, class) in ranking.iter().enumerate() {
+            assert_eq!(cls_out.rank_of_class("a.jpg", *class).unwrap(), rank);
+        }
+
+        cls_out
+            .add(
+                "b.jpg",
+                vec![f64::NAN, 0.5f64, 0.5f64, f64::NAN]
+                    .into_iter()
+                    .chain(vec![0f64; 96])
+                    .collect(),
+            )
+            .unwrap();
+        assert_eq!(cls_out.rank_of_class("b.jpg", 1usize).unwrap(), 0usize);
+        assert_eq!(cls_out.rank_of_class("b.jpg", 2usize).unwrap(), 1usize);
+        assert_eq!(cls_out.rank_of_class("b.jpg", 0usize).unwrap(), 98usize);
+        assert_eq!(cls_out.rank_of_class("b.jpg", 3usize).unwrap(), 99usize);
+
+        assert!(cls_out.rank_of_class("a.jpg", 100usize).is_err());
+        assert!(cls_out.rank_of_class("c.jpg", 0usize).is_err());
+    }
+
+    #[test]
+    fn metrics_mean_reciprocal_rank() {
+        let mut cls_db = ClassificationDataset::new(10u8, false);
+        let mut cls_out = ClassificationOutput::<u8, f32>::new(10u8);
+        let mut rng = rand::thread_rng();
+        for i in 0..100 {
+            let name = format!("image_{}.jpg", i);
+            let label = rng.gen_range(0u8..10u8);
+            cls_db.add(&name, &vec![label]).unwrap();
+            let mut confidences = (0..10)
+                .map(|_| rng.gen_range(0f32..0.5f32))
+                .collect::<Vec<f32>>();
+            confidences[label as usize] = 0.9f32;
+            cls_out.add(&name, confidences).unwrap();
+        }
+        assert_eq!(
+            metrics::mean_reciprocal_rank(&cls_db, &cls_out).unwrap(),
+            1f64
+        );
+
+        // Ranking the true class second on one image out of 100.
+        let label = cls_db
+            .get_gt("image_0.jpg")
+            .unwrap()
+            .iter()
+            .position(|x| *x)
+            .unwrap();
+        let mut confidences = vec![0f32; 10usize];
+        confidences[label] = 0.5f32;
+        confidences[(label + 1) % 10] = 0.6f32;
+        let mut other = ClassificationOutput::<u8, f32>::new(10u8);
+        for imagename in cls_out.list_images() {
+            match imagename {
+                "image_0.jpg" => other.add(imagename, confidences.clone()).unwrap(),
+                _ => other
+                    .add(
+                        imagename,
+                        cls_out.confidence_for_image(imagename).unwrap().clone(),
+                    )
+                    .unwrap(),
+            }
+        }
+        assert!(metrics::metrics_approx_eq(
+            metrics::mean_reciprocal_rank(&cls_db, &other).unwrap(),
+            (99f64 + 0.5f64) / 100f64,
+            4
+        ));
+
+        let multi = ClassificationDataset::new(10u8, true);
+        assert!(metrics::mean_reciprocal_rank(&multi, &cls_out).is_err());
+    }
+
+    #[test]
+    fn metrics_ndcg() {
+        let mut cls_db = ClassificationDataset::new(3u8, true);
+        cls_db.add("a.jpg", &vec![0u8, 2u8]).unwrap();
+        cls_db.add("b.jpg", &vec![1u8]).unwrap();
+        cls_db.add("c.jpg", &vec![]).unwrap();
+        let mut cls_out = ClassificationOutput::<u8, f64>::new(3u8);
+        cls_out.add("a.jpg", vec![0.9f64, 0.5f64, 0.1f64]).unwrap();
+        cls_out.add("b.jpg", vec![0.2f64, 0.7f64, 0.1f64]).unwrap();
+        cls_out.add("c.jpg", vec![0.2f64, 0.7f64, 0.1f64]).unwrap();
+
+        // a.jpg ranks its labels first and third: DCG@2 = 1 and IDCG@2 = 1 + 1 / log2(3).
+        // b.jpg ranks its label first, and c.jpg is excluded.
+        let expected = (1f64 / (1f64 + 1f64 / 3f64.log2()) + 1f64) / 2f64;
+        assert!(metrics::metrics_approx_eq(
+            metrics::ndcg(&cls_db, &cls_out, 2usize).unwrap(),
+            expected,
+            4
+        ));
+        // At k = 3, DCG = 1 + 1 / log2(4) = 1.5 for a.jpg.
+        let expected = (1.5f64 / (1f64 + 1f64 / 3f64.log2()) + 1f64) / 2f64;
+        assert!(metrics::metrics_approx_eq(
+            metrics::ndcg(&cls_db, &cls_out, 3usize).unwrap(),
+            expected,
+            4
+        ));
+        assert_eq!(metrics::ndcg(&cls_db, &cls_out, 1usize).unwrap(), 1f64);
+
+        assert!(metrics::ndcg(&cls_db, &cls_out, 0usize).is_err());
+        assert!(metrics::ndcg(&cls_db, &cls_out, 4usize).is_err());
+        let mut unlabelled = ClassificationDataset::new(3u8, true);
+        unlabelled.add("c.jpg", &vec![]).unwrap();
+        assert!(metrics::ndcg(&unlabelled, &cls_out, 2usize).is_err());
+        let single = ClassificationDataset::new(3u8, false);
+        assert!(metrics::ndcg(&single, &cls_out, 2usize).is_err());
+    }
+
+    #[test]
+    fn classification_with_hasher_iteration_order() {
+        use std::collections::hash_map::DefaultHasher;
+        use std::hash::BuildHasherDefault;
+
+        type FixedState = BuildHasherDefault<DefaultHasher>;
+        let names = (0..200)
+            .map(|i| format!("image_{}.jpg", i))
+            .collect::<Vec<String>>();
+
+        let mut lhs_db = ClassificationDataset::with_hasher(10u8, false, FixedState::default());
+        let mut rhs_db = ClassificationDataset::with_hasher(10u8, false, FixedState::default());
+        let mut lhs_out =
+            ClassificationOutput::<u8, f32, _>::with_hasher(10u8, FixedState::default());
+        let mut rhs_out =
+            ClassificationOutput::<u8, f32, _>::with_hasher(10u8, FixedState::default());
+        for (i, name) in names.iter().enumerate() {
+            let label = (i % 10) as u8;
+            lhs_db.add(name, &vec![label]).unwrap();
+            rhs_db.add(name, &vec![label]).unwrap();
+            lhs_out.add(name, vec![0.1f32; 10usize]).unwrap();
+            rhs_out.add(name, vec![0.1f32; 10usize]).unwrap();
+        }
+        assert_eq!(lhs_db.list_images(), rhs_db.list_images());
+        assert_eq!(lhs_out.list_images(), rhs_out.list_images());
+        assert!(lhs_db == rhs_db);
+        assert!(lhs_out == rhs_out);
+
+        let predicted = lhs_out.to_label_dataset().unwrap();
+        assert_eq!(
+            predicted.list_images(),
+            rhs_out.to_label_dataset().unwrap().list_images()
+        );
+    }
+
+    #[test]
+    fn utils_fx_hasher() {
+        use crate::utils::{FxBuildHasher, FxHasher};
+        use std::hash::{BuildHasher, Hasher};
+
+        let hash = |name: &str| FxBuildHasher::default().hash_one(name);
+        assert_eq!(hash("image_0.jpg"), hash("image_0.jpg"));
+        assert_ne!(hash("image_0.jpg"), hash("image_1.jpg"));
+        assert_ne!(hash("ab"), hash("ab\0"));
+        assert_ne!(hash("abcdefgh"), hash("abcdefghi"));
+        assert_eq!(FxHasher::default().finish(), 0u64);
+
+        let mut cls_db = ClassificationDataset::with_hasher(5u8, false, FxBuildHasher::default());
+        for i in 0..100 {
+            cls_db
+                .add(&format!("image_{}.jpg", i), &vec![(i % 5) as u8])
+                .unwrap();
+        }
+        assert_eq!(cls_db.num_images(), 100usize);
+        assert!(cls_db.image_is_present("image_42.jpg"));
+        assert_eq!(cls_db.class_counts(), vec![20usize; 5usize]);
+    }
+
+    #[cfg(feature = "fxhash")]
+    #[test]
+    fn classification_fxhash_default_hasher() {
+        let build = || {
+            let mut cls_out = ClassificationOutput::<u8, f32>::new(2u8);
+            for i in 0..100 {
+                cls_out
+                    .add(&format!("image_{}.jpg", i), vec![0.5f32, 0.5f32])
+                    .unwrap();
+            }
+            cls_out
+        };
+        assert_eq!(build().list_images(), build().list_images());
+
+        let mut lhs = ClassificationDataset::new(2u8, false);
+        let mut rhs = ClassificationDataset::new(2u8, false);
+        for i in 0..100 {
+            lhs.add(&format!("image_{}.jpg", i), &vec![1u8]).unwrap();
+            rhs.add(&format!("image_{}.jpg", i), &vec![1u8]).unwrap();
+        }
+        assert_eq!(lhs.list_images(), rhs.list_images());
+    }
+
+    #[cfg(feature = "bincode")]
+    #[test]
+    fn classification_output_bincode_file_round_trip() {
+        let mut cls_out = ClassificationOutput::<u16, f32>::new(10u16);
+        for i in 0..50 {
+            let confidences = (0..10).map(|c| ((i + c) % 7) as f32 / 7f32).collect();
+            cls_out
+                .add(&format!("image_{}.jpg", i), confidences)
+                .unwrap();
+        }
+        let filename = std::env::temp_dir().join("bagheera_test_bincode_round_trip.bin");
+        let filename = filename.to_str().unwrap();
+        cls_out.to_bincode_file(filename).unwrap();
+        let loaded = ClassificationOutput::<u16, f32>::from_bincode_file(filename).unwrap();
+        assert_eq!(loaded, cls_out);
+
+        #[cfg(feature = "serde")]
+        {
+            let json = serde_json::to_vec(cls_out.data()).unwrap();
+            let binary_len = std::fs::metadata(filename).unwrap().len() as usize;
+            assert!(binary_len < json.len());
+        }
+
+        assert!(ClassificationOutput::<u16, f64>::from_bincode_file(filename).is_err());
+        assert!(ClassificationOutput::<u8, f32>::from_bincode_file(filename).is_ok());
+
+        let mut bytes = std::fs::read(filename).unwrap();
+        bytes.pop();
+        std::fs::write(filename, &bytes).unwrap();
+        assert!(ClassificationOutput::<u16, f32>::from_bincode_file(filename).is_err());
+
+        let mut cls_out = ClassificationOutput::<u16, f32>::new(2u16);
+        cls_out.add("a.jpg", vec![0.5f32, 0.5f32]).unwrap();
+        cls_out.to_bincode_file(filename).unwrap();
+        let mut bytes = std::fs::read(filename).unwrap();
+        let vector_len_at = bytes.len() - 2 * 4 - 4;
+        bytes[vector_len_at] = 3u8;
+        std::fs::write(filename, &bytes).unwrap();
+        assert!(ClassificationOutput::<u16, f32>::from_bincode_file(filename).is_err());
+        std::fs::remove_file(filename).unwrap();
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn classification_dataset_from_jsonl() {
+        let filename = std::env::temp_dir().join("bagheera_test_from_jsonl.jsonl");
+        let filename = filename.to_str().unwrap();
+        std::fs::write(
+            filename,
+            "{\"image\": \"a.jpg\", \"labels\": [0, 3]}\n\
+             {\"image\": \"b.jpg\", \"labels\": [2]}\n\
+             \n\
+             {\"image\": \"c.jpg\", \"labels\": []}\n",
+        )
+        .unwrap();
+        let cls_db = ClassificationDataset::<u8>::from_jsonl(filename, 4u8, true).unwrap();
+        assert_eq!(cls_db.num_images(), 3usize);
+        assert_eq!(
+            cls_db.get_gt("a.jpg").unwrap(),
+            &vec![true, false, false, true]
+        );
+        assert_eq!(
+            cls_db.get_gt("c.jpg").unwrap(),
+            &vec![false, false, false, false]
+        );
+        assert!(ClassificationDataset::<u8>::from_jsonl(filename, 4u8, false).is_err());
+        assert!(ClassificationDataset::<u8>::from_jsonl(filename, 3u8, true).is_err());
+
+        std::fs::write(
+            filename,
+            "{\"image\": \"a.jpg\", \"labels\": [0]}\n\
+             {\"image\": \"b.jpg\", \"labels\": [1}\n\
+             {\"image\": \"c.jpg\", \"labels\": [2]}\n",
+        )
+        .unwrap();
+        let err = ClassificationDataset::<u8>::from_jsonl(filename, 4u8, false).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+        assert!(err.to_string().starts_with("Line 2 of"));
+        std::fs::remove_file(filename).unwrap();
+    }
+
+    #[test]
+    fn metrics_risk_coverage_curve() {
+        let mut cls_db = ClassificationDataset::new(2u8, false);
+        let mut cls_out = ClassificationOutput::<u8, f32>::new(2u8);
+        let confidences = [0.9f32, 0.8f32, 0.7f32, 0.6f32, 0.55f32];
+        let labels = [1u8, 1u8, 0u8, 1u8, 0u8];
+        for (i, (confidence, label)) in confidences.iter().zip(labels.iter()).enumerate() {
+            let name = format!("{}.jpg", i);
+            cls_db.add(&name, &vec![*label]).unwrap();
+            cls_out
+                .add(&name, vec![1f32 - *confidence, *confidence])
+                .unwrap();
+        }
+
+        let curve = metrics::risk_coverage_curve(&cls_db, &cls_out, 5usize).unwrap();
+        assert_eq!(
+            curve,
+            vec![
+                (0.2f64, 1f64),
+                (0.4f64, 1f64),
+                (0.6f64, 2f64 / 3f64),
+                (0.8f64, 0.75f64),
+                (1f64, 0.6f64)
+            ]
+        );
+        assert_eq!(
+            curve.last().unwrap().1,
+            metrics::top1_accuracy(&cls_db, &cls_out).unwrap()
+        );
+
+        let curve = metrics::risk_coverage_curve(&cls_db, &cls_out, 2usize).unwrap();
+        assert_eq!(curve, vec![(0.6f64, 2f64 / 3f64), (1f64, 0.6f64)]);
+        let curve = metrics::risk_coverage_curve(&cls_db, &cls_out, 8usize).unwrap();
+        assert_eq!(curve.len(), 8usize);
+        assert_eq!(curve.last().unwrap(), &(1f64, 0.6f64));
+
+        assert!(metrics::risk_coverage_curve(&cls_db, &cls_out, 0usize).is_err());
+        let cls_db = ClassificationDataset::new(2u8, true);
+        assert!(metrics::risk_coverage_curve(&cls_db, &cls_out, 5usize).is_err());
+    }
+
+    #[test]
+    fn metrics_aurc() {
+        let mut cls_db = ClassificationDataset::new(2u8, false);
+        for i in 0..4 {
+            cls_db.add(&format!("{}.jpg", i), &vec![1u8]).unwrap();
+        }
+        let build = |confidences: &[f32]| {
+            let mut cls_out = ClassificationOutput::<u8, f32>::new(2u8);
+            for (i, confidence) in confidences.iter().enumerate() {
+                cls_out
+                    .add(&format!("{}.jpg", i), vec![1f32 - *confidence, *confidence])
+                    .unwrap();
+            }
+            cls_out
+        };
+
+        let perfect = build(&[0.9f32, 0.8f32, 0.7f32, 0.4f32]);
+        let perfect_aurc = metrics::aurc(&cls_db, &perfect).unwrap();
+        assert!(metrics::metrics_approx_eq(perfect_aurc, 0.03125f64, 2));
+
+        let reversed = build(&[0.9f32, 0.8f32, 0.7f32, 0.05f32]);
+        assert_eq!(metrics::top1_accuracy(&cls_db, &reversed).unwrap(), 0.75f64);
+        assert!(metrics::aurc(&cls_db, &reversed).unwrap() > perfect_aurc);
+        let shuffled = build(&[0.9f32, 0.15f32, 0.8f32, 0.7f32]);
+        assert!(metrics::aurc(&cls_db, &shuffled).unwrap() > perfect_aurc);
+
+        let all_correct = build(&[0.9f32, 0.8f32, 0.7f32, 0.6f32]);
+        assert_eq!(metrics::aurc(&cls_db, &all_correct).unwrap(), 0f64);
+        assert!(metrics::aurc(&cls_db, &ClassificationOutput::<u8, f32>::new(2u8)).is_err());
+    }
+
+    #[test]
+    fn utils_topk_negative_values() {
+        use crate::utils::{TopK, TopKScratch};
+
+        let v = vec![
+            -0.5f64,
+            -3f64,
+            2f64,
+            -1e-3f64,
+            f64::NEG_INFINITY,
+            0.25f64,
+            -1e300f64,
+        ];
+        let expected = vec![2usize, 5usize, 3usize, 0usize, 1usize, 6usize, 4usize];
+        assert_eq!(v.top_k(v.len()).unwrap(), expected);
+        assert_eq!(v.kth_largest(3usize).unwrap(), -1e-3f64);
+        assert_eq!(v.kth_largest(7usize).unwrap(), f64::NEG_INFINITY);
+        let v32 = v.iter().map(|x| *x as f32).collect::<Vec<f32>>();
+        assert_eq!(v32.top_k(4usize).unwrap(), expected[..4].to_vec());
+        assert_eq!(
+            TopKScratch::<f64>::new().top_k(&v, 5usize).unwrap(),
+            &expected[..5]
+        );
+
+        let all_negative = vec![-2.5f32, -0.75f32, -1f32];
+        assert_eq!(
+            all_negative.top_k(3usize).unwrap(),
+            vec![1usize, 2usize, 0usize]
+        );
+        let signed_zero = vec![-1f32, -0f32, -2f32];
+        assert_eq!(signed_zero.top_k(1usize).unwrap(), vec![1usize]);
+        let v = vec![-7i32, 3i32, -1i32, 0i32];
+        assert_eq!(
+            v.top_k(4usize).unwrap(),
+            vec![1usize, 3usize, 2usize, 0usize]
+        );
+
+        let mut cls_db = ClassificationDataset::new(3u8, false);
+        cls_db.add("margins.jpg", &vec![2u8]).unwrap();
+        let mut cls_out = ClassificationOutput::<u8, f32>::new(3u8);
+        cls_out
+            .add("margins.jpg", vec![-1.5f32, -4f32, -0.2f32])
+            .unwrap();
+        assert_eq!(
+            cls_out.topk_for_image("margins.jpg", 3usize).unwrap(),
+            vec![2usize, 0usize, 1usize]
+        );
+        assert_eq!(metrics::top1_accuracy(&cls_db, &cls_out).unwrap(), 1f64);
+    }
+
+    #[test]
+    fn classification_output_validate() {
+        let mut cls_out = ClassificationOutput::<u8, f32>::new(3u8);
+        cls_out.add("a.jpg", vec![0.2f32, 0.3f32, 0.5f32]).unwrap();
+        cls_out.add("b.jpg", vec![0.6f32, 0.3f32, 0.1f32]).unwrap();
+        cls_out.add("c.jpg", vec![0.1f32, 0.1f32, 0.8f32]).unwrap();
+        assert!(cls_out.validate().is_ok());
+
+        let mut truncated = cls_out.clone();
+        truncated
+            .data_mut()
+            .get_mut("b.jpg")
+            .unwrap()
+            .truncate(2usize);
+        let err = truncated.validate().unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+        assert!(err.to_string().contains("b.jpg"));
+
+        for bad in &[f32::NAN, f32::INFINITY, f32::NEG_INFINITY] {
+            let mut corrupted = cls_out.clone();
+            corrupted.data_mut().get_mut("c.jpg").unwrap()[1] = *bad;
+            corrupted.data_mut().get_mut("b.jpg").unwrap()[0] = *bad;
+            assert!(corrupted
+                .validate()
+                .unwrap_err()
+                .to_string()
+                .contains("b.jpg"));
+        }
+
+        let mut quantized = ClassificationOutput::<u8, u8>::new(2u8);
+        quantized.add("a.jpg", vec![0u8, 255u8]).unwrap();
+        assert!(quantized.validate().is_ok());
+    }
+
+    #[test]
+    fn metrics_per_class_log_loss() {
+        let mut cls_db = ClassificationDataset::new(3u8, false);
+        cls_db.add("a.jpg", &vec![0u8]).unwrap();
+        cls_db.add("b.jpg", &vec![0u8]).unwrap();
+        cls_db.add("c.jpg", &vec![1u8]).unwrap();
+        cls_db.add("d.jpg", &vec![2u8]).unwrap();
+        cls_db.add("e.jpg", &vec![2u8]).unwrap();
+
+        let mut cls_out = ClassificationOutput::<u8, f64>::new(3u8);
+        cls_out
+            .add("a.jpg", vec![0.5f64, 0.25f64, 0.25f64])
+            .unwrap();
+        cls_out
+            .add("b.jpg", vec![0.25f64, 0.5f64, 0.25f64])
+            .unwrap();
+        cls_out.add("c.jpg", vec![0.1f64, 0.8f64, 0.1f64]).unwrap();
+        cls_out.add("d.jpg", vec![1f64, 0f64, 0f64]).unwrap();
+        cls_out.add("e.jpg", vec![0f64, 0f64, 1f64]).unwrap();
+        cls_out.add("f.jpg", vec![1f64, 0f64, 0f64]).unwrap();
+
+        let losses = metrics::per_class_log_loss(&cls_db, &cls_out).unwrap();
+        assert_eq!(losses.len(), 3usize);
+        let expected = [
+            (2f64.ln() + 4f64.ln()) / 2f64,
+            -(0.8f64.ln()),
+            -(1e-15f64.ln()) / 2f64,
+        ];
+        for (loss, expected) in losses.iter().zip(expected.iter()) {
+            assert!(metrics::metrics_approx_eq(*loss, *expected, 4));
+        }
+
+        let mut cls_db = ClassificationDataset::new(3u8, false);
+        cls_db.add("c.jpg", &vec![1u8]).unwrap();
+        assert_eq!(
+            metrics::per_class_log_loss(&cls_db, &cls_out).unwrap()[0],
+            0f64
+        );
+        let cls_db = ClassificationDataset::new(3u8, true);
+        assert!(metrics::per_class_log_loss(&cls_db, &cls_out).is_err());
+    }
+
+    #[test]
+    fn classification_output_topk_iter() {
+        use std::collections::HashMap;
+
+        let mut cls_out = ClassificationOutput::<u16, f32>::new(5u16);
+        for i in 0..20 {
+            let confidences = (0..5).map(|c| ((i * 7 + c * 3) % 11) as f32).collect();
+            cls_out
+                .add(&format!("image_{}.jpg", i), confidences)
+                .unwrap();
+        }
+
+        let eager = cls_out.topk_all(3usize).unwrap();
+        let lazy = cls_out
+            .topk_iter(3usize)
+            .map(|item| item.map(|(name, topk)| (name.to_string(), topk)))
+            .collect::<Result<HashMap<String, Vec<usize>>, std::io::Error>>()
+            .unwrap();
+        assert_eq!(lazy.len(), 20usize);
+        assert_eq!(lazy.len(), eager.len());
+        for (imagename, topk) in &lazy {
+            assert_eq!(&eager[imagename], topk);
+            assert_eq!(&cls_out.topk_for_image(imagename, 3usize).unwrap(), topk);
+        }
+
+        assert_eq!(
+            cls_out
+                .topk_iter(6usize)
+                .filter(|item| item.is_err())
+                .count(),
+            20usize
+        );
+        assert!(cls_out.topk_all(6usize).is_err());
+        assert!(ClassificationOutput::<u16, f32>::new(5u16)
+            .topk_iter(6usize)
+            .next()
+            .is_none());
+    }
+
+    #[test]
+    fn classification_output_topk_labels_for_image() {
+        let class_names = ["cat", "dog", "bird", "fish"]
+            .iter()
+            .map(|name| name.to_string())
+            .collect::<Vec<String>>();
+        let mut cls_out = ClassificationOutput::<u8, f64>::new(4u8);
+        cls_out
+            .add("a.jpg", vec![0.1f64, 0.2f64, 0.6f64, 0.1f64])
+            .unwrap();
+        cls_out
+            .add("b.jpg", vec![0.05f64, 0.05f64, 0.1f64, 0.8f64])
+            .unwrap();
+
+        assert_eq!(
+            cls_out
+                .topk_labels_for_image("a.jpg", 2usize, &class_names)
+                .unwrap(),
+            vec!["bird".to_string(), "dog".to_string()]
+        );
+        assert_eq!(
+            cls_out
+                .topk_labels_for_image("b.jpg", 1usize, &class_names)
+                .unwrap(),
+            vec!["fish".to_string()]
+        );
+
+        let err = cls_out
+            .topk_labels_for_image("a.jpg", 2usize, &class_names[..3])
+            .unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+        assert!(cls_out
+            .topk_labels_for_image("c.jpg", 2usize, &class_names)
+            .is_err());
+        assert!(cls_out
+            .topk_labels_for_image("a.jpg", 5usize, &class_names)
+            .is_err());
+    }
+
+    #[test]
+    fn classification_output_new_inferred() {
+        let mut cls_out = ClassificationOutput::<u8, f32>::new_inferred();
+        assert_eq!(cls_out.try_num_classes(), None);
+        assert_eq!(cls_out.num_classes(), 0u8);
+        cls_out
+            .add("a.jpg", vec![0.1f32, 0.2f32, 0.3f32, 0.4f32])
+            .unwrap();
+        assert_eq!(cls_out.try_num_classes(), Some(4u8));
+        assert_eq!(cls_out.num_classes_usize(), 4usize);
+        cls_out
+            .add("b.jpg", vec![0.4f32, 0.3f32, 0.2f32, 0.1f32])
+            .unwrap();
+        for confidences in [vec![0.5f32, 0.5f32], vec![0.2f32; 5]].iter() {
+            let err = cls_out.add("c.jpg", confidences.clone()).unwrap_err();
+            assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+            assert_eq!(
+                err.to_string(),
+                format!(
+                    "The image c.jpg has {} confidences while there are 4 classes.",
+                    confidences.len()
+                )
+            );
+        }
+        assert_eq!(cls_out.num_images(), 2usize);
+
+        let mut expected = ClassificationOutput::<u8, f32>::new(4u8);
+        expected
+            .add("a.jpg", vec![0.1f32, 0.2f32, 0.3f32, 0.4f32])
+            .unwrap();
+        expected
+            .add("b.jpg", vec![0.4f32, 0.3f32, 0.2f32, 0.1f32])
+            .unwrap();
+        assert_eq!(cls_out, expected);
+        assert_ne!(
+            ClassificationOutput::<u8, f32>::new_inferred(),
+            ClassificationOutput::<u8, f32>::new(0u8)
+        );
+
+        let mut too_many = ClassificationOutput::<u8, f32>::new_inferred();
+        assert!(too_many.add("a.jpg", vec![0f32; 300]).is_err());
+        assert_eq!(too_many.try_num_classes(), None);
+    }
+
+    #[test]
+    fn utils_topk_ties_match_across_float_widths() {
+        use crate::utils::{TopK, TopKScratch};
+
+        let mut rng = rand::thread_rng();
+        for _ in 0..50 {
+            let v32 = (0..64)
+                .map(|_| rng.gen_range(0..4) as f32 * 0.25f32)
+                .collect::<Vec<f32>>();
+            let v64 = v32.iter().map(|x| *x as f64).collect::<Vec<f64>>();
+            let v8 = v32.iter().map(|x| (*x * 4f32) as u8).collect::<Vec<u8>>();
+
+            let expected = {
+                let mut indices = (0..v32.len()).collect::<Vec<usize>>();
+                indices.sort_by(|lhs, rhs| v32[*rhs].partial_cmp(&v32[*lhs]).unwrap());
+                indices
+            };
+            for k in &[1usize, 5usize, 32usize, 64usize] {
+                assert_eq!(v32.top_k(*k).unwrap(), expected[..*k].to_vec());
+                assert_eq!(v64.top_k(*k).unwrap(), expected[..*k].to_vec());
+                assert_eq!(v8.top_k(*k).unwrap(), expected[..*k].to_vec());
+                assert_eq!(
+                    TopKScratch::<f64>::new().top_k(&v64, *k).unwrap(),
+                    &expected[..*k]
+                );
+            }
+        }
+
+        let mut cls_out = ClassificationOutput::<u8, f64>::new(4u8);
+        cls_out
+            .add("a.jpg", vec![0.5f64, 0.5f64, 0.5f64, 0.5f64])
+            .unwrap();
+        for class in 0..4 {
+            assert_eq!(cls_out.rank_of_class("a.jpg", class).unwrap(), class);
+        }
+        assert_eq!(
+            cls_out.topk_for_image("a.jpg", 4usize).unwrap(),
+            vec![0usize, 1usize, 2usize, 3usize]
+        );
+    }
+
+    #[test]
+    fn metrics_accuracy_single_pass() {
+        let mut rng = rand::thread_rng();
+        let mut cls_db = ClassificationDataset::new(5u8, false);
+        let mut cls_out = ClassificationOutput::<u8, f32>::new(5u8);
+        for i in 0..200 {
+            let name = format!("{}.jpg", i);
+            cls_db.add(&name, &vec![rng.gen_range(0u8..5u8)]).unwrap();
+            if i % 10 != 0 {
+                let confidences = (0..5).map(|_| rng.gen::<f32>()).collect();
+                cls_out.add(&name, confidences).unwrap();
+            }
+        }
+        cls_out.add("extra.jpg", vec![0.2f32; 5]).unwrap();
+
+        let accuracy = metrics::accuracy(&cls_db, &cls_out).unwrap();
+        assert_eq!(accuracy, metrics::top1_accuracy(&cls_db, &cls_out).unwrap());
+        let cm = metrics::confusion_matrix(&cls_db, &cls_out).unwrap();
+        let diagonal = (0..5).map(|class| cm.count(class, class)).sum::<usize>();
+        assert_eq!(accuracy, diagonal as f64 / cm.total() as f64);
+
+        assert!(metrics::accuracy(&cls_db, &ClassificationOutput::<u8, f32>::new(5u8)).is_err());
+        assert!(metrics::accuracy(&cls_db, &ClassificationOutput::<u8, f32>::new(4u8)).is_err());
+        let cls_db = ClassificationDataset::new(5u8, true);
+        assert!(metrics::accuracy(&cls_db, &cls_out).is_err());
+    }
+
+    #[test]
+    fn metrics_classification_report_for_classes() {
+        let mut rng = rand::thread_rng();
+        let mut cls_db = ClassificationDataset::new(4u8, false);
+        let mut cls_out = ClassificationOutput::<u8, f32>::new(4u8);
+        for i in 0..200 {
+            let name = format!("{}.jpg", i);
+            cls_db.add(&name, &vec![rng.gen_range(0u8..4u8)]).unwrap();
+            let confidences = (0..4).map(|_| rng.gen::<f32>()).collect();
+            cls_out.add(&name, confidences).unwrap();
+        }
+
+        let full = metrics::classification_report(&cls_db, &cls_out).unwrap();
+        assert_eq!(full.micro_f1(), full.accuracy());
+        for class in 0..4 {
+            let report =
+                metrics::classification_report_for_classes(&cls_db, &cls_out, &[class]).unwrap();
+            assert_eq!(report.precision(), &[full.precision()[class]]);
+            assert_eq!(report.recall(), &[full.recall()[class]]);
+            assert_eq!(report.f1(), &[full.f1()[class]]);
+            assert_eq!(report.support(), &[full.support()[class]]);
+            assert_eq!(report.num_images(), full.support()[class]);
+            assert_eq!(report.accuracy(), full.recall()[class]);
+            assert_eq!(report.macro_f1(), full.f1()[class]);
+            assert!(metrics::metrics_approx_eq(
+                report.micro_f1(),
+                full.f1()[class],
+                4
+            ));
+        }
+
+        let all = metrics::classification_report_for_classes(
+            &cls_db,
+            &cls_out,
+            &[0usize, 1usize, 2usize, 3usize],
+        )
+        .unwrap();
+        assert_eq!(all.accuracy(), full.accuracy());
+        assert_eq!(all.f1(), full.f1());
+        assert!(metrics::metrics_approx_eq(
+            all.micro_f1(),
+            full.micro_f1(),
+            4
+        ));
+        let reordered =
+            metrics::classification_report_for_classes(&cls_db, &cls_out, &[3usize, 1usize])
+                .unwrap();
+        assert_eq!(reordered.recall(), &[full.recall()[3], full.recall()[1]]);
+
+        assert!(metrics::classification_report_for_classes(&cls_db, &cls_out, &[4usize]).is_err());
+        assert!(
+            metrics::classification_report_for_classes(&cls_db, &cls_out, &[1usize, 1usize])
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn metrics_summary() {
+        let mut cls_db = ClassificationDataset::new(3u8, false);
+        let mut cls_out = ClassificationOutput::<u8, f32>::new(3u8);
+        let labels = [0u8, 0u8, 1u8, 2u8, 2u8, 2u8];
+        let predicted = [0usize, 1usize, 1usize, 2usize, 0usize, 2usize];
+        for (i, (label, predicted)) in labels.iter().zip(predicted.iter()).enumerate() {
+            let name = format!("{}.jpg", i);
+            cls_db.add(&name, &vec![*label]).unwrap();
+            let mut confidences = vec![0.1f32; 3];
+            confidences[*predicted] = 0.8f32;
+            cls_out.add(&name, confidences).unwrap();
+        }
+
+        let report = metrics::classification_report(&cls_db, &cls_out).unwrap();
+        let summary = report.summary();
+        assert_eq!(summary.num_images, report.num_images());
+        assert_eq!(summary.accuracy, report.accuracy());
+        assert_eq!(summary.macro_f1, report.macro_f1());
+        assert_eq!(summary.micro_f1, report.micro_f1());
+        assert_eq!(summary.weighted_f1, report.weighted_f1());
+        assert_eq!(summary.precision, report.precision());
+        assert_eq!(summary.recall, report.recall());
+        assert_eq!(summary.f1, report.f1());
+        assert_eq!(summary.support, vec![2usize, 1usize, 3usize]);
+
+        #[cfg(feature = "serde")]
+        {
+            let json = serde_json::to_value(&summary).unwrap();
+            let mut keys = json
+                .as_object()
+                .unwrap()
+                .keys()
+                .cloned()
+                .collect::<Vec<String>>();
+            keys.sort();
+            assert_eq!(
+                keys,
+                vec![
+                    "accuracy",
+                    "f1",
+                    "macro_f1",
+                    "micro_f1",
+                    "num_images",
+                    "precision",
+                    "recall",
+                    "support",
+                    "weighted_f1"
+                ]
+            );
+            assert_eq!(json["accuracy"], serde_json::json!(report.accuracy()));
+            assert_eq!(json["support"], serde_json::json!([2, 1, 3]));
+            assert_eq!(json["recall"], serde_json::json!(report.recall()));
+            let loaded: crate::metrics::MetricsSummary = serde_json::from_value(json).unwrap();
+            assert_eq!(loaded, summary);
+        }
+    }
+
+    #[test]
+    fn metrics_approx_eq_tolerance() {
+        let sum = (0..10).map(|_| 0.1f64).sum::<f64>();
+        assert_ne!(sum, 1f64);
+        assert!(metrics::metrics_approx_eq(sum, 1f64, 2i64));
+        assert!(metrics::metrics_approx_eq(1f64, sum, 2i64));
+        assert!(metrics::metrics_approx_eq(0.1f64 + 0.2f64, 0.3f64, 1i64));
+
+        let next = f64::from_bits(0.75f64.to_bits() + 3u64);
+        assert!(metrics::metrics_approx_eq(0.75f64, next, 3i64));
+        assert!(!metrics::metrics_approx_eq(0.75f64, next, 2i64));
+        assert!(!metrics::metrics_approx_eq(0.75f64, 0.76f64, 1000i64));
+        assert!(!metrics::metrics_approx_eq(2f64 / 3f64, 0.6667f64, 4i64));
+
+        assert!(metrics::metrics_approx_eq(0f64, -0f64, 0i64));
+        assert!(metrics::metrics_approx_eq(1e-20f64, -1e-20f64, 0i64));
+        assert!(!metrics::metrics_approx_eq(-0.5f64, 0.5f64, i64::MAX));
+        assert!(!metrics::metrics_approx_eq(f64::NAN, f64::NAN, i64::MAX));
+        assert!(metrics::metrics_approx_eq(
+            f64::INFINITY,
+            f64::INFINITY,
+            0i64
+        ));
+    }
+
+    #[test]
+    fn metrics_num_classes_drift() {
+        let mut cls_db = ClassificationDataset::new(10u16, false);
+        let mut cls_out = ClassificationOutput::<u16, f32>::new(11u16);
+        for i in 0..10u16 {
+            let name = format!("{}.jpg", i);
+            cls_db.add(&name, &vec![i]).unwrap();
+            let mut confidences = vec![0f32; 11];
+            confidences[10] = 1f32;
+            cls_out.add(&name, confidences).unwrap();
+        }
+
+        let errors = vec![
+            metrics::top1_accuracy(&cls_db, &cls_out).err().unwrap(),
+            metrics::accuracy(&cls_db, &cls_out).err().unwrap(),
+            metrics::confusion_matrix(&cls_db, &cls_out).err().unwrap(),
+            metrics::classification_report(&cls_db, &cls_out)
+                .err()
+                .unwrap(),
+            metrics::misclassified(&cls_db, &cls_out).err().unwrap(),
+            metrics::mean_reciprocal_rank(&cls_db, &cls_out)
+                .err()
+                .unwrap(),
+            metrics::per_class_log_loss(&cls_db, &cls_out)
+                .err()
+                .unwrap(),
+            metrics::aurc(&cls_db, &cls_out).err().unwrap(),
+            metrics::evaluate(&cls_db, &cls_out).err().unwrap(),
+        ];
+        for err in errors {
+            assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+            let message = err.to_string();
+            assert!(message.contains("10 classes"));
+            assert!(message.contains("11 classes"));
+            assert!(message.contains("background class"));
+        }
+
+        let mut evaluator = metrics::Evaluator::new(10u16, false);
+        assert!(evaluator.update(&[3u16], &[0.1f32; 11]).is_err());
+        assert!(evaluator.update(&[10u16], &[0.1f32; 10]).is_err());
+        assert_eq!(evaluator.num_images(), 0usize);
+
+        let mut cls_out = ClassificationOutput::<u16, f32>::new(10u16);
+        cls_out.add("0.jpg", vec![0.1f32; 10]).unwrap();
+        cls_out.data_mut().get_mut("0.jpg").unwrap().push(0.1f32);
+        let err = metrics::top1_accuracy(&cls_db, &cls_out).unwrap_err();
+        assert!(err.to_string().contains("0.jpg"));
+    }
+
+    #[test]
+    fn classification_fixed_class_output() {
+        use crate::classification::FixedClassOutput;
+
+        let mut cls_out = FixedClassOutput::<4, f32>::with_capacity(2usize);
+        assert!(cls_out.is_empty());
+        cls_out
+            .add("a.jpg", [0.1f32, 0.4f32, 0.2f32, 0.3f32])
+            .unwrap();
+        cls_out
+            .add("b.jpg", [0.5f32, -1f32, 0.5f32, 0.25f32])
+            .unwrap();
+        assert!(cls_out.add("a.jpg", [0f32; 4]).is_err());
+        assert_eq!(cls_out.num_images(), 2usize);
+        assert_eq!(cls_out.num_classes(), 4usize);
+
+        assert_eq!(
+            cls_out.top_k("a.jpg", 4usize).unwrap(),
+            vec![1usize, 3usize, 2usize, 0usize]
+        );
+        assert_eq!(
+            cls_out.top_k("b.jpg", 3usize).unwrap(),
+            vec![0usize, 2usize, 3usize]
+        );
+        assert_eq!(cls_out.argmax("a.jpg").unwrap(), 1usize);
+        assert_eq!(cls_out.argmax("b.jpg").unwrap(), 0usize);
+        assert_eq!(
+            cls_out.confidence_for_image("b.jpg").unwrap(),
+            &[0.5f32, -1f32, 0.5f32, 0.25f32]
+        );
+        assert!(cls_out.top_k("a.jpg", 5usize).is_err());
+        assert!(cls_out.top_k("c.jpg", 1usize).is_err());
+        assert!(cls_out.argmax("c.jpg").is_err());
+        assert_eq!(
+            cls_out.iter().map(|(name, _)| name).collect::<Vec<&str>>(),
+            vec!["a.jpg", "b.jpg"]
+        );
+
+        let dynamic = cls_out.clone().into_classification_output::<u8>().unwrap();
+        for (imagename, _) in cls_out.iter() {
+            assert_eq!(
+                dynamic.topk_for_image(imagename, 4usize).unwrap(),
+                cls_out.top_k(imagename, 4usize).unwrap()
+            );
+        }
+
+        let mut quantized = FixedClassOutput::<3, u8>::new();
+        quantized.add("a.jpg", [7u8, 200u8, 7u8]).unwrap();
+        assert_eq!(
+            quantized.top_k("a.jpg", 3usize).unwrap(),
+            vec![1usize, 0usize, 2usize]
+        );
+    }
+
+    #[test]
+    fn classification_csv_dataset_reader() {
+        use crate::classification::CsvDatasetReader;
+
+        let filename = std::env::temp_dir().join("bagheera_test_csv_dataset_reader.csv");
+        let filename = filename.to_str().unwrap();
+        std::fs::write(filename, "a.jpg,0,3\nb.jpg,2\n\nc.jpg\n").unwrap();
+        let records = CsvDatasetReader::new(filename, 4u8, true)
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(
+            records,
+            vec![
+                ("a.jpg".to_string(), vec![0u8, 3]),
+                ("b.jpg".to_string(), vec![2u8]),
+                ("c.jpg".to_string(), vec![]),
+            ]
+        );
+
+        std::fs::write(filename, "a.jpg,0\nb.jpg,1\n\nc.jpg,x\nd.jpg,2\n").unwrap();
+        let mut reader = CsvDatasetReader::new(filename, 4u8, false).unwrap();
+        assert_eq!(reader.next().unwrap().unwrap().0, "a.jpg");
+        assert_eq!(reader.next().unwrap().unwrap().0, "b.jpg");
+        let err = reader.next().unwrap().unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+        assert!(err.to_string().starts_with("Line 4 of"));
+        assert_eq!(reader.line_num(), 4usize);
+        assert!(reader.next().is_none());
+
+        std::fs::write(filename, "a.jpg,0\nb.jpg,4\n").unwrap();
+        let err = CsvDatasetReader::new(filename, 4u8, false)
+            .unwrap()
+            .nth(1)
+            .unwrap()
+            .unwrap_err();
+        assert!(err.to_string().starts_with("Line 2 of"));
+        std::fs::write(filename, "a.jpg,0,1\n").unwrap();
+        assert!(CsvDatasetReader::new(filename, 4u8, false)
+            .unwrap()
+            .next()
+            .unwrap()
+            .is_err());
+        std::fs::remove_file(filename).unwrap();
+    }
+
+    #[test]
+    fn classification_output_index() {
+        let mut cls_out = ClassificationOutput::<u8, f64>::new(3u8);
+        cls_out.add("a.jpg", vec![0.5f64, 0.25, 0.25]).unwrap();
+        assert_eq!(&cls_out["a.jpg"], &[0.5f64, 0.25, 0.25]);
+        assert_eq!(cls_out["a.jpg"][0], 0.5f64);
+        assert_eq!(
+            &cls_out["a.jpg"],
+            cls_out.confidence_for_image("a.jpg").unwrap().as_slice()
+        );
+
+        let result = std::panic::catch_unwind(|| cls_out["missing.jpg"].len());
+        let payload = result.unwrap_err();
+        let message = payload.downcast_ref::<String>().unwrap();
+        assert!(message.contains("missing.jpg"));
+    }
+
+    #[test]
+    fn classification_output_merge() {
+        let mut cls_out = ClassificationOutput::<u8, f32>::new(2u8);
+        cls_out.add("a.jpg", vec![0.25f32, 0.75]).unwrap();
+        let mut shard = ClassificationOutput::<u8, f32>::new(2u8);
+        shard.add("b.jpg", vec![0.5f32, 0.5]).unwrap();
+        shard.add("c.jpg", vec![1f32, 0f32]).unwrap();
+        cls_out.merge(shard).unwrap();
+        assert_eq!(cls_out.num_images(), 3usize);
+        assert_eq!(&cls_out["c.jpg"], &[1f32, 0f32]);
+
+        let mut overlapping = ClassificationOutput::<u8, f32>::new(2u8);
+        overlapping.add("d.jpg", vec![0.5f32, 0.5]).unwrap();
+        overlapping.add("b.jpg", vec![0f32, 1f32]).unwrap();
+        let err = cls_out.merge(overlapping).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+        assert!(err.to_string().contains("b.jpg"));
+        assert_eq!(cls_out.num_images(), 3usize);
+        assert!(!cls_out.image_is_present("d.jpg"));
+        assert_eq!(&cls_out["b.jpg"], &[0.5f32, 0.5]);
+
+        let mut other = ClassificationOutput::<u8, f32>::new(3u8);
+        other.add("e.jpg", vec![0.5f32, 0.25, 0.25]).unwrap();
+        assert!(cls_out.merge(other.clone()).is_err());
+        let mut inferred = ClassificationOutput::<u8, f32>::new_inferred();
+        inferred.merge(other).unwrap();
+        assert_eq!(inferred.num_classes(), 3u8);
+        assert_eq!(inferred.num_images(), 1usize);
+    }
+
+    #[test]
+    fn metrics_agreement_rate() {
+        let mut a = ClassificationOutput::<u8, f64>::new(3u8);
+        a.add("a.jpg", vec![0.7f64, 0.2, 0.1]).unwrap();
+        a.add("b.jpg", vec![0.1f64, 0.8, 0.1]).unwrap();
+        a.add("c.jpg", vec![0.2f64, 0.2, 0.6]).unwrap();
+        a.add("d.jpg", vec![0.5f64, 0.5, 0.0]).unwrap();
+        a.add("only_a.jpg", vec![1f64, 0.0, 0.0]).unwrap();
+
+        let mut b = ClassificationOutput::<u8, f64>::new(3u8);
+        b.add("a.jpg", vec![0.4f64, 0.3, 0.3]).unwrap();
+        b.add("b.jpg", vec![0.6f64, 0.3, 0.1]).unwrap();
+        b.add("c.jpg", vec![0.1f64, 0.1, 0.8]).unwrap();
+        b.add("d.jpg", vec![0.9f64, 0.1, 0.0]).unwrap();
+        b.add("only_b.jpg", vec![0f64, 1.0, 0.0]).unwrap();
+
+        assert!(metrics::metrics_approx_eq(
+            metrics::agreement_rate(&a, &b).unwrap(),
+            0.75f64,
+            2i64
+        ));
+        assert_eq!(metrics::agreement_rate(&a, &a).unwrap(), 1f64);
+
+        let mut disjoint = ClassificationOutput::<u8, f64>::new(3u8);
+        disjoint.add("e.jpg", vec![1f64, 0.0, 0.0]).unwrap();
+        assert!(metrics::agreement_rate(&a, &disjoint).is_err());
+        let other = ClassificationOutput::<u8, f64>::new(4u8);
+        assert!(metrics::agreement_rate(&a, &other).is_err());
+    }
+
+    #[test]
+    fn classification_dataset_cardinality_stats() {
+        let mut cls_db = ClassificationDataset::new(5u8, true);
+        assert_eq!(cls_db.cardinality_stats().max(), 0usize);
+
+        cls_db.add("a.jpg", &vec![0u8]).unwrap();
+        cls_db.add("b.jpg", &vec![0u8, 1, 2, 3]).unwrap();
+        cls_db.add("c.jpg", &vec![]).unwrap();
+        cls_db.add("d.jpg", &vec![1u8, 4]).unwrap();
+        cls_db.add("e.jpg", &vec![2u8, 3, 4]).unwrap();
+        let stats = cls_db.cardinality_stats();
+        assert_eq!(stats.min(), 0usize);
+        assert_eq!(stats.max(), 4usize);
+        assert!(metrics::metrics_approx_eq(stats.mean(), 2f64, 2i64));
+        assert_eq!(stats.median(), 2f64);
+
+        cls_db.add("f.jpg", &vec![0u8, 1, 2, 3, 4]).unwrap();
+        let stats = cls_db.cardinality_stats();
+        assert_eq!(stats.max(), 5usize);
+        assert_eq!(stats.median(), 2.5f64);
+    }
+
+    #[test]
+    fn classification_output_write_detailed_csv() {
+        let mut cls_out = ClassificationOutput::<u8, f64>::new(4u8);
+        cls_out.add("b.jpg", vec![0.1f64, 0.2, 0.3, 0.4]).unwrap();
+        cls_out
+            .add("a.jpg", vec![0.5f64, 0.25, 0.125, 0.125])
+            .unwrap();
+        let filename = std::env::temp_dir().join("bagheera_test_write_detailed_csv.csv");
+        let filename = filename.to_str().unwrap();
+
+        cls_out.write_detailed_csv(filename, 3usize).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(filename).unwrap(),
+            "imagename,top1_class,top1_conf,topk_classes\n\
+             a.jpg,0,0.5,0 1 2\n\
+             b.jpg,3,0.4,3 2 1\n"
+        );
+        let err = cls_out.write_detailed_csv(filename, 0usize).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+        assert_eq!(
+            err.to_string(),
+            crate::errors::topk_incorrect_k(0usize, 4usize).to_string()
+        );
+        assert!(cls_out.write_detailed_csv(filename, 5usize).is_err());
+        std::fs::remove_file(filename).unwrap();
+    }
+
+    #[test]
+    fn utils_top_k_iter() {
+        use crate::utils::{top_k_iter, TopK};
+
+        let mut rng = rand::thread_rng();
+        for len in [1usize, 2, 7, 100] {
+            let values: Vec<f64> = (0..len)
+                .map(|_| (rng.gen_range(0u8..10u8) as f64) / 4f64)
+                .collect();
+            for k in 0usize..=len {
+                assert_eq!(
+                    top_k_iter(values.iter().copied(), k).unwrap(),
+                    values.top_k(k).unwrap()
+                );
+            }
+        }
+        let streamed = (0u32..50u32).map(|x| (x * 17u32) % 23u32);
+        let collected: Vec<u32> = streamed.clone().collect();
+        assert_eq!(
+            top_k_iter(streamed, 10usize).unwrap(),
+            collected.top_k(10usize).unwrap()
+        );
+        assert!(top_k_iter(std::iter::empty::<f32>(), 1usize).is_err());
+        assert!(top_k_iter(vec![1i8, 2i8].into_iter(), 3usize).is_err());
+    }
+
+    #[test]
+    fn classification_output_confidence_histogram() {
+        let mut cls_out = ClassificationOutput::<u8, f64>::new(2u8);
+        for i in 0usize..1000usize {
+            let confidence = (i as f64 + 0.5f64) / 1000f64;
+            cls_out
+                .add(&format!("{}.jpg", i), vec![confidence, 1f64 - confidence])
+                .unwrap();
+        }
+        let histogram = cls_out.confidence_histogram(0usize, 10usize).unwrap();
+        assert_eq!(histogram.iter().sum::<usize>(), 1000usize);
+        assert!(histogram.iter().all(|count| *count == 100usize));
+        assert_eq!(
+            cls_out.confidence_histogram(1usize, 1usize).unwrap(),
+            vec![1000usize]
+        );
+
+        let mut rng = rand::thread_rng();
+        let mut random = ClassificationOutput::<u8, f32>::new(1u8);
+        for i in 0usize..10000usize {
+            random
+                .add(&format!("{}.jpg", i), vec![rng.gen_range(0f32..1f32)])
+                .unwrap();
+        }
+        let histogram = random.confidence_histogram(0usize, 4usize).unwrap();
+        assert!(histogram
+            .iter()
+            .all(|count| *count > 2000usize && *count < 3000usize));
+
+        assert!(cls_out.confidence_histogram(2usize, 10usize).is_err());
+        assert!(cls_out.confidence_histogram(0usize, 0usize).is_err());
+        cls_out.add("nan.jpg", vec![f64::NAN, 0f64]).unwrap();
+        assert!(cls_out.confidence_histogram(0usize, 10usize).is_err());
+    }
+
+    #[test]
+    fn classification_dataset_diff() {
+        use crate::utils::FromOneHot;
+
+        let mut old = ClassificationDataset::new(4u8, true);
+        old.add("a.jpg", &vec![0u8, 2]).unwrap();
+        old.add("b.jpg", &vec![1u8]).unwrap();
+        old.add("c.jpg", &vec![]).unwrap();
+        let mut new = old.clone();
+        assert!(old.diff(&new).is_empty());
+
+        new.add("d.jpg", &vec![3u8]).unwrap();
+        let mut relabelled = ClassificationDataset::new(4u8, true);
+        for imagename in ["a.jpg", "c.jpg", "d.jpg"] {
+            relabelled
+                .add(
+                    imagename,
+                    &new.get_gt(imagename).unwrap().from_one_hot::<u8>(),
+                )
+                .unwrap();
+        }
+        relabelled.add("b.jpg", &vec![1u8, 3]).unwrap();
+
+        let diff = old.diff(&relabelled);
+        assert_eq!(diff.added(), &["d.jpg".to_string()]);
+        assert!(diff.removed().is_empty());
+        assert_eq!(diff.changed(), &["b.jpg".to_string()]);
+        assert!(!diff.is_empty());
+
+        let reverse = relabelled.diff(&old);
+        assert!(reverse.added().is_empty());
+        assert_eq!(reverse.removed(), &["d.jpg".to_string()]);
+        assert_eq!(reverse.changed(), &["b.jpg".to_string()]);
+    }
+
+    #[test]
+    fn metrics_mean_average_precision() {
+        let mut cls_db = ClassificationDataset::new(2u8, true);
+        cls_db.add("a.jpg", &vec![0u8]).unwrap();
+        cls_db.add("b.jpg", &vec![0u8]).unwrap();
+        cls_db.add("c.jpg", &vec![1u8]).unwrap();
+        cls_db.add("d.jpg", &vec![1u8]).unwrap();
+        let mut cls_out = ClassificationOutput::<u8, f64>::new(2u8);
+        cls_out.add("a.jpg", vec![0.9f64, 0.1]).unwrap();
+        cls_out.add("b.jpg", vec![0.7f64, 0.3]).unwrap();
+        cls_out.add("c.jpg", vec![0.8f64, 0.2]).unwrap();
+        cls_out.add("d.jpg", vec![0.1f64, 0.9]).unwrap();
+
+        let macro_map = metrics::mean_average_precision(&cls_db, &cls_out, false).unwrap();
+        let weighted_map = metrics::mean_average_precision(&cls_db, &cls_out, true).unwrap();
+        assert!(metrics::metrics_approx_eq(macro_map, 5f64 / 6f64, 2i64));
+        assert!(metrics::metrics_approx_eq(macro_map, weighted_map, 2i64));
+
+        cls_db = ClassificationDataset::new(2u8, true);
+        cls_db.add("a.jpg", &vec![0u8]).unwrap();
+        cls_db.add("b.jpg", &vec![0u8]).unwrap();
+        cls_db.add("c.jpg", &vec![0u8]).unwrap();
+        cls_db.add("d.jpg", &vec![1u8]).unwrap();
+        cls_out = ClassificationOutput::<u8, f64>::new(2u8);
+        cls_out.add("a.jpg", vec![0.9f64, 0.8]).unwrap();
+        cls_out.add("b.jpg", vec![0.8f64, 0.1]).unwrap();
+        cls_out.add("c.jpg", vec![0.7f64, 0.2]).unwrap();
+        cls_out.add("d.jpg", vec![0.1f64, 0.7]).unwrap();
+        let macro_map = metrics::mean_average_precision(&cls_db, &cls_out, false).unwrap();
+        let weighted_map = metrics::mean_average_precision(&cls_db, &cls_out, true).unwrap();
+        assert!(metrics::metrics_approx_eq(macro_map, 0.75f64, 2i64));
+        assert!(metrics::metrics_approx_eq(weighted_map, 0.875f64, 2i64));
+
+        let mut tied = ClassificationOutput::<u8, f64>::new(2u8);
+        for imagename in ["a.jpg", "b.jpg", "c.jpg", "d.jpg"] {
+            tied.add(imagename, vec![0.5f64, 0.5]).unwrap();
+        }
+        let tied_map = metrics::mean_average_precision(&cls_db, &tied, false).unwrap();
+        assert!(metrics::metrics_approx_eq(tied_map, 0.5f64, 2i64));
+
+        let mut unlabelled = ClassificationDataset::new(2u8, true);
+        unlabelled.add("a.jpg", &vec![]).unwrap();
+        assert!(metrics::mean_average_precision(&unlabelled, &cls_out, false).is_err());
+    }
+
+    #[test]
+    fn metrics_trait_objects() {
+        use crate::metrics::{
+            MacroF1, MeanAveragePrecision, Metric, MicroF1, Top1Accuracy, TopKAccuracy, WeightedF1,
+        };
+
+        let mut cls_db = ClassificationDataset::new(3u8, false);
+        cls_db.add("a.jpg", &vec![0u8]).unwrap();
+        cls_db.add("b.jpg", &vec![1u8]).unwrap();
+        cls_db.add("c.jpg", &vec![2u8]).unwrap();
+        cls_db.add("d.jpg", &vec![2u8]).unwrap();
+        let mut cls_out = ClassificationOutput::<u8, f64>::new(3u8);
+        cls_out.add("a.jpg", vec![0.7f64, 0.2, 0.1]).unwrap();
+        cls_out.add("b.jpg", vec![0.5f64, 0.4, 0.1]).unwrap();
+        cls_out.add("c.jpg", vec![0.1f64, 0.2, 0.7]).unwrap();
+        cls_out.add("d.jpg", vec![0.2f64, 0.5, 0.3]).unwrap();
+
+        let metrics: Vec<Box<dyn Metric<u8, f64>>> = vec![
+            Box::new(Top1Accuracy),
+            Box::new(TopKAccuracy::new(2usize)),
+            Box::new(MacroF1),
+            Box::new(MicroF1),
+            Box::new(WeightedF1),
+            Box::new(MeanAveragePrecision),
+        ];
+        let report = metrics::classification_report(&cls_db, &cls_out).unwrap();
+        let expected = [
+            (
+                "top1_accuracy",
+                metrics::top1_accuracy(&cls_db, &cls_out).unwrap(),
+            ),
+            (
+                "top2_accuracy",
+                metrics::topk_accuracy(&cls_db, &cls_out, 2usize).unwrap(),
+            ),
+            ("macro_f1", report.macro_f1()),
+            ("micro_f1", report.micro_f1()),
+            ("weighted_f1", report.weighted_f1()),
+            (
+                "mean_average_precision",
+                metrics::mean_average_precision(&cls_db, &cls_out, false).unwrap(),
+            ),
+        ];
+        for (metric, (name, value)) in metrics.iter().zip(expected.iter()) {
+            assert_eq!(metric.name(), *name);
+            assert!(metrics::metrics_approx_eq(
+                metric.compute(&cls_db, &cls_out).unwrap(),
+                *value,
+                2i64
+            ));
+        }
+        assert!(metrics::metrics_approx_eq(
+            metrics[0].compute(&cls_db, &cls_out).unwrap(),
+            0.5f64,
+            2i64
+        ));
+
+        let multilabel = ClassificationDataset::new(3u8, true);
+        assert!(metrics[2].compute(&multilabel, &cls_out).is_err());
+    }
+
+    #[test]
+    fn classification_output_clip() {
+        let mut cls_out = ClassificationOutput::<u8, f64>::new(4u8);
+        cls_out.add("a.jpg", vec![-0.5f64, 0.0, 0.5, 1.5]).unwrap();
+        cls_out
+            .add("b.jpg", vec![0.25f64, 0.75, 1.0, 0.125])
+            .unwrap();
+        let original = cls_out.clone();
+
+        assert_eq!(
+            cls_out.clip_for_image("a.jpg", 0.125f64, 0.875f64).unwrap(),
+            vec![0.125f64, 0.125, 0.5, 0.875]
+        );
+        assert_eq!(
+            cls_out.clip_for_image("b.jpg", 0.125f64, 0.875f64).unwrap(),
+            vec![0.25f64, 0.75, 0.875, 0.125]
+        );
+        assert!(cls_out.clip_for_image("c.jpg", 0f64, 1f64).is_err());
+        assert!(cls_out.clip_for_image("a.jpg", 1f64, 0f64).is_err());
+        assert_eq!(cls_out, original);
+
+        assert!(cls_out.clip_all(1f64, 0f64).is_err());
+        assert_eq!(cls_out, original);
+        cls_out.clip_all(0f64, 1f64).unwrap();
+        assert_eq!(&cls_out["a.jpg"], &[0f64, 0.0, 0.5, 1.0]);
+        assert_eq!(&cls_out["b.jpg"], &original["b.jpg"]);
+        cls_out.clip_all(0.5f64, 0.5f64).unwrap();
+        assert!(cls_out["b.jpg"].iter().all(|x| *x == 0.5f64));
+    }
+
+    #[test]
+    fn metrics_datasets_as_predictions() {
+        let mut cls_db = ClassificationDataset::new(3u8, false);
+        let mut cls_out = ClassificationOutput::<u8, f64>::new(3u8);
+        let mut rng = rand::thread_rng();
+        for i in 0usize..50usize {
+            let imagename = format!("{}.jpg", i);
+            cls_db
+                .add(&imagename, &vec![rng.gen_range(0u8..3u8)])
+                .unwrap();
+            let confidences: Vec<f64> = (0..3).map(|_| rng.gen_range(0f64..1f64)).collect();
+            cls_out.add(&imagename, confidences).unwrap();
+        }
+        let predicted = cls_out.to_label_dataset().unwrap();
+        assert!(metrics::metrics_approx_eq(
+            metrics::accuracy_datasets(&cls_db, &predicted).unwrap(),
+            metrics::top1_accuracy(&cls_db, &cls_out).unwrap(),
+            2i64
+        ));
+        let cm = metrics::confusion_matrix_datasets(&cls_db, &predicted).unwrap();
+        let expected = metrics::confusion_matrix(&cls_db, &cls_out).unwrap();
+        for true_class in 0usize..3usize {
+            for predicted_class in 0usize..3usize {
+                assert_eq!(
+                    cm.count(true_class, predicted_class),
+                    expected.count(true_class, predicted_class)
+                );
+            }
+        }
+        assert_eq!(metrics::accuracy_datasets(&cls_db, &cls_db).unwrap(), 1f64);
+
+        let mut multilabel = ClassificationDataset::new(3u8, true);
+        multilabel.add("a.jpg", &vec![0u8, 2]).unwrap();
+        multilabel.add("b.jpg", &vec![1u8]).unwrap();
+        let mut predicted = ClassificationDataset::new(3u8, true);
+        predicted.add("a.jpg", &vec![0u8, 2]).unwrap();
+        predicted.add("b.jpg", &vec![1u8, 2]).unwrap();
+        predicted.add("c.jpg", &vec![]).unwrap();
+        assert_eq!(
+            metrics::accuracy_datasets(&multilabel, &predicted).unwrap(),
+            0.5f64
+        );
+        assert!(metrics::accuracy_datasets(&cls_db, &predicted).is_err());
+        assert!(metrics::confusion_matrix_datasets(&multilabel, &predicted).is_err());
+        let other = ClassificationDataset::new(4u8, false);
+        assert!(metrics::confusion_matrix_datasets(&cls_db, &other).is_err());
+    }
+
+    #[test]
+    fn utils_sparse_one_hot() {
+        use crate::utils::{from_sparse_one_hot, FromOneHot, ToOneHot};
+
+        let mut rng = rand::thread_rng();
+        for _ in 0..100 {
+            let labels: Vec<u32> = (0..rng.gen_range(0usize..20usize))
+                .map(|_| rng.gen_range(0u32..1000u32))
+                .collect();
+            let dense = labels.convert(1000u32);
+            let sparse = labels.to_sparse_one_hot(1000u32);
+            assert!(sparse.windows(2).all(|pair| pair[0] < pair[1]));
+            assert_eq!(from_sparse_one_hot(&sparse, 1000usize), dense);
+            assert_eq!(
+                sparse,
+                dense
+                    .from_one_hot::<u32>()
+                    .iter()
+                    .map(|label| *label as usize)
+                    .collect::<Vec<usize>>()
+            );
+        }
+        assert_eq!(2u16.to_sparse_one_hot(3u16), vec![2usize]);
+        assert!(std::panic::catch_unwind(|| vec![3u8].to_sparse_one_hot(3u8)).is_err());
+        assert!(std::panic::catch_unwind(|| from_sparse_one_hot(&[3usize], 3usize)).is_err());
+    }
+
+    #[test]
+    fn classification_csv_options() {
+        use crate::classification::{CsvDatasetReader, CsvOptions};
+
+        assert!(CsvOptions::new(b',', ',').is_err());
+        assert!(CsvOptions::new(0xe9u8, '|').is_err());
+        let options = CsvOptions::new(b'\t', '|').unwrap();
+
+        let filename = std::env::temp_dir().join("bagheera_test_csv_options.tsv");
+        let filename = filename.to_str().unwrap();
+        std::fs::write(filename, "a,1.jpg\t0|3\nb.jpg\t2\n\nc.jpg\t1|2\t0\nd.jpg\n").unwrap();
+        let records = CsvDatasetReader::with_options(filename, 4u8, true, options)
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(
+            records,
+            vec![
+                ("a,1.jpg".to_string(), vec![0u8, 3]),
+                ("b.jpg".to_string(), vec![2u8]),
+                ("c.jpg".to_string(), vec![1u8, 2, 0]),
+                ("d.jpg".to_string(), vec![]),
+            ]
+        );
+        std::fs::write(filename, "a.jpg,0;3\n").unwrap();
+        let records = CsvDatasetReader::new(filename, 4u8, true)
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(records, vec![("a.jpg".to_string(), vec![0u8, 3])]);
+
+        std::fs::write(filename, "a.jpg\t0.25\t0.75\nb.jpg\t1\t0\n").unwrap();
+        let cls_out =
+            ClassificationOutput::<u8, f64>::from_csv_file_with_options(filename, 2u8, &options)
+                .unwrap();
+        assert_eq!(&cls_out["a.jpg"], &[0.25f64, 0.75]);
+        assert_eq!(&cls_out["b.jpg"], &[1f64, 0f64]);
+
+        cls_out
+            .write_detailed_csv_with_options(filename, 2usize, &options)
+            .unwrap();
+        assert_eq!(
+            std::fs::read_to_string(filename).unwrap(),
+            "imagename\ttop1_class\ttop1_conf\ttopk_classes\n\
+             a.jpg\t1\t0.75\t1|0\n\
+             b.jpg\t0\t1\t0|1\n"
+        );
+        std::fs::remove_file(filename).unwrap();
+    }
+
+    #[test]
+    fn classification_dataset_most_frequent_classes() {
+        let mut cls_db = ClassificationDataset::new(6u16, false);
+        let labels = [3u16, 3, 3, 3, 3, 1, 1, 1, 5, 5, 0, 4];
+        for (i, label) in labels.iter().enumerate() {
+            cls_db.add(&format!("{}.jpg", i), &vec![*label]).unwrap();
+        }
+        assert_eq!(
+            cls_db.most_frequent_classes(3usize),
+            vec![(3u16, 5usize), (1u16, 3usize), (5u16, 2usize)]
+        );
+        assert_eq!(
+            cls_db.most_frequent_classes(10usize),
+            vec![
+                (3u16, 5usize),
+                (1u16, 3usize),
+                (5u16, 2usize),
+                (0u16, 1usize),
+                (4u16, 1usize),
+                (2u16, 0usize)
+            ]
+        );
+        assert!(cls_db.most_frequent_classes(0usize).is_empty());
+    }
+
+    #[test]
+    fn metrics_rank_weighted_accuracy() {
+        let mut cls_db = ClassificationDataset::new(5u8, false);
+        let mut cls_out = ClassificationOutput::<u8, f32>::new(5u8);
+        let mut rng = rand::thread_rng();
+        for i in 0usize..200usize {
+            let imagename = format!("{}.jpg", i);
+            cls_db
+                .add(&imagename, &vec![rng.gen_range(0u8..5u8)])
+                .unwrap();
+            let confidences: Vec<f32> = (0..5).map(|_| rng.gen_range(0u8..4u8) as f32).collect();
+            cls_out.add(&imagename, confidences).unwrap();
+        }
+        assert!(metrics::metrics_approx_eq(
+            metrics::rank_weighted_accuracy(&cls_db, &cls_out, 1usize).unwrap(),
+            metrics::top1_accuracy(&cls_db, &cls_out).unwrap(),
+            2i64
+        ));
+        for k in 2usize..=5usize {
+            let rank_weighted = metrics::rank_weighted_accuracy(&cls_db, &cls_out, k).unwrap();
+            assert!(rank_weighted >= metrics::top1_accuracy(&cls_db, &cls_out).unwrap());
+            assert!(rank_weighted <= metrics::topk_accuracy(&cls_db, &cls_out, k).unwrap());
+        }
+        assert!(metrics::metrics_approx_eq(
+            metrics::rank_weighted_accuracy(&cls_db, &cls_out, 5usize).unwrap(),
+            metrics::mean_reciprocal_rank(&cls_db, &cls_out).unwrap(),
+            8i64
+        ));
+        assert!(metrics::rank_weighted_accuracy(&cls_db, &cls_out, 0usize).is_err());
+        assert!(metrics::rank_weighted_accuracy(&cls_db, &cls_out, 6usize).is_err());
+    }
+
+    #[test]
+    fn classification_dataset_add_or_replace() {
+        let mut cls_db = ClassificationDataset::new(4u8, true);
+        assert_eq!(cls_db.add_or_replace("a.jpg", &vec![0u8, 2]).unwrap(), None);
+        assert_eq!(
+            cls_db.add_or_replace("a.jpg", &vec![3u8, 1]).unwrap(),
+            Some(vec![0u8, 2])
+        );
+        assert_eq!(
+            cls_db.get_gt("a.jpg").unwrap(),
+            &vec![false, true, false, true]
+        );
+        assert_eq!(
+            cls_db.add_or_replace("a.jpg", &vec![]).unwrap(),
+            Some(vec![1u8, 3])
+        );
+        assert!(cls_db.add_or_replace("a.jpg", &vec![4u8]).is_err());
+        assert_eq!(cls_db.get_gt("a.jpg").unwrap(), &vec![false; 4]);
+        assert_eq!(cls_db.num_images(), 1usize);
+
+        let mut single = ClassificationDataset::new(4u8, false);
+        single.add("b.jpg", &vec![1u8]).unwrap();
+        assert!(single.add_or_replace("b.jpg", &vec![0u8, 2]).is_err());
+        assert!(single.add_or_replace("b.jpg", &vec![]).is_err());
+        assert_eq!(
+            single.add_or_replace("b.jpg", &vec![2u8]).unwrap(),
+            Some(vec![1u8])
+        );
+        assert!(single.add("b.jpg", &vec![3u8]).is_err());
+    }
+
+    #[test]
+    fn metrics_confidence_gap() {
+        let mut cls_db = ClassificationDataset::new(3u8, false);
+        cls_db.add("a.jpg", &vec![0u8]).unwrap();
+        cls_db.add("b.jpg", &vec![2u8]).unwrap();
+        cls_db.add("c.jpg", &vec![1u8]).unwrap();
+        cls_db.add("only_gt.jpg", &vec![1u8]).unwrap();
+        let mut cls_out = ClassificationOutput::<u8, f64>::new(3u8);
+        cls_out.add("a.jpg", vec![0.5f64, 0.25, 0.25]).unwrap();
+        cls_out.add("b.jpg", vec![0.875f64, 0.0, 0.125]).unwrap();
+        cls_out.add("c.jpg", vec![0.25f64, 0.5, 0.25]).unwrap();
+
+        let gaps = metrics::confidence_gap(&cls_db, &cls_out).unwrap();
+        assert_eq!(
+            gaps,
+            vec![
+                ("a.jpg".to_string(), 0f64),
+                ("b.jpg".to_string(), 0.75f64),
+                ("c.jpg".to_string(), 0f64),
+            ]
+        );
+        let correctness = metrics::correctness_vector(&cls_db, &cls_out).unwrap();
+        for ((imagename, gap), (other, correct)) in gaps.iter().zip(correctness.iter()) {
+            assert_eq!(imagename, other);
+            assert_eq!(*gap > 0f64, !*correct);
+        }
+
+        let mut quantized = ClassificationOutput::<u8, u8>::new(3u8);
+        quantized.add("b.jpg", vec![200u8, 10, 40]).unwrap();
+        assert_eq!(
+            metrics::confidence_gap(&cls_db, &quantized).unwrap(),
+            vec![("b.jpg".to_string(), 160u8)]
+        );
+        assert!(metrics::confidence_gap(&ClassificationDataset::new(3u8, true), &cls_out).is_err());
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn metrics_par_classification_report_parity() {
+        let mut cls_db = ClassificationDataset::new(20u16, false);
+        let mut cls_out = ClassificationOutput::<u16, f32>::new(20u16);
+        let mut rng = rand::thread_rng();
+        for i in 0..20000 {
+            let imagename = format!("{}.jpg", i);
+            cls_db
+                .add(&imagename, &vec![rng.gen_range(0u16..20u16)])
+                .unwrap();
+            if i % 10 != 0 {
+                let confidences: Vec<f32> =
+                    (0..20).map(|_| rng.gen_range(0u8..8u8) as f32).collect();
+                cls_out.add(&imagename, confidences).unwrap();
+            }
+        }
+        let serial = metrics::classification_report(&cls_db, &cls_out).unwrap();
+        let parallel = metrics::par_classification_report(&cls_db, &cls_out).unwrap();
+        assert_eq!(parallel.num_images(), 18000usize);
+        assert_eq!(parallel.summary(), serial.summary());
+        assert_eq!(parallel.precision(), serial.precision());
+        assert_eq!(parallel.support(), serial.support());
+
+        let multilabel = ClassificationDataset::new(20u16, true);
+        assert!(metrics::par_classification_report(&multilabel, &cls_out).is_err());
+    }
+
+    #[test]
+    fn utils_nonan_total_order() {
+        use crate::utils::NoNaN;
+        use std::cmp::Ordering;
+
+        let values = [
+            f64::NEG_INFINITY,
+            -f64::MAX,
+            -1e300f64,
+            -2.5f64,
+            -f64::MIN_POSITIVE,
+            -0f64,
+            0f64,
+            f64::MIN_POSITIVE,
+            1f64,
+            1f64,
+            2.5f64,
+            1e300f64,
+            f64::MAX,
+            f64::INFINITY,
+        ];
+        assert!(NoNaN::new(f64::NAN).is_none());
+        let keys: Vec<NoNaN<f64>> = values.iter().map(|x| NoNaN::new(*x).unwrap()).collect();
+        for a in &keys {
+            assert_eq!(a.cmp(a), Ordering::Equal);
+            for b in &keys {
+                assert_eq!(a.partial_cmp(b), Some(a.cmp(b)));
+                assert_eq!(a == b, a.cmp(b) == Ordering::Equal);
+                assert_eq!(a.cmp(b), b.cmp(a).reverse());
+                assert_eq!(a.cmp(b), a.value().partial_cmp(&b.value()).unwrap());
+                for c in &keys {
+                    if a <= b && b <= c {
+                        assert!(a <= c);
+                    }
+                }
+            }
+        }
+
+        let mut rng = rand::thread_rng();
+        let mut shuffled: Vec<NoNaN<f64>> = (0..1000)
+            .map(|_| {
+                let value = *values
+                    .get(rng.gen_range(0usize..values.len() + 1usize))
+                    .unwrap_or(&rng.gen_range(-1e6f64..1e6f64));
+                NoNaN::new(value).unwrap()
+            })
+            .collect();
+        shuffled.sort();
+        assert!(shuffled.windows(2).all(|pair| pair[0] <= pair[1]));
+        shuffled.sort_unstable_by(|a, b| b.cmp(a));
+        assert!(shuffled.windows(2).all(|pair| pair[0] >= pair[1]));
+    }
+
+    #[test]
+    fn utils_to_weighted_one_hot() {
+        use crate::utils::ToOneHot;
+        use std::collections::HashMap;
+
+        let weights: HashMap<u16, f64> = [(1u16, 0.25f64), (4u16, 3f64), (5u16, 0.5f64)]
+            .iter()
+            .copied()
+            .collect();
+        let labels = vec![4u16, 0, 1];
+        let one_hot = labels.to_weighted_one_hot(6u16, &weights).unwrap();
+        assert_eq!(one_hot, vec![1f64, 0.25, 0.0, 0.0, 3.0, 0.0]);
+        for (weight, is_label) in one_hot.iter().zip(labels.convert(6u16).iter()) {
+            assert_eq!(*weight != 0f64, *is_label);
+        }
+        assert_eq!(
+            5u16.to_weighted_one_hot(6u16, &weights).unwrap(),
+            vec![0f64, 0.0, 0.0, 0.0, 0.0, 0.5]
+        );
+        assert_eq!(
+            Vec::<u16>::new()
+                .to_weighted_one_hot(3u16, &HashMap::new())
+                .unwrap(),
+            vec![0f64; 3]
+        );
+        let err = vec![0u16, 6]
+            .to_weighted_one_hot(6u16, &weights)
+            .unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn classification_dataset_support() {
+        use crate::classification::{ClassificationDataset, ClassificationOutput};
+        use crate::metrics::classification_report;
+
+        let mut cls_db = ClassificationDataset::new(4u8, false);
+        let mut cls_out = ClassificationOutput::<u8, f32>::new(4u8);
+        for (i, label) in [0u8, 3, 3, 1, 3, 0].iter().enumerate() {
+            let imagename = format!("{}.jpg", i);
+            cls_db.add(&imagename, &vec![*label]).unwrap();
+            cls_out
+                .add(&imagename, vec![0.1f32, 0.2, 0.3, 0.4])
+                .unwrap();
+        }
+        assert_eq!(cls_db.support(), cls_db.class_counts());
+        assert_eq!(cls_db.support(), vec![2usize, 1, 0, 3]);
+        let report = classification_report(&cls_db, &cls_out).unwrap();
+        assert_eq!(report.support(), cls_db.support().as_slice());
+
+        let mut multilabel = ClassificationDataset::new(3u8, true);
+        multilabel.add("a.jpg", &vec![0u8, 2]).unwrap();
+        multilabel.add("b.jpg", &vec![2u8]).unwrap();
+        assert_eq!(multilabel.support(), multilabel.class_counts());
+    }
+
+    #[test]
+    fn classification_output_is_probability_distribution() {
+        use crate::classification::ClassificationOutput;
+
+        let logits = [
+            ("india.jpg", vec![2.0f64, 1.0, 0.1]),
+            ("iran.jpg", vec![-0.5f64, 0.3, 1.2]),
+        ];
+        let mut cls_logits = ClassificationOutput::<u8, f64>::new(3u8);
+        let mut cls_probs = ClassificationOutput::<u8, f64>::new(3u8);
+        for (imagename, logit) in logits.iter() {
+            let exps = logit.iter().map(|x| x.exp()).collect::<Vec<f64>>();
+            let total = exps.iter().sum::<f64>();
+            cls_probs
+                .add(imagename, exps.iter().map(|x| x / total).collect())
+                .unwrap();
+            cls_logits.add(imagename, logit.clone()).unwrap();
+        }
+        assert!(cls_probs.is_probability_distribution(1e-9f64));
+        assert!(!cls_logits.is_probability_distribution(1e-9f64));
+        assert!(ClassificationOutput::<u8, f64>::new(3u8).is_probability_distribution(0f64));
+
+        let mut cls_out = ClassificationOutput::<u8, f32>::new(2u8);
+        cls_out.add("a.jpg", vec![0.5f32, 0.52]).unwrap();
+        assert!(!cls_out.is_probability_distribution(1e-3f32));
+        assert!(cls_out.is_probability_distribution(0.05f32));
+        cls_out.add("b.jpg", vec![1.2f32, -0.2]).unwrap();
+        assert!(!cls_out.is_probability_distribution(0.05f32));
+        let mut cls_nan = ClassificationOutput::<u8, f32>::new(2u8);
+        cls_nan.add("c.jpg", vec![f32::NAN, 1.0]).unwrap();
+        assert!(!cls_nan.is_probability_distribution(1f32));
+    }
+
+    #[test]
+    fn classification_output_top1_confidences() {
+        use crate::classification::ClassificationOutput;
+
+        let mut cls_out = ClassificationOutput::<u8, f64>::new(4u8);
+        cls_out.add("a.jpg", vec![0.1f64, 0.6, 0.2, 0.1]).unwrap();
+        cls_out.add("b.jpg", vec![0.4f64, 0.1, 0.1, 0.4]).unwrap();
+        cls_out.add("c.jpg", vec![0.9f64, 0.0, 0.05, 0.05]).unwrap();
+        let top1 = cls_out.top1_confidences();
+        assert_eq!(top1.len(), cls_out.num_images());
+        for (imagename, confidence) in top1.iter() {
+            let confidences = cls_out.confidence_for_image(imagename).unwrap();
+            let max_confidence = confidences.iter().cloned().fold(f64::MIN, f64::max);
+            assert_eq!(*confidence, max_confidence);
+            let top1_class = cls_out.topk_for_image(imagename, 1usize).unwrap()[0];
+            assert_eq!(*confidence, confidences[top1_class]);
+        }
+
+        let mut cls_nan = ClassificationOutput::<u8, f32>::new(2u8);
+        cls_nan.add("first.jpg", vec![f32::NAN, 0.3]).unwrap();
+        cls_nan.add("last.jpg", vec![0.8f32, f32::NAN]).unwrap();
+        cls_nan.add("all.jpg", vec![f32::NAN, f32::NAN]).unwrap();
+        let top1 = cls_nan.top1_confidences();
+        assert_eq!(top1["first.jpg"], 0.3f32);
+        assert_eq!(top1["last.jpg"], 0.8f32);
+        assert!(top1["all.jpg"].is_nan());
+
+        let cls_int = {
+            let mut cls_int = ClassificationOutput::<u8, u8>::new(3u8);
+            cls_int.add("int.jpg", vec![3u8, 250u8, 7u8]).unwrap();
+            cls_int
+        };
+        assert_eq!(cls_int.top1_confidences()["int.jpg"], 250u8);
+    }
+
+    #[test]
+    fn classification_dataset_images_for_class() {
+        use crate::classification::ClassificationDataset;
+
+        let mut cls_db = ClassificationDataset::new(4u16, false);
+        cls_db.add("lion.jpg", &vec![1u16]).unwrap();
+        cls_db.add("tiger.jpg", &vec![3u16]).unwrap();
+        cls_db.add("cheetah.jpg", &vec![1u16]).unwrap();
+        cls_db.add("panther.jpg", &vec![0u16]).unwrap();
+        assert_eq!(
+            cls_db.images_for_class(1u16).unwrap(),
+            vec!["cheetah.jpg", "lion.jpg"]
+        );
+        assert_eq!(cls_db.images_for_class(3u16).unwrap(), vec!["tiger.jpg"]);
+        assert!(cls_db.images_for_class(2u16).unwrap().is_empty());
+        let err = cls_db.images_for_class(4u16).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn classification_dataset_add_label_range() {
+        use crate::classification::ClassificationDataset;
+
+        let mut cls_db = ClassificationDataset::new(3u8, false);
+        cls_db.add("valid.jpg", &vec![2u8]).unwrap();
+        assert_eq!(
+            cls_db.get_gt("valid.jpg").unwrap(),
+            &vec![false, false, true]
+        );
+        let err = cls_db.add("single.jpg", &vec![3u8]).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+        assert!(err.to_string().contains("label 3"));
+        assert!(!cls_db.image_is_present("single.jpg"));
+        assert_eq!(cls_db.num_images(), 1usize);
+
+        let mut ml_db = ClassificationDataset::new(3u8, true);
+        ml_db.add("valid.jpg", &vec![0u8, 1u8, 2u8]).unwrap();
+        let err = ml_db.add("multi.jpg", &vec![0u8, 7u8, 1u8]).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+        assert!(err.to_string().contains("label 7"));
+        assert!(!ml_db.image_is_present("multi.jpg"));
+        assert_eq!(ml_db.num_images(), 1usize);
+    }
+
+    #[test]
+    fn classification_dataset_add_batch() {
+        use crate::classification::ClassificationDataset;
+
+        let mut cls_db = ClassificationDataset::new(4u8, true);
+        cls_db.add("existing.jpg", &vec![0u8]).unwrap();
+        cls_db
+            .add_batch(&[
+                ("a.jpg", vec![1u8, 3u8]),
+                ("b.jpg", vec![]),
+                ("c.jpg", vec![2u8]),
+            ])
+            .unwrap();
+        assert_eq!(cls_db.num_images(), 4usize);
+        assert_eq!(
+            cls_db.get_gt("a.jpg").unwrap(),
+            &vec![false, true, false, true]
+        );
+        assert_eq!(cls_db.images_without_labels(), vec!["b.jpg"]);
+
+        let before = cls_db.clone();
+        let err = cls_db
+            .add_batch(&[
+                ("d.jpg", vec![1u8]),
+                ("e.jpg", vec![4u8]),
+                ("f.jpg", vec![5u8]),
+            ])
+            .unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+        assert!(err.to_string().contains("e.jpg"));
+        assert!(cls_db.diff(&before).is_empty());
+
+        for batch in [
+            vec![("g.jpg", vec![1u8]), ("existing.jpg", vec![2u8])],
+            vec![("g.jpg", vec![1u8]), ("g.jpg", vec![2u8])],
+        ]
+        .iter()
+        {
+            assert!(cls_db.add_batch(batch).is_err());
+            assert!(cls_db.diff(&before).is_empty());
+        }
+        let err = cls_db
+            .add_batch(&[("g.jpg", vec![1u8]), ("existing.jpg", vec![2u8])])
+            .unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+        assert_eq!(
+            err.to_string(),
+            "The batch entry of image existing.jpg is invalid: \
+             Image existing.jpg was already present."
+        );
+        let err = cls_db
+            .add_batch(&[("g.jpg", vec![1u8]), ("g.jpg", vec![2u8])])
+            .unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+        assert_eq!(
+            err.to_string(),
+            "The batch entry of image g.jpg is invalid: \
+             The image g.jpg appears more than once in the batch."
+        );
+
+        let mut single = ClassificationDataset::new(4u8, false);
+        let err = single
+            .add_batch(&[("h.jpg", vec![1u8]), ("i.jpg", vec![1u8, 2u8])])
+            .unwrap_err();
+        assert!(err.to_string().contains("i.jpg"));
+        assert!(single.is_empty());
+    }
+
+    #[test]
+    fn classification_dataset_into_iterator() {
+        use crate::classification::ClassificationDataset;
+        use std::collections::HashMap;
+
+        let mut cls_db = ClassificationDataset::new(5u32, true);
+        cls_db.add("a.jpg", &vec![4u32, 1u32]).unwrap();
+        cls_db.add("b.jpg", &vec![]).unwrap();
+        cls_db.add("c.jpg", &vec![3u32]).unwrap();
+        let expected: HashMap<&str, Vec<u32>> = [
+            ("a.jpg", vec![1u32, 4u32]),
+            ("b.jpg", vec![]),
+            ("c.jpg", vec![3u32]),
+        ]
+        .iter()
+        .cloned()
+        .collect();
+        assert_eq!(cls_db.iter().len(), 3usize);
+        assert_eq!(cls_db.iter().collect::<HashMap<_, _>>(), expected);
+        assert_eq!((&cls_db).into_iter().collect::<HashMap<_, _>>(), expected);
+        let mut visited = 0usize;
+        for (imagename, labels) in &cls_db {
+            assert_eq!(labels, expected[imagename]);
+            visited += 1;
+        }
+        assert_eq!(visited, 3usize);
+    }
+
+    #[test]
+    fn classification_dataset_from_entries() {
+        use crate::classification::ClassificationDataset;
+
+        let cls_db = ClassificationDataset::from_entries(
+            4u16,
+            false,
+            (0u16..4u16).map(|class| (format!("{}.jpg", class), vec![class])),
+        )
+        .unwrap();
+        assert_eq!(cls_db.num_images(), 4usize);
+        assert_eq!(cls_db.class_counts(), vec![1usize; 4]);
+        assert!(!cls_db.is_multilabel());
+
+        let duplicated = vec![
+            ("a.jpg".to_string(), vec![0u16]),
+            ("b.jpg".to_string(), vec![1u16]),
+            ("a.jpg".to_string(), vec![2u16]),
+        ];
+        let err = ClassificationDataset::from_entries(4u16, false, duplicated).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+        assert!(err.to_string().contains("a.jpg"));
+
+        let out_of_range = vec![("c.jpg".to_string(), vec![0u16, 4u16])];
+        assert!(ClassificationDataset::from_entries(4u16, true, out_of_range).is_err());
+        let multilabel = vec![("d.jpg".to_string(), vec![0u16, 1u16])];
+        assert!(ClassificationDataset::from_entries(4u16, false, multilabel).is_err());
+    }
+
+    #[test]
+    fn classification_dataset_to_one_hot_matrix() {
+        use crate::classification::ClassificationDataset;
+
+        let mut cls_db = ClassificationDataset::new(4u8, true);
+        cls_db.add("c.jpg", &vec![3u8]).unwrap();
+        cls_db.add("a.jpg", &vec![0u8, 2u8]).unwrap();
+        cls_db.add("b.jpg", &vec![]).unwrap();
+        let (names, matrix) = cls_db.to_one_hot_matrix();
+        assert_eq!(names, vec!["a.jpg", "b.jpg", "c.jpg"]);
+        assert_eq!(
+            matrix,
+            vec![
+                vec![true, false, true, false],
+                vec![false, false, false, false],
+                vec![false, false, false, true],
+            ]
+        );
+        let (empty_names, empty_matrix) = ClassificationDataset::new(4u8, true).to_one_hot_matrix();
+        assert!(empty_names.is_empty() && empty_matrix.is_empty());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn classification_dataset_json_file_case_insensitive_keys() {
+        let filename = std::env::temp_dir().join("bagheera_test_json_case_insensitive.json");
+        let filename = filename.to_str().unwrap();
+        std::fs::write(
+            filename,
+            r#"{"num_classes":3,"data":{"IMG.JPG":[false,true,false]},"is_multilabel":false,"case_insensitive_keys":true}"#,
+        )
+        .unwrap();
+        let loaded = ClassificationDataset::<u8>::from_json_file(filename).unwrap();
+        assert!(loaded.image_is_present("IMG.JPG"));
+        assert!(loaded.image_is_present("img.jpg"));
+        assert_eq!(loaded.get_gt("Img.Jpg").unwrap(), &vec![false, true, false]);
+        assert_eq!(loaded.list_images(), vec!["img.jpg"]);
+
+        std::fs::write(
+            filename,
+            r#"{"num_classes":3,"data":{"IMG.JPG":[false,true,false],"img.jpg":[true,false,false]},"is_multilabel":false,"case_insensitive_keys":true}"#,
+        )
+        .unwrap();
+        assert!(ClassificationDataset::<u8>::from_json_file(filename).is_err());
+        std::fs::remove_file(filename).unwrap();
+
+        let mut case_sensitive = ClassificationDataset::new(3u8, false);
+        case_sensitive.add("img.jpg", &vec![1u8]).unwrap();
+        let case_insensitive = case_sensitive.clone().with_case_insensitive_keys().unwrap();
+        assert_ne!(case_sensitive, case_insensitive);
+        assert_eq!(
+            case_insensitive,
+            case_sensitive.clone().with_case_insensitive_keys().unwrap()
+        );
+    }
+
+    #[test]
+    fn fixed_class_output_sorted_names() {
+        use crate::fixed::FixedClassOutput;
+
+        let mut cls_out = FixedClassOutput::<2, f64>::new();
+        for name in ["d.jpg", "b.jpg", "e.jpg", "a.jpg", "c.jpg"].iter() {
+            cls_out.add(name, [0.5f64, 0.5f64]).unwrap();
+        }
+        assert_eq!(
+            cls_out.iter().map(|(name, _)| name).collect::<Vec<&str>>(),
+            vec!["a.jpg", "b.jpg", "c.jpg", "d.jpg", "e.jpg"]
+        );
+        for name in ["a.jpg", "c.jpg", "e.jpg"].iter() {
+            assert!(cls_out.image_is_present(name));
+            let err = cls_out.add(name, [1f64, 0f64]).unwrap_err();
+            assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+        }
+        assert!(!cls_out.image_is_present("f.jpg"));
+        let err = cls_out.confidence_for_image("f.jpg").unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::NotFound);
+        assert_eq!(cls_out.num_images(), 5usize);
+    }
+
+    #[test]
+    fn classification_output_from_csv_file_errors() {
+        let filename = std::env::temp_dir().join("bagheera_test_from_csv_file_errors.csv");
+        let filename = filename.to_str().unwrap();
+        let missing = std::env::temp_dir().join("bagheera_test_from_csv_file_missing.csv");
+        let err = ClassificationOutput::<u8, f32>::from_csv_file(missing.to_str().unwrap(), 2u8)
+            .unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::NotFound);
+
+        std::fs::write(filename, "a.jpg,0.25,0.75\nb.jpg,0.5,abc\n").unwrap();
+        let err = ClassificationOutput::<u8, f32>::from_csv_file(filename, 2u8).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+        assert!(err.to_string().starts_with("Line 2 of"));
+        assert!(err.to_string().contains("\"abc\""));
+
+        std::fs::write(filename, "a.jpg,0.25,0.75\nb.jpg,0.5,0.25,0.25\n").unwrap();
+        let err = ClassificationOutput::<u8, f32>::from_csv_file(filename, 2u8).unwrap_err();
+        assert!(err.to_string().starts_with("Line 2 of"));
+
+        std::fs::write(filename, "\na.jpg,0.25,0.75\n").unwrap();
+        let err = ClassificationOutput::<u8, f32>::from_csv_file(filename, 2u8).unwrap_err();
+        assert!(err.to_string().starts_with("Line 1 of"));
+
+        std::fs::write(filename, "a.jpg,0.25,0.75\nb.jpg,1,0\n").unwrap();
+        let cls_out = ClassificationOutput::<u8, f32>::from_csv_file(filename, 2u8).unwrap();
+        assert_eq!(cls_out.num_images(), 2usize);
+        std::fs::remove_file(filename).unwrap();
+    }
+
+    #[test]
+    fn classification_dataset_add_without_label() {
+        let mut cls_db = ClassificationDataset::new(3u8, false);
+        let err = cls_db.add("empty.jpg", &vec![]).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+        assert_eq!(
+            err.to_string(),
+            "Image empty.jpg has 0 labels while the dataset is single-label."
+        );
+        assert!(cls_db.is_empty());
+        assert!(ClassificationDataset::new(3u8, true)
+            .add("empty.jpg", &vec![])
+            .is_ok());
+    }
+
+    #[test]
+    fn metrics_evaluator_update_errors_and_ties() {
+        let mut evaluator = metrics::Evaluator::new(3u8, false);
+        let err = evaluator
+            .update(&[], &[0.1f32, 0.2f32, 0.7f32])
+            .unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+        assert_eq!(
+            err.to_string(),
+            "Single-label evaluation expects exactly one label per image, got 0."
+        );
+        evaluator.update(&[1u8], &[0.4f32, 0.4f32, 0.2f32]).unwrap();
+        evaluator.update(&[2u8], &[1u8, 3u8, 3u8]).unwrap();
+        let report = evaluator.finalize();
+        assert_eq!(report.num_images(), 2usize);
+        assert_eq!(report.recall(), &[0f64, 0f64, 0f64]);
+        assert_eq!(report.precision(), &[0f64, 0f64, 0f64]);
+    }
+
+    #[cfg(feature = "ndarray")]
+    #[test]
+    fn classification_output_from_ndarray_errors() {
+        use ndarray::Array2;
+
+        let matrix = Array2::<f32>::zeros((2, 3));
+        let err = ClassificationOutput::from_ndarray(matrix.view(), &["a.jpg", "b.jpg"], 4u8)
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "The matrix has 3 columns when num_classes is specified as 4."
+        );
+        let empty = Array2::<f32>::zeros((0, 3));
+        assert!(ClassificationOutput::from_ndarray(empty.view(), &[], 4u8).is_err());
+        let err = ClassificationOutput::from_ndarray(matrix.view(), &["a.jpg"], 3u8).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "The matrix has 2 rows but 1 image names were provided."
+        );
+        let err = ClassificationOutput::from_ndarray(matrix.view(), &["a.jpg", "a.jpg"], 3u8)
+            .unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+        assert_eq!(err.to_string(), "Image a.jpg was already present.");
+    }
+
+    #[test]
+    fn classification_dataset_builder_missing_num_classes() {
+        let err = ClassificationDatasetBuilder::<u8>::new()
+            .capacity(8usize)
+            .build()
+            .unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+        assert_eq!(
+            err.to_string(),
+            "The number of classes must be set to build a ClassificationDataset."
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn classification_dataset_json_file_validation_errors() {
+        let filename = std::env::temp_dir().join("bagheera_test_json_validation_errors.json");
+        let filename = filename.to_str().unwrap();
+        std::fs::write(
+            filename,
+            r#"{"num_classes":2,"data":{"a.jpg":[true,true]},"is_multilabel":false}"#,
+        )
+        .unwrap();
+        let err = ClassificationDataset::<u16>::from_json_file(filename).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+        assert_eq!(
+            err.to_string(),
+            "Image a.jpg has 2 labels while the dataset is single-label."
+        );
+        std::fs::write(
+            filename,
+            r#"{"num_classes":3,"data":{"a.jpg":[true,false]},"is_multilabel":true}"#,
+        )
+        .unwrap();
+        let err = ClassificationDataset::<u16>::from_json_file(filename).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+        assert_eq!(
+            err.to_string(),
+            "The groundtruth of image a.jpg has 2 entries while there are 3 classes."
+        );
+        std::fs::remove_file(filename).unwrap();
+    }
+
+    #[test]
+    fn classification_output_remap_classes_overflow() {
+        use crate::classification::Reduce;
+
+        let mut cls_out = ClassificationOutput::<u8, f32>::new(2u8);
+        cls_out.add("a.jpg", vec![0.25f32, 0.75f32]).unwrap();
+        let err = cls_out
+            .remap_classes(&[0usize, 299usize], 300usize, Reduce::Sum)
+            .unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+        assert_eq!(
+            err.to_string(),
+            "300 classes do not fit in the class index type."
+        );
+        let remapped = cls_out
+            .remap_classes(&[0usize, 254usize], 255usize, Reduce::Sum)
+            .unwrap();
+        assert_eq!(remapped.num_classes(), 255u8);
+    }
+
+    #[test]
+    fn classification_output_to_label_dataset_without_classes() {
+        let mut cls_out = ClassificationOutput::<u8, f32>::new(0u8);
+        cls_out.add("x.jpg", vec![]).unwrap();
+        assert!(cls_out.to_label_dataset().is_err());
+        let empty = ClassificationOutput::<u8, f32>::new(0u8);
+        assert!(empty.to_label_dataset().unwrap().is_empty());
+    }
+
+    #[test]
+    fn classification_csv_dataset_reader_blank_labels() {
+        use crate::classification::CsvDatasetReader;
+
+        let filename = std::env::temp_dir().join("bagheera_test_csv_dataset_reader_blank.csv");
+        let filename = filename.to_str().unwrap();
+        std::fs::write(filename, "a.jpg, \nb.jpg,1\n").unwrap();
+        let records = CsvDatasetReader::new(filename, 4u8, true)
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(records[0], ("a.jpg".to_string(), vec![]));
+        assert_eq!(records[1], ("b.jpg".to_string(), vec![1u8]));
+        std::fs::write(filename, "a.jpg,1,\n").unwrap();
+        assert!(CsvDatasetReader::new(filename, 4u8, true)
+            .unwrap()
+            .next()
+            .unwrap()
+            .is_err());
+        std::fs::remove_file(filename).unwrap();
+    }
 }
@@ -0,0 +1,2658 @@
+//! Metrics for image classification
+//!
+//! Provides functions which compare the groundtruth stored in a
+//! [`ClassificationDataset`] against the output of a classifier stored in a
+//! [`ClassificationOutput`].
+//!
+//! Every metric takes a `&ClassificationDataset<T1>` and a `&ClassificationOutput<T1, T2>`
+//! sharing the same class index type `T1`. Evaluating a dataset against an output indexed
+//! with a different integer type is therefore caught at compile time, while a different
+//! number of classes is reported at runtime as an [io::Error]. The [`evaluate`] entry point
+//! performs the runtime checks once and binds both halves together in an [`Evaluation`].
+//!
+//! ```rust
+//! use bagheera::classification::{ClassificationDataset, ClassificationOutput};
+//! use bagheera::metrics::evaluate;
+//!
+//! let mut cls_db = ClassificationDataset::new(2u16, false);
+//! cls_db.add("india.jpg", &vec![1u16]).unwrap();
+//! let mut cls_out = ClassificationOutput::<u16, f32>::new(2u16);
+//! cls_out.add("india.jpg", vec![0.1f32, 0.9f32]).unwrap();
+//!
+//! let evaluation = evaluate(&cls_db, &cls_out).unwrap();
+//! assert_eq!(evaluation.topk_accuracy(1usize).unwrap(), 1f64);
+//! ```
+//!
+//! ```compile_fail
+//! use bagheera::classification::{ClassificationDataset, ClassificationOutput};
+//! use bagheera::metrics::evaluate;
+//!
+//! let cls_db = ClassificationDataset::new(2u8, false);
+//! let cls_out = ClassificationOutput::<u16, f32>::new(2u16);
+//! let evaluation = evaluate(&cls_db, &cls_out);
+//! ```
+
+use std::collections::HashMap;
+use std::io;
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use crate::classification::{ClassificationDataset, ClassificationOutput};
+use crate::errors;
+use crate::utils;
+use crate::utils::TopK;
+
+/// Returns an [io::Error] instance if `gt` and `pred` have different number of classes, or
+/// if the groundtruth or the confidence vector of an image does not have one entry per
+/// class.
+///
+/// Every metric calls this before indexing any vector, so that a drift in the number of
+/// classes is reported, naming the offending image, instead of causing a panic or a
+/// silently wrong result.
+fn check_num_classes<
+    T1: num_traits::PrimInt + num_traits::Unsigned + num_traits::FromPrimitive,
+    T2: num_traits::Num + Copy + PartialOrd,
+>(
+    gt: &ClassificationDataset<T1>,
+    pred: &ClassificationOutput<T1, T2>,
+) -> Result<(), io::Error> {
+    if gt.num_classes() != pred.num_classes() {
+        return Err(errors::num_classes_mismatch(
+            gt.num_classes_usize(),
+            pred.num_classes_usize(),
+        ));
+    }
+    let num_classes = gt.num_classes_usize();
+    for (imagename, one_hot) in gt.one_hot_data() {
+        if one_hot.len() != num_classes {
+            return Err(errors::groundtruth_length_mismatch(
+                imagename,
+                one_hot.len(),
+                num_classes,
+            ));
+        }
+    }
+    for (imagename, confidences) in pred.data() {
+        if confidences.len() != num_classes {
+            return Err(errors::confidence_length_mismatch(
+                imagename,
+                confidences.len(),
+                num_classes,
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Returns an [io::Error] instance naming `metric` if `gt` is multi-label.
+fn require_single_label<
+    T1: num_traits::PrimInt + num_traits::Unsigned + num_traits::FromPrimitive,
+>(
+    gt: &ClassificationDataset<T1>,
+    metric: &str,
+) -> Result<(), io::Error> {
+    gt.assert_same_modality(false)
+        .map_err(|_| errors::requires_single_label(metric))
+}
+
+/// Returns an [io::Error] instance naming `metric` if `gt` is single-label.
+fn require_multilabel<
+    T1: num_traits::PrimInt + num_traits::Unsigned + num_traits::FromPrimitive,
+>(
+    gt: &ClassificationDataset<T1>,
+    metric: &str,
+) -> Result<(), io::Error> {
+    gt.assert_same_modality(true)
+        .map_err(|_| errors::requires_multilabel(metric))
+}
+
+/// Returns the sorted names of the images present in both `gt` and `pred`.
+fn common_images<
+    'a,
+    T1: num_traits::PrimInt + num_traits::Unsigned + num_traits::FromPrimitive,
+    T2: num_traits::Num + Copy + PartialOrd,
+>(
+    gt: &'a ClassificationDataset<T1>,
+    pred: &ClassificationOutput<T1, T2>,
+) -> Vec<&'a str> {
+    let mut images = gt
+        .list_images()
+        .into_iter()
+        .filter(|imagename| pred.image_is_present(imagename))
+        .collect::<Vec<&str>>();
+    images.sort_unstable();
+    images
+}
+
+/// Returns the class index of the label of `imagename` in a single-label `gt`.
+fn true_class<T1: num_traits::PrimInt + num_traits::Unsigned + num_traits::FromPrimitive>(
+    gt: &ClassificationDataset<T1>,
+    imagename: &str,
+) -> Result<usize, io::Error> {
+    gt.get_gt(imagename)?
+        .iter()
+        .position(|x| *x)
+        .ok_or_else(|| errors::image_without_label(imagename))
+}
+
+/// Returns true if the metric values `a` and `b` are equal up to rounding noise.
+///
+/// The values are considered equal when they differ by at most [`f64::EPSILON`], which
+/// handles values close to zero, or when at most `ulps` representable `f64` values lie
+/// between them. NaN is never equal to anything. This is the tolerance used by the tests
+/// of the crate and should be used to compare metrics computed in a different order, such
+/// as those of a previous run.
+///
+/// # Examples
+///
+/// ```rust
+/// use bagheera::metrics::metrics_approx_eq;
+///
+/// assert!(metrics_approx_eq(0.1f64 + 0.2f64, 0.3f64, 2i64));
+/// assert!(!metrics_approx_eq(0.3f64, 0.3001f64, 2i64));
+/// ```
+pub fn metrics_approx_eq(a: f64, b: f64, ulps: i64) -> bool {
+    if a.is_nan() || b.is_nan() {
+        return false;
+    }
+    if (a - b).abs() <= f64::EPSILON {
+        return true;
+    }
+    if a.is_sign_negative() != b.is_sign_negative() {
+        return false;
+    }
+    let distance = (a.to_bits() as i64).wrapping_sub(b.to_bits() as i64);
+    distance
+        .checked_abs()
+        .is_some_and(|distance| distance <= ulps)
+}
+
+/// Returns the list of images for which the top-1 prediction in `pred` differs from the
+/// label in `gt`.
+///
+/// Each entry is a `(imagename, true_class, predicted_class)` tuple and the entries are
+/// sorted by image name. Only images present in both `gt` and `pred` are considered.
+/// An [io::Error] instance is returned if `gt` is multi-label or if `gt` and `pred` have
+/// different number of classes.
+///
+/// # Examples
+///
+/// ```rust
+/// use bagheera::classification::{ClassificationDataset, ClassificationOutput};
+/// use bagheera::metrics::misclassified;
+///
+/// let mut cls_db = ClassificationDataset::new(3u8, false);
+/// cls_db.add("india.jpg", &vec![0u8]).unwrap();
+/// cls_db.add("iran.jpg", &vec![2u8]).unwrap();
+///
+/// let mut cls_out = ClassificationOutput::<u8, f32>::new(3u8);
+/// cls_out.add("india.jpg", vec![0.8f32, 0.1f32, 0.1f32]).unwrap();
+/// cls_out.add("iran.jpg", vec![0.2f32, 0.7f32, 0.1f32]).unwrap();
+///
+/// let wrong = misclassified(&cls_db, &cls_out).unwrap();
+/// assert_eq!(wrong, vec![("iran.jpg".to_string(), 2usize, 1usize)]);
+/// ```
+pub fn misclassified<
+    T1: num_traits::PrimInt + num_traits::Unsigned + num_traits::FromPrimitive,
+    T2: num_traits::Num + Copy + PartialOrd,
+>(
+    gt: &ClassificationDataset<T1>,
+    pred: &ClassificationOutput<T1, T2>,
+) -> Result<Vec<(String, usize, usize)>, io::Error>
+where
+    Vec<T2>: TopK,
+{
+    require_single_label(gt, "misclassified")?;
+    check_num_classes(gt, pred)?;
+
+    let mut wrong = Vec::<(String, usize, usize)>::new();
+    for imagename in common_images(gt, pred) {
+        let true_class = true_class(gt, imagename)?;
+        let predicted_class = pred.topk_for_image(imagename, 1usize)?[0];
+        if true_class != predicted_class {
+            wrong.push((imagename.to_string(), true_class, predicted_class));
+        }
+    }
+    Ok(wrong)
+}
+
+/// Struct representing the confusion matrix of a single-label classifier.
+///
+/// Rows are indexed by the true class and columns by the predicted class.
+pub struct ConfusionMatrix {
+    num_classes: usize,
+    counts: Vec<Vec<usize>>,
+}
+
+impl ConfusionMatrix {
+    /// Creates a new instance of [`Self`] for `num_classes` classes with all counts set to zero.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use bagheera::metrics::ConfusionMatrix;
+    /// let cm = ConfusionMatrix::new(3usize);
+    /// assert_eq!(cm.num_classes(), 3usize);
+    /// assert_eq!(cm.total(), 0usize);
+    /// ```
+    pub fn new(num_classes: usize) -> Self {
+        ConfusionMatrix {
+            num_classes,
+            counts: vec![vec![0usize; num_classes]; num_classes],
+        }
+    }
+
+    /// Records one image with label `true_class` predicted as `predicted_class`.
+    ///
+    /// An [io::Error] instance is returned if either class is more than or equal to
+    /// the number of classes.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use bagheera::metrics::ConfusionMatrix;
+    /// let mut cm = ConfusionMatrix::new(3usize);
+    /// cm.add(0usize, 2usize).unwrap();
+    /// assert_eq!(cm.count(0usize, 2usize), 1usize);
+    /// assert!(cm.add(3usize, 0usize).is_err());
+    /// ```
+    pub fn add(&mut self, true_class: usize, predicted_class: usize) -> Result<(), io::Error> {
+        for class in [true_class, predicted_class].iter() {
+            if *class >= self.num_classes {
+                return Err(errors::label_out_of_range(*class, self.num_classes));
+            }
+        }
+        self.counts[true_class][predicted_class] += 1;
+        Ok(())
+    }
+
+    /// Adds the counts of `other`, which has the same number of classes, to the [`Self`]
+    /// instance.
+    #[cfg(feature = "rayon")]
+    fn merge(mut self, other: Self) -> Self {
+        for (row, other_row) in self.counts.iter_mut().zip(other.counts.iter()) {
+            for (count, other_count) in row.iter_mut().zip(other_row.iter()) {
+                *count += *other_count;
+            }
+        }
+        self
+    }
+
+    /// Returns the number of classes.
+    #[inline(always)]
+    pub fn num_classes(&self) -> usize {
+        self.num_classes
+    }
+
+    /// Returns the number of images with label `true_class` predicted as `predicted_class`.
+    ///
+    /// Panics if either class is more than or equal to the number of classes.
+    #[inline(always)]
+    pub fn count(&self, true_class: usize, predicted_class: usize) -> usize {
+        self.counts[true_class][predicted_class]
+    }
+
+    /// Returns the total number of images recorded in the [`Self`] instance.
+    pub fn total(&self) -> usize {
+        self.counts
+            .iter()
+            .map(|row| row.iter().sum::<usize>())
+            .sum()
+    }
+
+    /// Returns the `n` most confused class pairs as `(true_class, predicted_class, count)`
+    /// tuples sorted by descending count.
+    ///
+    /// The diagonal and the cells with a zero count are excluded, so fewer than `n`
+    /// entries are returned when there are not enough confusions. Ties are broken by the
+    /// smaller true class and then the smaller predicted class.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use bagheera::metrics::ConfusionMatrix;
+    /// let mut cm = ConfusionMatrix::new(3usize);
+    /// cm.add(0usize, 0usize).unwrap();
+    /// cm.add(0usize, 0usize).unwrap();
+    /// cm.add(1usize, 2usize).unwrap();
+    /// cm.add(2usize, 0usize).unwrap();
+    /// cm.add(2usize, 0usize).unwrap();
+    /// assert_eq!(
+    ///     cm.top_confusions(5usize),
+    ///     vec![(2usize, 0usize, 2usize), (1usize, 2usize, 1usize)]
+    /// );
+    /// ```
+    pub fn top_confusions(&self, n: usize) -> Vec<(usize, usize, usize)> {
+        let mut cells = Vec::<(usize, usize, usize)>::new();
+        for (true_class, row) in self.counts.iter().enumerate() {
+            for (predicted_class, count) in row.iter().enumerate() {
+                if true_class != predicted_class && *count > 0 {
+                    cells.push((true_class, predicted_class, *count));
+                }
+            }
+        }
+        cells.sort_by(|lhs, rhs| {
+            rhs.2
+                .cmp(&lhs.2)
+                .then(lhs.0.cmp(&rhs.0))
+                .then(lhs.1.cmp(&rhs.1))
+        });
+        cells.truncate(n);
+        cells
+    }
+}
+
+/// Returns the [`ConfusionMatrix`] of the top-1 predictions in `pred` against the labels
+/// in `gt`.
+///
+/// Only images present in both `gt` and `pred` are considered. An [io::Error] instance is
+/// returned if `gt` is multi-label or if `gt` and `pred` have different number of classes.
+///
+/// # Examples
+///
+/// ```rust
+/// use bagheera::classification::{ClassificationDataset, ClassificationOutput};
+/// use bagheera::metrics::confusion_matrix;
+///
+/// let mut cls_db = ClassificationDataset::new(2u8, false);
+/// cls_db.add("india.jpg", &vec![0u8]).unwrap();
+/// cls_db.add("iran.jpg", &vec![1u8]).unwrap();
+///
+/// let mut cls_out = ClassificationOutput::<u8, f32>::new(2u8);
+/// cls_out.add("india.jpg", vec![0.8f32, 0.2f32]).unwrap();
+/// cls_out.add("iran.jpg", vec![0.6f32, 0.4f32]).unwrap();
+///
+/// let cm = confusion_matrix(&cls_db, &cls_out).unwrap();
+/// assert_eq!(cm.count(0usize, 0usize), 1usize);
+/// assert_eq!(cm.count(1usize, 0usize), 1usize);
+/// ```
+pub fn confusion_matrix<
+    T1: num_traits::PrimInt + num_traits::Unsigned + num_traits::FromPrimitive,
+    T2: num_traits::Num + Copy + PartialOrd,
+>(
+    gt: &ClassificationDataset<T1>,
+    pred: &ClassificationOutput<T1, T2>,
+) -> Result<ConfusionMatrix, io::Error>
+where
+    Vec<T2>: TopK,
+{
+    require_single_label(gt, "confusion_matrix")?;
+    check_num_classes(gt, pred)?;
+
+    let mut cm = ConfusionMatrix::new(gt.num_classes_usize());
+    for imagename in common_images(gt, pred) {
+        cm.add(
+            true_class(gt, imagename)?,
+            pred.topk_for_image(imagename, 1usize)?[0],
+        )?;
+    }
+    Ok(cm)
+}
+
+/// Returns an [io::Error] instance if the groundtruth `gt` and the predicted labels `pred`
+/// have different modalities or number of classes, or if the one-hot vector of an image
+/// does not have one entry per class.
+fn check_datasets<T1: num_traits::PrimInt + num_traits::Unsigned + num_traits::FromPrimitive>(
+    gt: &ClassificationDataset<T1>,
+    pred: &ClassificationDataset<T1>,
+) -> Result<(), io::Error> {
+    gt.assert_same_modality(pred.is_multilabel())?;
+    if gt.num_classes() != pred.num_classes() {
+        return Err(errors::num_classes_mismatch(
+            gt.num_classes_usize(),
+            pred.num_classes_usize(),
+        ));
+    }
+    let num_classes = gt.num_classes_usize();
+    for (imagename, one_hot) in gt.one_hot_data().iter().chain(pred.one_hot_data().iter()) {
+        if one_hot.len() != num_classes {
+            return Err(errors::groundtruth_length_mismatch(
+                imagename,
+                one_hot.len(),
+                num_classes,
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Returns the sorted names of the images present in both `gt` and `pred`.
+fn common_images_datasets<
+    'a,
+    T1: num_traits::PrimInt + num_traits::Unsigned + num_traits::FromPrimitive,
+>(
+    gt: &'a ClassificationDataset<T1>,
+    pred: &ClassificationDataset<T1>,
+) -> Vec<&'a str> {
+    let mut images = gt
+        .list_images()
+        .into_iter()
+        .filter(|imagename| pred.image_is_present(imagename))
+        .collect::<Vec<&str>>();
+    images.sort_unstable();
+    images
+}
+
+/// Returns the fraction of the images in common whose labels in `pred` are exactly their
+/// labels in `gt`.
+///
+/// This evaluates predictions which are already hard labels, stored as a
+/// [`ClassificationDataset`], without fabricating confidences. For single-label datasets
+/// this is the accuracy, and for multi-label ones it is the subset accuracy, an image being
+/// correct only when its whole label set is predicted. An [io::Error] instance is returned
+/// if `gt` and `pred` have different modalities or number of classes, or if they have no
+/// image in common.
+///
+/// # Examples
+///
+/// ```rust
+/// use bagheera::classification::ClassificationDataset;
+/// use bagheera::metrics::accuracy_datasets;
+///
+/// let mut cls_db = ClassificationDataset::new(3u8, false);
+/// cls_db.add("india.jpg", &vec![1u8]).unwrap();
+/// cls_db.add("iran.jpg", &vec![2u8]).unwrap();
+///
+/// let mut predicted = ClassificationDataset::new(3u8, false);
+/// predicted.add("india.jpg", &vec![1u8]).unwrap();
+/// predicted.add("iran.jpg", &vec![0u8]).unwrap();
+///
+/// assert_eq!(accuracy_datasets(&cls_db, &predicted).unwrap(), 0.5f64);
+/// ```
+pub fn accuracy_datasets<
+    T1: num_traits::PrimInt + num_traits::Unsigned + num_traits::FromPrimitive,
+>(
+    gt: &ClassificationDataset<T1>,
+    pred: &ClassificationDataset<T1>,
+) -> Result<f64, io::Error> {
+    check_datasets(gt, pred)?;
+    let images = common_images_datasets(gt, pred);
+    if images.is_empty() {
+        return Err(errors::no_common_images());
+    }
+    let mut num_correct = 0usize;
+    for imagename in &images {
+        num_correct += (gt.get_gt(imagename)? == pred.get_gt(imagename)?) as usize;
+    }
+    Ok(num_correct as f64 / images.len() as f64)
+}
+
+/// Returns the [`ConfusionMatrix`] of the predicted labels `pred` against `gt`.
+///
+/// This is the [`confusion_matrix`] of predictions which are already hard labels, stored as
+/// a single-label [`ClassificationDataset`]. Only images present in both `gt` and `pred`
+/// are considered. An [io::Error] instance is returned if `gt` or `pred` is multi-label, or
+/// if they have different number of classes.
+///
+/// # Examples
+///
+/// ```rust
+/// use bagheera::classification::ClassificationDataset;
+/// use bagheera::metrics::confusion_matrix_datasets;
+///
+/// let mut cls_db = ClassificationDataset::new(2u8, false);
+/// cls_db.add("india.jpg", &vec![0u8]).unwrap();
+/// cls_db.add("iran.jpg", &vec![1u8]).unwrap();
+///
+/// let mut predicted = ClassificationDataset::new(2u8, false);
+/// predicted.add("india.jpg", &vec![0u8]).unwrap();
+/// predicted.add("iran.jpg", &vec![0u8]).unwrap();
+///
+/// let cm = confusion_matrix_datasets(&cls_db, &predicted).unwrap();
+/// assert_eq!(cm.count(0usize, 0usize), 1usize);
+/// assert_eq!(cm.count(1usize, 0usize), 1usize);
+/// ```
+pub fn confusion_matrix_datasets<
+    T1: num_traits::PrimInt + num_traits::Unsigned + num_traits::FromPrimitive,
+>(
+    gt: &ClassificationDataset<T1>,
+    pred: &ClassificationDataset<T1>,
+) -> Result<ConfusionMatrix, io::Error> {
+    require_single_label(gt, "confusion_matrix_datasets")?;
+    check_datasets(gt, pred)?;
+
+    let mut cm = ConfusionMatrix::new(gt.num_classes_usize());
+    for imagename in common_images_datasets(gt, pred) {
+        cm.add(true_class(gt, imagename)?, true_class(pred, imagename)?)?;
+    }
+    Ok(cm)
+}
+
+/// Returns `(threshold, precision, recall)` tuples for the class `class` at each of the
+/// `thresholds`.
+///
+/// An image is predicted as positive for `class` when its confidence for `class` is more
+/// than or equal to the threshold, and is a groundtruth positive when `class` is one of
+/// its labels. Only images present in both `gt` and `pred` are considered. When no image
+/// is predicted as positive the precision is `1.0`, and when there are no groundtruth
+/// positives the recall is `0.0`.
+///
+/// An [io::Error] instance is returned if `class` is more than or equal to the number of
+/// classes or if `gt` and `pred` have different number of classes.
+///
+/// # Examples
+///
+/// ```rust
+/// use bagheera::classification::{ClassificationDataset, ClassificationOutput};
+/// use bagheera::metrics::precision_recall_curve;
+///
+/// let mut cls_db = ClassificationDataset::new(2u8, true);
+/// cls_db.add("india.jpg", &vec![0u8, 1u8]).unwrap();
+/// cls_db.add("iran.jpg", &vec![0u8]).unwrap();
+///
+/// let mut cls_out = ClassificationOutput::<u8, f32>::new(2u8);
+/// cls_out.add("india.jpg", vec![0.9f32, 0.6f32]).unwrap();
+/// cls_out.add("iran.jpg", vec![0.4f32, 0.3f32]).unwrap();
+///
+/// let curve = precision_recall_curve(&cls_db, &cls_out, 1usize, &[0.2f32, 0.5f32]).unwrap();
+/// assert_eq!(curve, vec![(0.2f32, 0.5f64, 1f64), (0.5f32, 1f64, 1f64)]);
+/// ```
+pub fn precision_recall_curve<
+    T1: num_traits::PrimInt + num_traits::Unsigned + num_traits::FromPrimitive,
+    T2: num_traits::Float + fast_float::FastFloat + num_traits::FromPrimitive,
+>(
+    gt: &ClassificationDataset<T1>,
+    pred: &ClassificationOutput<T1, T2>,
+    class: usize,
+    thresholds: &[T2],
+) -> Result<Vec<(T2, f64, f64)>, io::Error> {
+    check_num_classes(gt, pred)?;
+    let num_classes = gt.num_classes_usize();
+    if class >= num_classes {
+        return Err(errors::label_out_of_range(class, num_classes));
+    }
+
+    let mut samples = Vec::<(bool, T2)>::new();
+    for imagename in common_images(gt, pred) {
+        samples.push((
+            gt.get_gt(imagename)?[class],
+            pred.confidence_for_image(imagename)?[class],
+        ));
+    }
+    let num_positives = samples
+        .iter()
+        .filter(|(is_positive, _)| *is_positive)
+        .count();
+
+    let mut curve = Vec::<(T2, f64, f64)>::with_capacity(thresholds.len());
+    for threshold in thresholds {
+        let mut true_positives = 0usize;
+        let mut predicted_positives = 0usize;
+        for (is_positive, confidence) in &samples {
+            if *confidence >= *threshold {
+                predicted_positives += 1;
+                if *is_positive {
+                    true_positives += 1;
+                }
+            }
+        }
+        let precision = match predicted_positives {
+            0 => 1f64,
+            _ => true_positives as f64 / predicted_positives as f64,
+        };
+        let recall = match num_positives {
+            0 => 0f64,
+            _ => true_positives as f64 / num_positives as f64,
+        };
+        curve.push((*threshold, precision, recall));
+    }
+    Ok(curve)
+}
+
+/// Returns the fraction of images whose label in `gt` is among the top-`k` predictions
+/// in `pred`.
+///
+/// Only images present in both `gt` and `pred` are considered. The ranking only relies on
+/// the ordering of the confidences, so integer confidences such as the `u8` scores of a
+/// quantized classifier are supported. An [io::Error] instance is returned if `gt` is
+/// multi-label, if `gt` and `pred` have different number of classes, if `k` is more than
+/// the number of classes or if there is no image in common.
+///
+/// # Examples
+///
+/// ```rust
+/// use bagheera::classification::{ClassificationDataset, ClassificationOutput};
+/// use bagheera::metrics::topk_accuracy;
+///
+/// let mut cls_db = ClassificationDataset::new(3u8, false);
+/// cls_db.add("india.jpg", &vec![0u8]).unwrap();
+/// cls_db.add("iran.jpg", &vec![2u8]).unwrap();
+///
+/// let mut cls_out = ClassificationOutput::<u8, f32>::new(3u8);
+/// cls_out.add("india.jpg", vec![0.8f32, 0.1f32, 0.1f32]).unwrap();
+/// cls_out.add("iran.jpg", vec![0.2f32, 0.7f32, 0.1f32]).unwrap();
+///
+/// assert_eq!(topk_accuracy(&cls_db, &cls_out, 1usize).unwrap(), 0.5f64);
+/// assert_eq!(topk_accuracy(&cls_db, &cls_out, 3usize).unwrap(), 1f64);
+/// ```
+pub fn topk_accuracy<
+    T1: num_traits::PrimInt + num_traits::Unsigned + num_traits::FromPrimitive,
+    T2: num_traits::Num + Copy + PartialOrd,
+>(
+    gt: &ClassificationDataset<T1>,
+    pred: &ClassificationOutput<T1, T2>,
+    k: usize,
+) -> Result<f64, io::Error>
+where
+    Vec<T2>: TopK,
+{
+    require_single_label(gt, "topk_accuracy")?;
+    check_num_classes(gt, pred)?;
+
+    let images = common_images(gt, pred);
+    if images.is_empty() {
+        return Err(errors::no_common_images());
+    }
+    let mut num_correct = 0usize;
+    for imagename in &images {
+        let true_class = true_class(gt, imagename)?;
+        if pred
+            .confidence_for_image(imagename)?
+            .top_k(k)?
+            .contains(&true_class)
+        {
+            num_correct += 1;
+        }
+    }
+    Ok(num_correct as f64 / images.len() as f64)
+}
+
+/// Returns the [`topk_accuracy`] for each of the `ks`, ranking the predictions of every
+/// image only once.
+///
+/// The predictions are ranked up to the largest of the `ks` and the smaller values read a
+/// prefix of that ranking. The conditions under which an [io::Error] instance is returned
+/// are the same as for [`topk_accuracy`] with the largest of the `ks`.
+///
+/// # Examples
+///
+/// ```rust
+/// use bagheera::classification::{ClassificationDataset, ClassificationOutput};
+/// use bagheera::metrics::topk_accuracies;
+///
+/// let mut cls_db = ClassificationDataset::new(3u8, false);
+/// cls_db.add("india.jpg", &vec![0u8]).unwrap();
+/// cls_db.add("iran.jpg", &vec![2u8]).unwrap();
+///
+/// let mut cls_out = ClassificationOutput::<u8, f32>::new(3u8);
+/// cls_out.add("india.jpg", vec![0.8f32, 0.1f32, 0.1f32]).unwrap();
+/// cls_out.add("iran.jpg", vec![0.2f32, 0.7f32, 0.1f32]).unwrap();
+///
+/// assert_eq!(
+///     topk_accuracies(&cls_db, &cls_out, &[1usize, 3usize]).unwrap(),
+///     vec![0.5f64, 1f64]
+/// );
+/// ```
+pub fn topk_accuracies<
+    T1: num_traits::PrimInt + num_traits::Unsigned + num_traits::FromPrimitive,
+    T2: num_traits::Num + Copy + PartialOrd,
+>(
+    gt: &ClassificationDataset<T1>,
+    pred: &ClassificationOutput<T1, T2>,
+    ks: &[usize],
+) -> Result<Vec<f64>, io::Error>
+where
+    Vec<T2>: TopK,
+{
+    require_single_label(gt, "topk_accuracies")?;
+    check_num_classes(gt, pred)?;
+
+    let images = common_images(gt, pred);
+    if images.is_empty() {
+        return Err(errors::no_common_images());
+    }
+    let max_k = ks.iter().copied().max().unwrap_or(0usize);
+    let mut num_correct = vec![0usize; ks.len()];
+    for imagename in &images {
+        let true_class = true_class(gt, imagename)?;
+        let ranking = pred.confidence_for_image(imagename)?.top_k(max_k)?;
+        if let Some(rank) = ranking.iter().position(|x| *x == true_class) {
+            for (count, k) in num_correct.iter_mut().zip(ks.iter()) {
+                if rank < *k {
+                    *count += 1;
+                }
+            }
+        }
+    }
+    Ok(num_correct
+        .into_iter()
+        .map(|x| x as f64 / images.len() as f64)
+        .collect())
+}
+
+/// Returns the fraction of images whose label in `gt` is the top-1 prediction in `pred`.
+///
+/// This is [`topk_accuracy`] with `k = 1`.
+///
+/// # Examples
+///
+/// ```rust
+/// use bagheera::classification::{ClassificationDataset, ClassificationOutput};
+/// use bagheera::metrics::top1_accuracy;
+///
+/// let mut cls_db = ClassificationDataset::new(2u8, false);
+/// cls_db.add("india.jpg", &vec![1u8]).unwrap();
+///
+/// let mut cls_out = ClassificationOutput::<u8, u8>::new(2u8);
+/// cls_out.add("india.jpg", vec![12u8, 243u8]).unwrap();
+///
+/// assert_eq!(top1_accuracy(&cls_db, &cls_out).unwrap(), 1f64);
+/// ```
+pub fn top1_accuracy<
+    T1: num_traits::PrimInt + num_traits::Unsigned + num_traits::FromPrimitive,
+    T2: num_traits::Num + Copy + PartialOrd,
+>(
+    gt: &ClassificationDataset<T1>,
+    pred: &ClassificationOutput<T1, T2>,
+) -> Result<f64, io::Error>
+where
+    Vec<T2>: TopK,
+{
+    require_single_label(gt, "top1_accuracy")?;
+    topk_accuracy(gt, pred, 1usize)
+}
+
+/// Returns the fraction of images whose label in `gt` is the top-1 prediction in `pred`,
+/// computed in a single pass over the images.
+///
+/// The result is the same as [`top1_accuracy`] and as the accuracy of the
+/// [`confusion_matrix`], but the images in common are neither collected nor sorted and no
+/// matrix is built, which makes this the cheapest way to get the accuracy of a large
+/// dataset. The conditions under which an [io::Error] instance is returned are the same as
+/// for [`top1_accuracy`].
+///
+/// # Examples
+///
+/// ```rust
+/// use bagheera::classification::{ClassificationDataset, ClassificationOutput};
+/// use bagheera::metrics::accuracy;
+///
+/// let mut cls_db = ClassificationDataset::new(2u8, false);
+/// cls_db.add("india.jpg", &vec![1u8]).unwrap();
+/// cls_db.add("iran.jpg", &vec![1u8]).unwrap();
+///
+/// let mut cls_out = ClassificationOutput::<u8, f32>::new(2u8);
+/// cls_out.add("india.jpg", vec![0.1f32, 0.9f32]).unwrap();
+/// cls_out.add("iran.jpg", vec![0.6f32, 0.4f32]).unwrap();
+///
+/// assert_eq!(accuracy(&cls_db, &cls_out).unwrap(), 0.5f64);
+/// ```
+pub fn accuracy<
+    T1: num_traits::PrimInt + num_traits::Unsigned + num_traits::FromPrimitive,
+    T2: num_traits::Num + Copy + PartialOrd,
+>(
+    gt: &ClassificationDataset<T1>,
+    pred: &ClassificationOutput<T1, T2>,
+) -> Result<f64, io::Error>
+where
+    Vec<T2>: TopK,
+{
+    require_single_label(gt, "accuracy")?;
+    check_num_classes(gt, pred)?;
+
+    let mut num_images = 0usize;
+    let mut num_correct = 0usize;
+    for (imagename, one_hot) in gt.one_hot_data() {
+        let confidences = match pred.data().get(imagename) {
+            Some(confidences) => confidences,
+            None => continue,
+        };
+        let predicted = confidences.top_k(1usize)?[0];
+        match one_hot.iter().position(|x| *x) {
+            Some(true_class) => num_correct += (predicted == true_class) as usize,
+            None => return Err(errors::image_without_label(imagename)),
+        }
+        num_images += 1usize;
+    }
+    if num_images == 0usize {
+        return Err(errors::no_common_images());
+    }
+    Ok(num_correct as f64 / num_images as f64)
+}
+
+/// Returns the fraction of the images present in both `a` and `b` for which the two
+/// classifier outputs have the same top-1 prediction.
+///
+/// Images present in only one of the outputs are ignored. Ties in confidence are broken in
+/// favour of the smaller class index, as in [`TopK::top_k`]. An [io::Error] instance is
+/// returned if `a` and `b` have different number of classes, if the confidence vector of an
+/// image in common does not have one entry per class, or if the outputs have no image in
+/// common.
+///
+/// # Examples
+///
+/// ```rust
+/// use bagheera::classification::ClassificationOutput;
+/// use bagheera::metrics::agreement_rate;
+///
+/// let mut a = ClassificationOutput::<u8, f32>::new(2u8);
+/// a.add("india.jpg", vec![0.1f32, 0.9f32]).unwrap();
+/// a.add("iran.jpg", vec![0.6f32, 0.4f32]).unwrap();
+///
+/// let mut b = ClassificationOutput::<u8, f32>::new(2u8);
+/// b.add("india.jpg", vec![0.3f32, 0.7f32]).unwrap();
+/// b.add("iran.jpg", vec![0.2f32, 0.8f32]).unwrap();
+///
+/// assert_eq!(agreement_rate(&a, &b).unwrap(), 0.5f64);
+/// ```
+pub fn agreement_rate<
+    T1: num_traits::PrimInt + num_traits::Unsigned + num_traits::FromPrimitive,
+    T2: num_traits::Num + Copy + PartialOrd,
+>(
+    a: &ClassificationOutput<T1, T2>,
+    b: &ClassificationOutput<T1, T2>,
+) -> Result<f64, io::Error>
+where
+    Vec<T2>: TopK,
+{
+    if a.num_classes() != b.num_classes() {
+        return Err(errors::outputs_num_classes_mismatch(
+            a.num_classes_usize(),
+            b.num_classes_usize(),
+        ));
+    }
+    let num_classes = a.num_classes_usize();
+    let mut num_images = 0usize;
+    let mut num_agreements = 0usize;
+    for (imagename, confidences) in a.data() {
+        let other_confidences = match b.data().get(imagename) {
+            Some(other_confidences) => other_confidences,
+            None => continue,
+        };
+        for vector in [confidences, other_confidences] {
+            if vector.len() != num_classes {
+                return Err(errors::confidence_length_mismatch(
+                    imagename,
+                    vector.len(),
+                    num_classes,
+                ));
+            }
+        }
+        num_agreements +=
+            (confidences.top_k(1usize)?[0] == other_confidences.top_k(1usize)?[0]) as usize;
+        num_images += 1usize;
+    }
+    if num_images == 0usize {
+        return Err(errors::no_common_images());
+    }
+    Ok(num_agreements as f64 / num_images as f64)
+}
+
+/// Returns the [`top1_accuracy`] of `pred` when predictions below `min_conf` are replaced
+/// by `unknown_class`.
+///
+/// An image is predicted as `unknown_class` when its highest confidence is less than
+/// `min_conf`, and as its top-1 class otherwise. Images labelled with `unknown_class` in
+/// `gt` are therefore counted as correct when they are rejected, as well as when
+/// `unknown_class` itself is their top-1 class. On top of the conditions of
+/// [`top1_accuracy`], an [io::Error] instance is returned if `unknown_class` is not less
+/// than the number of classes.
+///
+/// # Examples
+///
+/// ```rust
+/// use bagheera::classification::{ClassificationDataset, ClassificationOutput};
+/// use bagheera::metrics::top1_accuracy_with_unknown;
+///
+/// let mut cls_db = ClassificationDataset::new(3u8, false);
+/// cls_db.add("india.jpg", &vec![1u8]).unwrap();
+/// cls_db.add("iran.jpg", &vec![0u8]).unwrap();
+///
+/// let mut cls_out = ClassificationOutput::<u8, f32>::new(3u8);
+/// cls_out.add("india.jpg", vec![0.1f32, 0.8f32, 0.1f32]).unwrap();
+/// cls_out.add("iran.jpg", vec![0.3f32, 0.3f32, 0.4f32]).unwrap();
+///
+/// let accuracy = top1_accuracy_with_unknown(&cls_db, &cls_out, 0.5f32, 0usize).unwrap();
+/// assert_eq!(accuracy, 1f64);
+/// ```
+pub fn top1_accuracy_with_unknown<
+    T1: num_traits::PrimInt + num_traits::Unsigned + num_traits::FromPrimitive,
+    T2: num_traits::Num + Copy + PartialOrd,
+>(
+    gt: &ClassificationDataset<T1>,
+    pred: &ClassificationOutput<T1, T2>,
+    min_conf: T2,
+    unknown_class: usize,
+) -> Result<f64, io::Error>
+where
+    Vec<T2>: TopK,
+{
+    require_single_label(gt, "top1_accuracy_with_unknown")?;
+    check_num_classes(gt, pred)?;
+    if unknown_class >= pred.num_classes_usize() {
+        return Err(errors::label_out_of_range(
+            unknown_class,
+            pred.num_classes_usize(),
+        ));
+    }
+
+    let images = common_images(gt, pred);
+    if images.is_empty() {
+        return Err(errors::no_common_images());
+    }
+    let mut num_correct = 0usize;
+    for imagename in &images {
+        let true_class = true_class(gt, imagename)?;
+        let confidences = pred.confidence_for_image(imagename)?;
+        let top1 = confidences.top_k(1usize)?[0];
+        let predicted = match confidences[top1] < min_conf {
+            true => unknown_class,
+            false => top1,
+        };
+        if predicted == true_class {
+            num_correct += 1;
+        }
+    }
+    Ok(num_correct as f64 / images.len() as f64)
+}
+
+/// Returns the name of every image present in both `gt` and `pred` along with whether its
+/// label in `gt` is the top-1 prediction in `pred`.
+///
+/// The images are sorted by name and the fraction of `true` flags equals
+/// [`top1_accuracy`]. The flags can be resampled to estimate the variability of the
+/// accuracy. The conditions under which an [io::Error] instance is returned are the same
+/// as for [`top1_accuracy`].
+///
+/// # Examples
+///
+/// ```rust
+/// use bagheera::classification::{ClassificationDataset, ClassificationOutput};
+/// use bagheera::metrics::correctness_vector;
+///
+/// let mut cls_db = ClassificationDataset::new(2u8, false);
+/// cls_db.add("india.jpg", &vec![1u8]).unwrap();
+/// cls_db.add("iran.jpg", &vec![1u8]).unwrap();
+///
+/// let mut cls_out = ClassificationOutput::<u8, f32>::new(2u8);
+/// cls_out.add("india.jpg", vec![0.1f32, 0.9f32]).unwrap();
+/// cls_out.add("iran.jpg", vec![0.6f32, 0.4f32]).unwrap();
+///
+/// assert_eq!(
+///     correctness_vector(&cls_db, &cls_out).unwrap(),
+///     vec![("india.jpg".to_string(), true), ("iran.jpg".to_string(), false)]
+/// );
+/// ```
+pub fn correctness_vector<
+    T1: num_traits::PrimInt + num_traits::Unsigned + num_traits::FromPrimitive,
+    T2: num_traits::Num + Copy + PartialOrd,
+>(
+    gt: &ClassificationDataset<T1>,
+    pred: &ClassificationOutput<T1, T2>,
+) -> Result<Vec<(String, bool)>, io::Error>
+where
+    Vec<T2>: TopK,
+{
+    require_single_label(gt, "correctness_vector")?;
+    check_num_classes(gt, pred)?;
+
+    let images = common_images(gt, pred);
+    if images.is_empty() {
+        return Err(errors::no_common_images());
+    }
+    let mut correctness = Vec::<(String, bool)>::with_capacity(images.len());
+    for imagename in images {
+        let true_class = true_class(gt, imagename)?;
+        let predicted = pred.confidence_for_image(imagename)?.top_k(1usize)?[0];
+        correctness.push((imagename.to_string(), predicted == true_class));
+    }
+    Ok(correctness)
+}
+
+/// Returns the top-1 accuracy of `pred` where every image counts as much as its weight in
+/// `weights`.
+///
+/// Only images present in both `gt` and `pred` are considered and images of `weights`
+/// outside of them are ignored. With equal weights, this is [`top1_accuracy`]. On top of
+/// the conditions of [`top1_accuracy`], an [io::Error] instance is returned if a common
+/// image does not have a weight or if the weights of the common images are negative, NaN
+/// or sum to zero.
+///
+/// # Examples
+///
+/// ```rust
+/// use std::collections::HashMap;
+/// use bagheera::classification::{ClassificationDataset, ClassificationOutput};
+/// use bagheera::metrics::weighted_accuracy;
+///
+/// let mut cls_db = ClassificationDataset::new(2u8, false);
+/// cls_db.add("india.jpg", &vec![1u8]).unwrap();
+/// cls_db.add("iran.jpg", &vec![1u8]).unwrap();
+///
+/// let mut cls_out = ClassificationOutput::<u8, f32>::new(2u8);
+/// cls_out.add("india.jpg", vec![0.1f32, 0.9f32]).unwrap();
+/// cls_out.add("iran.jpg", vec![0.6f32, 0.4f32]).unwrap();
+///
+/// let mut weights = HashMap::new();
+/// weights.insert("india.jpg".to_string(), 3f64);
+/// weights.insert("iran.jpg".to_string(), 1f64);
+/// assert_eq!(weighted_accuracy(&cls_db, &cls_out, &weights).unwrap(), 0.75f64);
+/// ```
+pub fn weighted_accuracy<
+    T1: num_traits::PrimInt + num_traits::Unsigned + num_traits::FromPrimitive,
+    T2: num_traits::Num + Copy + PartialOrd,
+>(
+    gt: &ClassificationDataset<T1>,
+    pred: &ClassificationOutput<T1, T2>,
+    weights: &HashMap<String, f64>,
+) -> Result<f64, io::Error>
+where
+    Vec<T2>: TopK,
+{
+    require_single_label(gt, "weighted_accuracy")?;
+    let mut total_weight = 0f64;
+    let mut correct_weight = 0f64;
+    for (imagename, correct) in correctness_vector(gt, pred)? {
+        let weight = match weights.get(&imagename) {
+            Some(weight) => *weight,
+            None => return Err(errors::missing_sample_weight(&imagename)),
+        };
+        if weight.is_nan() || weight < 0f64 {
+            return Err(errors::invalid_sample_weights());
+        }
+        total_weight += weight;
+        if correct {
+            correct_weight += weight;
+        }
+    }
+    if total_weight <= 0f64 {
+        return Err(errors::invalid_sample_weights());
+    }
+    Ok(correct_weight / total_weight)
+}
+
+/// Returns the [`top1_accuracy`] along with the bounds of its `1 - alpha` bootstrap
+/// confidence interval, as `(point_estimate, lower, upper)`.
+///
+/// The per-image flags of [`correctness_vector`] are resampled with replacement
+/// `n_bootstrap` times using a generator seeded with `seed`, so the interval is
+/// reproducible. The bounds are the `alpha / 2` and `1 - alpha / 2` percentiles of the
+/// resampled accuracies. On top of the conditions of [`top1_accuracy`], an [io::Error]
+/// instance is returned if `n_bootstrap` is zero or if `alpha` is not in (0, 1).
+///
+/// # Examples
+///
+/// ```rust
+/// use bagheera::classification::{ClassificationDataset, ClassificationOutput};
+/// use bagheera::metrics::accuracy_confidence_interval;
+///
+/// let mut cls_db = ClassificationDataset::new(2u8, false);
+/// let mut cls_out = ClassificationOutput::<u8, f32>::new(2u8);
+/// for i in 0..100 {
+///     let name = format!("{}.jpg", i);
+///     cls_db.add(&name, &vec![1u8]).unwrap();
+///     cls_out.add(&name, vec![(i % 4) as f32, 2f32]).unwrap();
+/// }
+///
+/// let (accuracy, lower, upper) =
+///     accuracy_confidence_interval(&cls_db, &cls_out, 1000usize, 0.05f64, 42u64).unwrap();
+/// assert_eq!(accuracy, 0.5f64);
+/// assert!(lower <= accuracy && accuracy <= upper);
+/// ```
+pub fn accuracy_confidence_interval<
+    T1: num_traits::PrimInt + num_traits::Unsigned + num_traits::FromPrimitive,
+    T2: num_traits::Num + Copy + PartialOrd,
+>(
+    gt: &ClassificationDataset<T1>,
+    pred: &ClassificationOutput<T1, T2>,
+    n_bootstrap: usize,
+    alpha: f64,
+    seed: u64,
+) -> Result<(f64, f64, f64), io::Error>
+where
+    Vec<T2>: TopK,
+{
+    if n_bootstrap == 0usize || !(alpha > 0f64 && alpha < 1f64) {
+        return Err(errors::invalid_bootstrap_parameters(n_bootstrap, alpha));
+    }
+    let correctness = correctness_vector(gt, pred)?
+        .into_iter()
+        .map(|(_, correct)| correct)
+        .collect::<Vec<bool>>();
+    let num_images = correctness.len();
+    let point_estimate = correctness.iter().filter(|x| **x).count() as f64 / num_images as f64;
+
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut estimates = (0..n_bootstrap)
+        .map(|_| {
+            let num_correct = (0..num_images)
+                .filter(|_| correctness[rng.gen_range(0usize..num_images)])
+                .count();
+            num_correct as f64 / num_images as f64
+        })
+        .collect::<Vec<f64>>();
+    estimates.sort_unstable_by(|a, b| a.partial_cmp(b).unwrap());
+    let percentile = |q: f64| estimates[(q * (n_bootstrap - 1usize) as f64).round() as usize];
+    Ok((
+        point_estimate,
+        percentile(alpha / 2f64),
+        percentile(1f64 - alpha / 2f64),
+    ))
+}
+
+/// Returns `(coverage, accuracy)` points of the accuracy of `pred` when only its most
+/// confident top-1 predictions are accepted.
+///
+/// The images are sorted by decreasing top-1 confidence, ties being broken by image name.
+/// For each of the `steps` points, the smallest number of images covering at least
+/// `step / steps` of them is accepted, starting from the most confident ones. The coverage
+/// is the fraction of accepted images and the accuracy is the [`top1_accuracy`] over them,
+/// so the last point has a coverage of `1.0` and the overall accuracy. On top of the
+/// conditions of [`top1_accuracy`], an [io::Error] instance is returned if `steps` is zero.
+///
+/// # Examples
+///
+/// ```rust
+/// use bagheera::classification::{ClassificationDataset, ClassificationOutput};
+/// use bagheera::metrics::risk_coverage_curve;
+///
+/// let mut cls_db = ClassificationDataset::new(2u8, false);
+/// cls_db.add("india.jpg", &vec![1u8]).unwrap();
+/// cls_db.add("iran.jpg", &vec![1u8]).unwrap();
+///
+/// let mut cls_out = ClassificationOutput::<u8, f32>::new(2u8);
+/// cls_out.add("india.jpg", vec![0.1f32, 0.9f32]).unwrap();
+/// cls_out.add("iran.jpg", vec![0.6f32, 0.4f32]).unwrap();
+///
+/// let curve = risk_coverage_curve(&cls_db, &cls_out, 2usize).unwrap();
+/// assert_eq!(curve, vec![(0.5f64, 1f64), (1f64, 0.5f64)]);
+/// ```
+pub fn risk_coverage_curve<
+    T1: num_traits::PrimInt + num_traits::Unsigned + num_traits::FromPrimitive,
+    T2: num_traits::Num + Copy + PartialOrd,
+>(
+    gt: &ClassificationDataset<T1>,
+    pred: &ClassificationOutput<T1, T2>,
+    steps: usize,
+) -> Result<Vec<(f64, f64)>, io::Error>
+where
+    Vec<T2>: TopK,
+{
+    require_single_label(gt, "risk_coverage_curve")?;
+    if steps == 0usize {
+        return Err(errors::no_curve_steps());
+    }
+    let mut samples = Vec::<(T2, bool)>::new();
+    for (imagename, correct) in correctness_vector(gt, pred)? {
+        let confidences = pred.confidence_for_image(&imagename)?;
+        samples.push((confidences[confidences.top_k(1usize)?[0]], correct));
+    }
+    samples.sort_by(|lhs, rhs| rhs.0.partial_cmp(&lhs.0).unwrap());
+
+    let num_images = samples.len();
+    let mut num_correct = Vec::<usize>::with_capacity(num_images + 1usize);
+    num_correct.push(0usize);
+    for (_, correct) in &samples {
+        num_correct.push(num_correct.last().unwrap() + *correct as usize);
+    }
+    Ok((1usize..=steps)
+        .map(|step| {
+            let num_accepted = (step * num_images).div_ceil(steps);
+            (
+                num_accepted as f64 / num_images as f64,
+                num_correct[num_accepted] as f64 / num_accepted as f64,
+            )
+        })
+        .collect())
+}
+
+/// Returns the area under the risk-coverage curve of `pred`, where the risk is one minus
+/// the accuracy.
+///
+/// The curve is the [`risk_coverage_curve`] with one point per image, extended to a
+/// coverage of zero with the risk of the most confident image, and the area is computed
+/// with the trapezoidal rule. Lower is better: confidences ranking the correct predictions
+/// before the wrong ones give the smallest area achievable for a given accuracy. The
+/// conditions under which an [io::Error] instance is returned are the same as for
+/// [`top1_accuracy`].
+///
+/// # Examples
+///
+/// ```rust
+/// use bagheera::classification::{ClassificationDataset, ClassificationOutput};
+/// use bagheera::metrics::aurc;
+///
+/// let mut cls_db = ClassificationDataset::new(2u8, false);
+/// cls_db.add("india.jpg", &vec![1u8]).unwrap();
+/// cls_db.add("iran.jpg", &vec![1u8]).unwrap();
+///
+/// let mut cls_out = ClassificationOutput::<u8, f32>::new(2u8);
+/// cls_out.add("india.jpg", vec![0.1f32, 0.9f32]).unwrap();
+/// cls_out.add("iran.jpg", vec![0.6f32, 0.4f32]).unwrap();
+///
+/// assert_eq!(aurc(&cls_db, &cls_out).unwrap(), 0.125f64);
+/// ```
+pub fn aurc<
+    T1: num_traits::PrimInt + num_traits::Unsigned + num_traits::FromPrimitive,
+    T2: num_traits::Num + Copy + PartialOrd,
+>(
+    gt: &ClassificationDataset<T1>,
+    pred: &ClassificationOutput<T1, T2>,
+) -> Result<f64, io::Error>
+where
+    Vec<T2>: TopK,
+{
+    require_single_label(gt, "aurc")?;
+    check_num_classes(gt, pred)?;
+    let num_images = common_images(gt, pred).len();
+    if num_images == 0usize {
+        return Err(errors::no_common_images());
+    }
+
+    let curve = risk_coverage_curve(gt, pred, num_images)?;
+    let mut area = 0f64;
+    let (mut coverage, mut risk) = (0f64, 1f64 - curve[0].1);
+    for (next_coverage, accuracy) in curve {
+        let next_risk = 1f64 - accuracy;
+        area += (next_coverage - coverage) * (risk + next_risk) / 2f64;
+        coverage = next_coverage;
+        risk = next_risk;
+    }
+    Ok(area)
+}
+
+/// Returns the mean over all images of `1 / (rank + 1)`, where `rank` is the position of
+/// the label in `gt` among the classes of `pred` sorted by descending confidence.
+///
+/// The rank is computed by [`ClassificationOutput::rank_of_class()`]. Only images present in
+/// both `gt` and `pred` are considered. An [io::Error] instance is returned if `gt` is
+/// multi-label, if `gt` and `pred` have different number of classes or if there is no
+/// image in common.
+///
+/// # Examples
+///
+/// ```rust
+/// use bagheera::classification::{ClassificationDataset, ClassificationOutput};
+/// use bagheera::metrics::mean_reciprocal_rank;
+///
+/// let mut cls_db = ClassificationDataset::new(3u8, false);
+/// cls_db.add("india.jpg", &vec![0u8]).unwrap();
+/// cls_db.add("iran.jpg", &vec![2u8]).unwrap();
+///
+/// let mut cls_out = ClassificationOutput::<u8, f32>::new(3u8);
+/// cls_out.add("india.jpg", vec![0.8f32, 0.1f32, 0.1f32]).unwrap();
+/// cls_out.add("iran.jpg", vec![0.2f32, 0.7f32, 0.1f32]).unwrap();
+///
+/// assert_eq!(mean_reciprocal_rank(&cls_db, &cls_out).unwrap(), (1f64 + 1f64 / 3f64) / 2f64);
+/// ```
+pub fn mean_reciprocal_rank<
+    T1: num_traits::PrimInt + num_traits::Unsigned + num_traits::FromPrimitive,
+    T2: num_traits::Num + Copy + PartialOrd,
+>(
+    gt: &ClassificationDataset<T1>,
+    pred: &ClassificationOutput<T1, T2>,
+) -> Result<f64, io::Error> {
+    require_single_label(gt, "mean_reciprocal_rank")?;
+    check_num_classes(gt, pred)?;
+
+    let images = common_images(gt, pred);
+    if images.is_empty() {
+        return Err(errors::no_common_images());
+    }
+    let mut total = 0f64;
+    for imagename in &images {
+        let rank = pred.rank_of_class(imagename, true_class(gt, imagename)?)?;
+        total += 1f64 / (rank + 1usize) as f64;
+    }
+    Ok(total / images.len() as f64)
+}
+
+/// Returns the mean over all images of `1 / (rank + 1)` when the label in `gt` is among the
+/// top-`k` classes of `pred` at position `rank`, and of zero otherwise.
+///
+/// This lies between the [`top1_accuracy`], which it equals for `k = 1`, and the
+/// [`topk_accuracy`], as a correct class ranked lower earns less credit. It is the
+/// [`mean_reciprocal_rank`] cut off at `k`. Ties are broken as in [`TopK::top_k`]. Only
+/// images present in both `gt` and `pred` are considered. An [io::Error] instance is
+/// returned if `gt` is multi-label, if `gt` and `pred` have different number of classes,
+/// if `k` is not between 1 and the number of classes or if there is no image in common.
+///
+/// # Examples
+///
+/// ```rust
+/// use bagheera::classification::{ClassificationDataset, ClassificationOutput};
+/// use bagheera::metrics::rank_weighted_accuracy;
+///
+/// let mut cls_db = ClassificationDataset::new(3u8, false);
+/// cls_db.add("india.jpg", &vec![0u8]).unwrap();
+/// cls_db.add("iran.jpg", &vec![2u8]).unwrap();
+///
+/// let mut cls_out = ClassificationOutput::<u8, f32>::new(3u8);
+/// cls_out.add("india.jpg", vec![0.3f32, 0.6f32, 0.1f32]).unwrap();
+/// cls_out.add("iran.jpg", vec![0.2f32, 0.7f32, 0.1f32]).unwrap();
+///
+/// assert_eq!(rank_weighted_accuracy(&cls_db, &cls_out, 2usize).unwrap(), 0.25f64);
+/// ```
+pub fn rank_weighted_accuracy<
+    T1: num_traits::PrimInt + num_traits::Unsigned + num_traits::FromPrimitive,
+    T2: num_traits::Num + Copy + PartialOrd,
+>(
+    gt: &ClassificationDataset<T1>,
+    pred: &ClassificationOutput<T1, T2>,
+    k: usize,
+) -> Result<f64, io::Error>
+where
+    Vec<T2>: TopK,
+{
+    require_single_label(gt, "rank_weighted_accuracy")?;
+    check_num_classes(gt, pred)?;
+    if k == 0usize || k > pred.num_classes_usize() {
+        return Err(errors::cutoff_incorrect_k(k, pred.num_classes_usize()));
+    }
+
+    let images = common_images(gt, pred);
+    if images.is_empty() {
+        return Err(errors::no_common_images());
+    }
+    let mut total = 0f64;
+    for imagename in &images {
+        let label = true_class(gt, imagename)?;
+        if let Some(rank) = pred
+            .topk_for_image(imagename, k)?
+            .iter()
+            .position(|class| *class == label)
+        {
+            total += 1f64 / (rank + 1usize) as f64;
+        }
+    }
+    Ok(total / images.len() as f64)
+}
+
+/// Returns the mean normalized discounted cumulative gain at `k` of the rankings of
+/// `pred`, the labels of each image in `gt` having a gain of 1.
+///
+/// For every image, the top-`k` classes of `pred` are ranked by descending confidence and
+/// the gain of the label at rank `i`, starting at 0, is discounted by `log2(i + 2)`. The
+/// sum is divided by that of a ranking placing every label first. Images without any label
+/// in `gt` have no ideal ranking and are excluded from the mean. Only images present in both
+/// `gt` and `pred` are considered. An [io::Error] instance is returned if `gt` is
+/// single-label, if `gt` and `pred` have different number of classes, if `k` is not
+/// between 1 and the number of classes, if there is no image in common or if none of them
+/// has a label.
+///
+/// # Examples
+///
+/// ```rust
+/// use bagheera::classification::{ClassificationDataset, ClassificationOutput};
+/// use bagheera::metrics::ndcg;
+///
+/// let mut cls_db = ClassificationDataset::new(3u8, true);
+/// cls_db.add("india.jpg", &vec![0u8, 2u8]).unwrap();
+///
+/// let mut cls_out = ClassificationOutput::<u8, f32>::new(3u8);
+/// cls_out.add("india.jpg", vec![0.8f32, 0.1f32, 0.6f32]).unwrap();
+///
+/// assert_eq!(ndcg(&cls_db, &cls_out, 2usize).unwrap(), 1f64);
+/// ```
+pub fn ndcg<
+    T1: num_traits::PrimInt + num_traits::Unsigned + num_traits::FromPrimitive,
+    T2: num_traits::Num + Copy + PartialOrd,
+>(
+    gt: &ClassificationDataset<T1>,
+    pred: &ClassificationOutput<T1, T2>,
+    k: usize,
+) -> Result<f64, io::Error>
+where
+    Vec<T2>: TopK,
+{
+    require_multilabel(gt, "ndcg")?;
+    check_num_classes(gt, pred)?;
+    if k == 0usize || k > pred.num_classes_usize() {
+        return Err(errors::cutoff_incorrect_k(k, pred.num_classes_usize()));
+    }
+
+    let images = common_images(gt, pred);
+    if images.is_empty() {
+        return Err(errors::no_common_images());
+    }
+    let discount = |rank: usize| 1f64 / ((rank + 2usize) as f64).log2();
+    let mut total = 0f64;
+    let mut num_labelled = 0usize;
+    for imagename in &images {
+        let one_hot = gt.get_gt(imagename)?;
+        let num_labels = one_hot.iter().filter(|x| **x).count();
+        if num_labels == 0usize {
+            continue;
+        }
+        let dcg = pred
+            .confidence_for_image(imagename)?
+            .top_k(k)?
+            .into_iter()
+            .enumerate()
+            .filter(|(_, class)| one_hot[*class])
+            .map(|(rank, _)| discount(rank))
+            .sum::<f64>();
+        let idcg = (0usize..num_labels.min(k)).map(discount).sum::<f64>();
+        total += dcg / idcg;
+        num_labelled += 1;
+    }
+    if num_labelled == 0usize {
+        return Err(errors::no_labelled_images());
+    }
+    Ok(total / num_labelled as f64)
+}
+
+/// Returns the fraction of images whose labels in `gt` are exactly the classes of `pred`
+/// with a confidence of at least `threshold`.
+///
+/// This is the exact-match ratio of a multi-label classifier for a global threshold. Only
+/// images present in both `gt` and `pred` are considered. An [io::Error] instance is
+/// returned if `gt` is single-label, if `gt` and `pred` have different number of classes
+/// or if there is no image in common.
+///
+/// # Examples
+///
+/// ```rust
+/// use bagheera::classification::{ClassificationDataset, ClassificationOutput};
+/// use bagheera::metrics::subset_accuracy;
+///
+/// let mut cls_db = ClassificationDataset::new(3u8, true);
+/// cls_db.add("india.jpg", &vec![0u8, 2u8]).unwrap();
+/// cls_db.add("iran.jpg", &vec![1u8]).unwrap();
+///
+/// let mut cls_out = ClassificationOutput::<u8, f32>::new(3u8);
+/// cls_out.add("india.jpg", vec![0.8f32, 0.1f32, 0.6f32]).unwrap();
+/// cls_out.add("iran.jpg", vec![0.7f32, 0.9f32, 0.1f32]).unwrap();
+///
+/// assert_eq!(subset_accuracy(&cls_db, &cls_out, 0.5f32).unwrap(), 0.5f64);
+/// ```
+pub fn subset_accuracy<
+    T1: num_traits::PrimInt + num_traits::Unsigned + num_traits::FromPrimitive,
+    T2: num_traits::Num + Copy + PartialOrd,
+>(
+    gt: &ClassificationDataset<T1>,
+    pred: &ClassificationOutput<T1, T2>,
+    threshold: T2,
+) -> Result<f64, io::Error> {
+    require_multilabel(gt, "subset_accuracy")?;
+    check_num_classes(gt, pred)?;
+    subset_accuracy_with_thresholds(gt, pred, &vec![threshold; pred.num_classes_usize()])
+}
+
+/// Returns the [`subset_accuracy`] of `pred` using the threshold of each class in
+/// `thresholds`.
+///
+/// On top of the conditions of [`subset_accuracy`], an [io::Error] instance is returned if
+/// `thresholds` does not have one entry per class.
+///
+/// # Examples
+///
+/// ```rust
+/// use bagheera::classification::{ClassificationDataset, ClassificationOutput};
+/// use bagheera::metrics::subset_accuracy_with_thresholds;
+///
+/// let mut cls_db = ClassificationDataset::new(3u8, true);
+/// cls_db.add("india.jpg", &vec![0u8, 2u8]).unwrap();
+/// cls_db.add("iran.jpg", &vec![1u8]).unwrap();
+///
+/// let mut cls_out = ClassificationOutput::<u8, f32>::new(3u8);
+/// cls_out.add("india.jpg", vec![0.8f32, 0.1f32, 0.6f32]).unwrap();
+/// cls_out.add("iran.jpg", vec![0.7f32, 0.9f32, 0.1f32]).unwrap();
+///
+/// let thresholds = [0.75f32, 0.5f32, 0.5f32];
+/// assert_eq!(
+///     subset_accuracy_with_thresholds(&cls_db, &cls_out, &thresholds).unwrap(),
+///     1f64
+/// );
+/// ```
+pub fn subset_accuracy_with_thresholds<
+    T1: num_traits::PrimInt + num_traits::Unsigned + num_traits::FromPrimitive,
+    T2: num_traits::Num + Copy + PartialOrd,
+>(
+    gt: &ClassificationDataset<T1>,
+    pred: &ClassificationOutput<T1, T2>,
+    thresholds: &[T2],
+) -> Result<f64, io::Error> {
+    require_multilabel(gt, "subset_accuracy")?;
+    check_num_classes(gt, pred)?;
+    if thresholds.len() != pred.num_classes_usize() {
+        return Err(errors::thresholds_length_mismatch(
+            thresholds.len(),
+            pred.num_classes_usize(),
+        ));
+    }
+
+    let images = common_images(gt, pred);
+    if images.is_empty() {
+        return Err(errors::no_common_images());
+    }
+    let mut num_correct = 0usize;
+    for imagename in &images {
+        let mut predicted = vec![false; pred.num_classes_usize()];
+        for class in pred.predicted_labels_with_thresholds(imagename, thresholds)? {
+            predicted[class] = true;
+        }
+        if gt.get_gt(imagename)? == &predicted {
+            num_correct += 1;
+        }
+    }
+    Ok(num_correct as f64 / images.len() as f64)
+}
+
+/// Returns, for each class, the threshold among `candidates` which maximizes the F1 score
+/// of that class.
+///
+/// A class is predicted for an image when its confidence is at least the threshold, as in
+/// [`ClassificationOutput::predicted_labels_with_thresholds()`], so the result can be fed
+/// back to it. The F1 score is set to zero when its denominator is zero, and ties are
+/// broken in favour of the earliest candidate. Only images present in both `gt` and `pred`
+/// are considered. An [io::Error] instance is returned if `candidates` is empty, if `gt`
+/// and `pred` have different number of classes or if there is no image in common.
+///
+/// # Examples
+///
+/// ```rust
+/// use bagheera::classification::{ClassificationDataset, ClassificationOutput};
+/// use bagheera::metrics::optimal_thresholds_f1;
+///
+/// let mut cls_db = ClassificationDataset::new(2u8, true);
+/// cls_db.add("india.jpg", &vec![0u8, 1u8]).unwrap();
+/// cls_db.add("iran.jpg", &vec![0u8]).unwrap();
+///
+/// let mut cls_out = ClassificationOutput::<u8, f32>::new(2u8);
+/// cls_out.add("india.jpg", vec![0.8f32, 0.3f32]).unwrap();
+/// cls_out.add("iran.jpg", vec![0.6f32, 0.2f32]).unwrap();
+///
+/// let candidates = [0.25f32, 0.5f32, 0.75f32];
+/// let thresholds = optimal_thresholds_f1(&cls_db, &cls_out, &candidates).unwrap();
+/// assert_eq!(thresholds, vec![0.25f32, 0.25f32]);
+/// ```
+pub fn optimal_thresholds_f1<
+    T1: num_traits::PrimInt + num_traits::Unsigned + num_traits::FromPrimitive,
+    T2: num_traits::Num + Copy + PartialOrd,
+>(
+    gt: &ClassificationDataset<T1>,
+    pred: &ClassificationOutput<T1, T2>,
+    candidates: &[T2],
+) -> Result<Vec<T2>, io::Error> {
+    if candidates.is_empty() {
+        return Err(errors::no_threshold_candidates());
+    }
+    check_num_classes(gt, pred)?;
+    let images = common_images(gt, pred);
+    if images.is_empty() {
+        return Err(errors::no_common_images());
+    }
+
+    let num_classes = pred.num_classes_usize();
+    // counts[class][candidate] holds the (true positive, false positive, false negative)
+    // counts of the class for the candidate threshold.
+    let mut counts = vec![vec![(0usize, 0usize, 0usize); candidates.len()]; num_classes];
+    for imagename in &images {
+        let one_hot = gt.get_gt(imagename)?;
+        let confidences = pred.confidence_for_image(imagename)?;
+        for class in 0usize..num_classes {
+            for (candidate, threshold) in candidates.iter().enumerate() {
+                let count = &mut counts[class][candidate];
+                match (one_hot[class], confidences[class] >= *threshold) {
+                    (true, true) => count.0 += 1,
+                    (false, true) => count.1 += 1,
+                    (true, false) => count.2 += 1,
+                    (false, false) => {}
+                }
+            }
+        }
+    }
+
+    Ok(counts
+        .iter()
+        .map(|class_counts| {
+            let mut best = (0usize, -1f64);
+            for (candidate, (tp, fp, fn_)) in class_counts.iter().enumerate() {
+                let denominator = 2 * tp + fp + fn_;
+                let f1 = match denominator {
+                    0 => 0f64,
+                    _ => (2 * tp) as f64 / denominator as f64,
+                };
+                if f1 > best.1 {
+                    best = (candidate, f1);
+                }
+            }
+            candidates[best.0]
+        })
+        .collect())
+}
+
+/// Returns the average precision of `samples`, which pair a confidence with whether the
+/// image is a positive, given their number `num_positives` of positives.
+///
+/// Images with equal confidences are accepted together, so the result does not depend on
+/// the order of `samples`.
+fn average_precision<T2: num_traits::Num + Copy + PartialOrd>(
+    mut samples: Vec<(T2, bool)>,
+    num_positives: usize,
+) -> f64 {
+    samples.sort_by(|lhs, rhs| rhs.0.partial_cmp(&lhs.0).unwrap());
+    let mut true_positives = 0usize;
+    let mut accepted = 0usize;
+    let mut previous_recall = 0f64;
+    let mut ap = 0f64;
+    for (position, (confidence, positive)) in samples.iter().enumerate() {
+        true_positives += *positive as usize;
+        accepted += 1usize;
+        if samples
+            .get(position + 1usize)
+            .is_some_and(|next| next.0 == *confidence)
+        {
+            continue;
+        }
+        let recall = true_positives as f64 / num_positives as f64;
+        ap += (recall - previous_recall) * true_positives as f64 / accepted as f64;
+        previous_recall = recall;
+    }
+    ap
+}
+
+/// Returns the mean over the classes of the average precision of `pred`.
+///
+/// For each class, the images in common are ranked by decreasing confidence for that
+/// class, and the average precision is the sum over the distinct confidences of the
+/// precision at that confidence weighted by the increase in recall. Classes without any
+/// positive image in `gt` have no average precision and are left out. With `weighted` set
+/// to false, this is the usual macro mAP in which every class counts equally. With
+/// `weighted` set to true, the average precision of each class is weighted by its number
+/// of positive images, so frequent classes dominate the result. Both coincide when every
+/// class has the same number of positives. An [io::Error] instance is returned if `gt` and
+/// `pred` have different number of classes, if there is no image in common, or if no class
+/// has a positive image.
+///
+/// # Examples
+///
+/// ```rust
+/// use bagheera::classification::{ClassificationDataset, ClassificationOutput};
+/// use bagheera::metrics::mean_average_precision;
+///
+/// let mut cls_db = ClassificationDataset::new(2u8, true);
+/// cls_db.add("india.jpg", &vec![0u8, 1u8]).unwrap();
+/// cls_db.add("iran.jpg", &vec![1u8]).unwrap();
+///
+/// let mut cls_out = ClassificationOutput::<u8, f32>::new(2u8);
+/// cls_out.add("india.jpg", vec![0.8f32, 0.3f32]).unwrap();
+/// cls_out.add("iran.jpg", vec![0.2f32, 0.9f32]).unwrap();
+///
+/// assert_eq!(mean_average_precision(&cls_db, &cls_out, false).unwrap(), 1f64);
+/// ```
+pub fn mean_average_precision<
+    T1: num_traits::PrimInt + num_traits::Unsigned + num_traits::FromPrimitive,
+    T2: num_traits::Num + Copy + PartialOrd,
+>(
+    gt: &ClassificationDataset<T1>,
+    pred: &ClassificationOutput<T1, T2>,
+    weighted: bool,
+) -> Result<f64, io::Error> {
+    check_num_classes(gt, pred)?;
+    let images = common_images(gt, pred);
+    if images.is_empty() {
+        return Err(errors::no_common_images());
+    }
+
+    let mut sum = 0f64;
+    let mut total_weight = 0f64;
+    for class in 0usize..gt.num_classes_usize() {
+        let mut samples = Vec::<(T2, bool)>::with_capacity(images.len());
+        for imagename in &images {
+            samples.push((
+                pred.confidence_for_image(imagename)?[class],
+                gt.get_gt(imagename)?[class],
+            ));
+        }
+        let num_positives = samples.iter().filter(|(_, positive)| *positive).count();
+        if num_positives == 0usize {
+            continue;
+        }
+        let weight = match weighted {
+            true => num_positives as f64,
+            false => 1f64,
+        };
+        sum += weight * average_precision(samples, num_positives);
+        total_weight += weight;
+    }
+    if total_weight == 0f64 {
+        return Err(errors::no_labelled_images());
+    }
+    Ok(sum / total_weight)
+}
+
+/// Struct holding every metric of a [`ClassificationReport`] as plain data.
+///
+/// Instances are returned by [`ClassificationReport::summary()`]. With the `serde` feature,
+/// they can be serialized, for instance to JSON, to compare the metrics of different runs.
+/// Per-class vectors are indexed by the class.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MetricsSummary {
+    /// Number of images the metrics are computed over.
+    pub num_images: usize,
+    /// Top-1 accuracy.
+    pub accuracy: f64,
+    /// Unweighted mean of the per-class F1 scores.
+    pub macro_f1: f64,
+    /// F1 score of the counts pooled over all classes.
+    pub micro_f1: f64,
+    /// Mean of the per-class F1 scores weighted by the support of each class.
+    pub weighted_f1: f64,
+    /// Per-class precision.
+    pub precision: Vec<f64>,
+    /// Per-class recall.
+    pub recall: Vec<f64>,
+    /// Per-class F1 score.
+    pub f1: Vec<f64>,
+    /// Per-class number of groundtruth images.
+    pub support: Vec<usize>,
+}
+
+/// Struct holding the per-class and averaged metrics of a single-label classifier.
+///
+/// Per-class vectors are indexed by the class. The precision, recall and F1 score of a
+/// class are set to zero when their denominator is zero.
+pub struct ClassificationReport {
+    num_images: usize,
+    accuracy: f64,
+    micro_f1: f64,
+    precision: Vec<f64>,
+    recall: Vec<f64>,
+    f1: Vec<f64>,
+    support: Vec<usize>,
+}
+
+impl ClassificationReport {
+    /// Creates a new instance of [`Self`] from a [`ConfusionMatrix`].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use bagheera::metrics::{ClassificationReport, ConfusionMatrix};
+    /// let mut cm = ConfusionMatrix::new(2usize);
+    /// cm.add(0usize, 0usize).unwrap();
+    /// cm.add(1usize, 0usize).unwrap();
+    /// let report = ClassificationReport::from_confusion_matrix(&cm);
+    /// assert_eq!(report.accuracy(), 0.5f64);
+    /// assert_eq!(report.precision(), &[0.5f64, 0f64]);
+    /// assert_eq!(report.recall(), &[1f64, 0f64]);
+    /// ```
+    pub fn from_confusion_matrix(cm: &ConfusionMatrix) -> Self {
+        let num_classes = cm.num_classes();
+        let num_images = cm.total();
+        let mut precision = vec![0f64; num_classes];
+        let mut recall = vec![0f64; num_classes];
+        let mut f1 = vec![0f64; num_classes];
+        let mut support = vec![0usize; num_classes];
+        let mut num_correct = 0usize;
+        for class in 0..num_classes {
+            let true_positives = cm.count(class, class);
+            let predicted = (0..num_classes).map(|x| cm.count(x, class)).sum::<usize>();
+            support[class] = (0..num_classes).map(|x| cm.count(class, x)).sum::<usize>();
+            num_correct += true_positives;
+            if predicted > 0 {
+                precision[class] = true_positives as f64 / predicted as f64;
+            }
+            if support[class] > 0 {
+                recall[class] = true_positives as f64 / support[class] as f64;
+            }
+            if precision[class] + recall[class] > 0f64 {
+                f1[class] =
+                    2f64 * precision[class] * recall[class] / (precision[class] + recall[class]);
+            }
+        }
+        let accuracy = match num_images {
+            0 => 0f64,
+            _ => num_correct as f64 / num_images as f64,
+        };
+        ClassificationReport {
+            num_images,
+            accuracy,
+            micro_f1: accuracy,
+            precision,
+            recall,
+            f1,
+            support,
+        }
+    }
+
+    /// Creates a new instance of [`Self`] from a [`ConfusionMatrix`], restricted to the
+    /// classes listed in `classes`.
+    ///
+    /// The per-class vectors have one entry per listed class, in the order of `classes`, and
+    /// each entry is the same as in [`Self::from_confusion_matrix()`]. In particular,
+    /// images of other classes predicted as a listed class still lower its precision. Images
+    /// of other classes are otherwise ignored: the accuracy is computed over the images of
+    /// the listed classes only, and the number of images is their total support. The micro
+    /// F1 score pools the counts of the listed classes. An [io::Error] instance is returned
+    /// if a class is listed twice or is not less than the number of classes.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use bagheera::metrics::{ClassificationReport, ConfusionMatrix};
+    /// let mut cm = ConfusionMatrix::new(3usize);
+    /// cm.add(0usize, 0usize).unwrap();
+    /// cm.add(1usize, 0usize).unwrap();
+    /// cm.add(2usize, 2usize).unwrap();
+    /// let report = ClassificationReport::for_classes(&cm, &[0usize]).unwrap();
+    /// assert_eq!(report.num_images(), 1usize);
+    /// assert_eq!(report.accuracy(), 1f64);
+    /// assert_eq!(report.precision(), &[0.5f64]);
+    /// ```
+    pub fn for_classes(cm: &ConfusionMatrix, classes: &[usize]) -> Result<Self, io::Error> {
+        let num_classes = cm.num_classes();
+        let mut is_listed = vec![false; num_classes];
+        for class in classes {
+            if *class >= num_classes {
+                return Err(errors::label_out_of_range(*class, num_classes));
+            }
+            if is_listed[*class] {
+                return Err(errors::duplicate_class(*class));
+            }
+            is_listed[*class] = true;
+        }
+
+        let full = Self::from_confusion_matrix(cm);
+        let select = |values: &[f64]| classes.iter().map(|class| values[*class]).collect();
+        let support = classes
+            .iter()
+            .map(|class| full.support[*class])
+            .collect::<Vec<usize>>();
+        let num_images = support.iter().sum::<usize>();
+        let true_positives = classes
+            .iter()
+            .map(|class| cm.count(*class, *class))
+            .sum::<usize>();
+        let predicted = classes
+            .iter()
+            .map(|class| (0..num_classes).map(|x| cm.count(x, *class)).sum::<usize>())
+            .sum::<usize>();
+        let accuracy = match num_images {
+            0 => 0f64,
+            _ => true_positives as f64 / num_images as f64,
+        };
+        let micro_f1 = match num_images + predicted {
+            0 => 0f64,
+            total => 2f64 * true_positives as f64 / total as f64,
+        };
+        Ok(ClassificationReport {
+            num_images,
+            accuracy,
+            micro_f1,
+            precision: select(&full.precision),
+            recall: select(&full.recall),
+            f1: select(&full.f1),
+            support,
+        })
+    }
+
+    /// Returns the number of images used to compute the [`Self`] instance.
+    #[inline(always)]
+    pub fn num_images(&self) -> usize {
+        self.num_images
+    }
+
+    /// Returns the top-1 accuracy.
+    #[inline(always)]
+    pub fn accuracy(&self) -> f64 {
+        self.accuracy
+    }
+
+    /// Returns the per-class precision.
+    #[inline(always)]
+    pub fn precision(&self) -> &[f64] {
+        &self.precision
+    }
+
+    /// Returns the per-class recall.
+    #[inline(always)]
+    pub fn recall(&self) -> &[f64] {
+        &self.recall
+    }
+
+    /// Returns the per-class F1 score.
+    #[inline(always)]
+    pub fn f1(&self) -> &[f64] {
+        &self.f1
+    }
+
+    /// Returns the per-class number of groundtruth images.
+    ///
+    /// Only the images with a prediction are counted, so this equals
+    /// [`ClassificationDataset::support()`] when every groundtruth image has one.
+    #[inline(always)]
+    pub fn support(&self) -> &[usize] {
+        &self.support
+    }
+
+    /// Returns the unweighted mean of the per-class F1 scores.
+    pub fn macro_f1(&self) -> f64 {
+        match self.f1.len() {
+            0 => 0f64,
+            _ => self.f1.iter().sum::<f64>() / self.f1.len() as f64,
+        }
+    }
+
+    /// Returns the mean of the per-class F1 scores weighted by the support of each class.
+    pub fn weighted_f1(&self) -> f64 {
+        match self.num_images {
+            0 => 0f64,
+            _ => {
+                self.f1
+                    .iter()
+                    .zip(self.support.iter())
+                    .map(|(f1, support)| f1 * *support as f64)
+                    .sum::<f64>()
+                    / self.num_images as f64
+            }
+        }
+    }
+
+    /// Returns the F1 score computed from the counts pooled over all classes.
+    ///
+    /// For single-label classification over all classes this is equal to the accuracy.
+    #[inline(always)]
+    pub fn micro_f1(&self) -> f64 {
+        self.micro_f1
+    }
+
+    /// Returns a [`MetricsSummary`] holding every metric of the [`Self`] instance.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use bagheera::metrics::{ClassificationReport, ConfusionMatrix};
+    /// let mut cm = ConfusionMatrix::new(2usize);
+    /// cm.add(0usize, 0usize).unwrap();
+    /// cm.add(1usize, 0usize).unwrap();
+    /// let summary = ClassificationReport::from_confusion_matrix(&cm).summary();
+    /// assert_eq!(summary.accuracy, 0.5f64);
+    /// assert_eq!(summary.support, vec![1usize, 1usize]);
+    /// ```
+    pub fn summary(&self) -> MetricsSummary {
+        MetricsSummary {
+            num_images: self.num_images,
+            accuracy: self.accuracy,
+            macro_f1: self.macro_f1(),
+            micro_f1: self.micro_f1(),
+            weighted_f1: self.weighted_f1(),
+            precision: self.precision.clone(),
+            recall: self.recall.clone(),
+            f1: self.f1.clone(),
+            support: self.support.clone(),
+        }
+    }
+}
+
+/// Returns the [`ClassificationReport`] of the top-1 predictions in `pred` against the
+/// labels in `gt`.
+///
+/// The conditions under which an [io::Error] instance is returned are the same as for
+/// [`confusion_matrix`].
+///
+/// # Examples
+///
+/// ```rust
+/// use bagheera::classification::{ClassificationDataset, ClassificationOutput};
+/// use bagheera::metrics::classification_report;
+///
+/// let mut cls_db = ClassificationDataset::new(2u8, false);
+/// cls_db.add("india.jpg", &vec![0u8]).unwrap();
+/// cls_db.add("iran.jpg", &vec![1u8]).unwrap();
+///
+/// let mut cls_out = ClassificationOutput::<u8, f32>::new(2u8);
+/// cls_out.add("india.jpg", vec![0.8f32, 0.2f32]).unwrap();
+/// cls_out.add("iran.jpg", vec![0.6f32, 0.4f32]).unwrap();
+///
+/// let report = classification_report(&cls_db, &cls_out).unwrap();
+/// assert_eq!(report.num_images(), 2usize);
+/// assert_eq!(report.accuracy(), 0.5f64);
+/// assert_eq!(report.support(), &[1usize, 1usize]);
+/// ```
+pub fn classification_report<
+    T1: num_traits::PrimInt + num_traits::Unsigned + num_traits::FromPrimitive,
+    T2: num_traits::Num + Copy + PartialOrd,
+>(
+    gt: &ClassificationDataset<T1>,
+    pred: &ClassificationOutput<T1, T2>,
+) -> Result<ClassificationReport, io::Error>
+where
+    Vec<T2>: TopK,
+{
+    require_single_label(gt, "classification_report")?;
+    Ok(ClassificationReport::from_confusion_matrix(
+        &confusion_matrix(gt, pred)?,
+    ))
+}
+
+/// Returns the [`ClassificationReport`] of the top-1 predictions in `pred` against the
+/// labels in `gt`, ranking the images in parallel.
+///
+/// Every thread counts its images in its own confusion matrix, and the matrices are summed
+/// at the end, so the result is identical to [`classification_report`], which should be
+/// preferred for small datasets. The conditions under which an [io::Error] instance is
+/// returned are the same as well.
+///
+/// # Examples
+///
+/// ```rust
+/// use bagheera::classification::{ClassificationDataset, ClassificationOutput};
+/// use bagheera::metrics::{classification_report, par_classification_report};
+///
+/// let mut cls_db = ClassificationDataset::new(2u8, false);
+/// cls_db.add("india.jpg", &vec![0u8]).unwrap();
+/// cls_db.add("iran.jpg", &vec![1u8]).unwrap();
+///
+/// let mut cls_out = ClassificationOutput::<u8, f32>::new(2u8);
+/// cls_out.add("india.jpg", vec![0.8f32, 0.2f32]).unwrap();
+/// cls_out.add("iran.jpg", vec![0.6f32, 0.4f32]).unwrap();
+///
+/// let report = par_classification_report(&cls_db, &cls_out).unwrap();
+/// assert_eq!(report.summary(), classification_report(&cls_db, &cls_out).unwrap().summary());
+/// ```
+#[cfg(feature = "rayon")]
+pub fn par_classification_report<
+    T1: num_traits::PrimInt + num_traits::Unsigned + num_traits::FromPrimitive + Send + Sync,
+    T2: num_traits::Num + Copy + PartialOrd + Send + Sync,
+>(
+    gt: &ClassificationDataset<T1>,
+    pred: &ClassificationOutput<T1, T2>,
+) -> Result<ClassificationReport, io::Error>
+where
+    Vec<T2>: TopK,
+{
+    use rayon::prelude::*;
+
+    require_single_label(gt, "par_classification_report")?;
+    check_num_classes(gt, pred)?;
+
+    let num_classes = gt.num_classes_usize();
+    let cm = gt
+        .one_hot_data()
+        .par_iter()
+        .filter_map(|(imagename, one_hot)| {
+            pred.data()
+                .get(imagename)
+                .map(|confidences| (imagename, one_hot, confidences))
+        })
+        .try_fold(
+            || ConfusionMatrix::new(num_classes),
+            |mut cm, (imagename, one_hot, confidences)| {
+                let true_class = one_hot
+                    .iter()
+                    .position(|x| *x)
+                    .ok_or_else(|| errors::image_without_label(imagename))?;
+                cm.add(true_class, confidences.top_k(1usize)?[0])?;
+                Ok::<ConfusionMatrix, io::Error>(cm)
+            },
+        )
+        .try_reduce(
+            || ConfusionMatrix::new(num_classes),
+            |lhs, rhs| Ok(lhs.merge(rhs)),
+        )?;
+    Ok(ClassificationReport::from_confusion_matrix(&cm))
+}
+
+/// Returns the [`ClassificationReport`] of the top-1 predictions in `pred` against the
+/// labels in `gt`, restricted to the classes listed in `classes`.
+///
+/// Predictions are not restricted: an image of a listed class predicted as another class is
+/// wrong. Images whose label is not listed only count as false positives of the listed
+/// class they are predicted as, as described in [`ClassificationReport::for_classes()`].
+/// On top of the conditions of [`classification_report`], an [io::Error] instance is
+/// returned if a class is listed twice or is not less than the number of classes.
+///
+/// # Examples
+///
+/// ```rust
+/// use bagheera::classification::{ClassificationDataset, ClassificationOutput};
+/// use bagheera::metrics::classification_report_for_classes;
+///
+/// let mut cls_db = ClassificationDataset::new(3u8, false);
+/// cls_db.add("india.jpg", &vec![0u8]).unwrap();
+/// cls_db.add("iran.jpg", &vec![1u8]).unwrap();
+/// cls_db.add("peru.jpg", &vec![2u8]).unwrap();
+///
+/// let mut cls_out = ClassificationOutput::<u8, f32>::new(3u8);
+/// cls_out.add("india.jpg", vec![0.8f32, 0.1f32, 0.1f32]).unwrap();
+/// cls_out.add("iran.jpg", vec![0.1f32, 0.8f32, 0.1f32]).unwrap();
+/// cls_out.add("peru.jpg", vec![0.1f32, 0.8f32, 0.1f32]).unwrap();
+///
+/// let report = classification_report_for_classes(&cls_db, &cls_out, &[0usize, 1usize]).unwrap();
+/// assert_eq!(report.accuracy(), 1f64);
+/// assert_eq!(report.precision(), &[1f64, 0.5f64]);
+/// ```
+pub fn classification_report_for_classes<
+    T1: num_traits::PrimInt + num_traits::Unsigned + num_traits::FromPrimitive,
+    T2: num_traits::Num + Copy + PartialOrd,
+>(
+    gt: &ClassificationDataset<T1>,
+    pred: &ClassificationOutput<T1, T2>,
+    classes: &[usize],
+) -> Result<ClassificationReport, io::Error>
+where
+    Vec<T2>: TopK,
+{
+    require_single_label(gt, "classification_report_for_classes")?;
+    ClassificationReport::for_classes(&confusion_matrix(gt, pred)?, classes)
+}
+
+/// Generic struct for evaluating a classifier one image at a time.
+///
+/// Only the confusion counts are accumulated, so arbitrarily large sets of predictions
+/// can be evaluated without holding them in a [`ClassificationOutput`]. Feeding the same
+/// images to an [`Evaluator`] and to [`classification_report`] gives the same
+/// [`ClassificationReport`]. Streaming evaluation is only supported for single-label
+/// classification.
+pub struct Evaluator<T1: num_traits::PrimInt + num_traits::Unsigned + num_traits::FromPrimitive> {
+    num_classes: T1,
+    is_multilabel: bool,
+    confusion_matrix: ConfusionMatrix,
+}
+
+impl<T1: num_traits::PrimInt + num_traits::Unsigned + num_traits::FromPrimitive> Evaluator<T1> {
+    /// Creates a new instance of [`Self`] which has not seen any image.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use bagheera::metrics::Evaluator;
+    /// let mut evaluator = Evaluator::new(3u8, false);
+    /// evaluator.update(&[2u8], &[0.1f32, 0.2f32, 0.7f32]).unwrap();
+    /// evaluator.update(&[0u8], &[0.1f32, 0.2f32, 0.7f32]).unwrap();
+    /// let report = evaluator.finalize();
+    /// assert_eq!(report.num_images(), 2usize);
+    /// assert_eq!(report.accuracy(), 0.5f64);
+    /// ```
+    pub fn new(num_classes: T1, is_multilabel: bool) -> Self {
+        Evaluator {
+            num_classes,
+            is_multilabel,
+            confusion_matrix: ConfusionMatrix::new(num_classes.to_usize().unwrap()),
+        }
+    }
+
+    /// Records the labels and the classifier confidences of one image.
+    ///
+    /// An [io::Error] instance is returned if the [`Self`] instance is multi-label, if
+    /// `true_labels` does not hold exactly one label, if the label is out of range or if
+    /// `confidences` does not hold one value per class. The confidences are ranked as they
+    /// are, without being copied into a new vector.
+    pub fn update<T2: num_traits::Num + Copy + PartialOrd + utils::RankKey>(
+        &mut self,
+        true_labels: &[T1],
+        confidences: &[T2],
+    ) -> Result<(), io::Error> {
+        if self.is_multilabel {
+            return Err(errors::requires_single_label("Evaluator"));
+        }
+        if true_labels.len() != 1usize {
+            return Err(errors::evaluator_label_count(true_labels.len()));
+        }
+        let num_classes = self.num_classes.to_usize().unwrap();
+        if confidences.len() != num_classes {
+            return Err(errors::num_classes_mismatch(num_classes, confidences.len()));
+        }
+        self.confusion_matrix.add(
+            true_labels[0].to_usize().unwrap(),
+            utils::top_k_iter(confidences.iter().copied(), 1usize)?[0],
+        )
+    }
+
+    /// Returns the number of images recorded so far.
+    #[inline(always)]
+    pub fn num_images(&self) -> usize {
+        self.confusion_matrix.total()
+    }
+
+    /// Consumes the [`Self`] instance and returns the [`ClassificationReport`] of all the
+    /// recorded images.
+    pub fn finalize(self) -> ClassificationReport {
+        ClassificationReport::from_confusion_matrix(&self.confusion_matrix)
+    }
+}
+
+/// Returns the mean over all images of the confidence that `pred` assigns to the label in
+/// `gt`.
+///
+/// Only images present in both `gt` and `pred` are considered. An [io::Error] instance is
+/// returned if `gt` is multi-label, if `gt` and `pred` have different number of classes or
+/// if there is no image in common.
+///
+/// # Examples
+///
+/// ```rust
+/// use bagheera::classification::{ClassificationDataset, ClassificationOutput};
+/// use bagheera::metrics::mean_true_class_confidence;
+///
+/// let mut cls_db = ClassificationDataset::new(2u8, false);
+/// cls_db.add("india.jpg", &vec![0u8]).unwrap();
+/// cls_db.add("iran.jpg", &vec![1u8]).unwrap();
+///
+/// let mut cls_out = ClassificationOutput::<u8, f32>::new(2u8);
+/// cls_out.add("india.jpg", vec![0.75f32, 0.25f32]).unwrap();
+/// cls_out.add("iran.jpg", vec![0.75f32, 0.25f32]).unwrap();
+///
+/// assert_eq!(mean_true_class_confidence(&cls_db, &cls_out).unwrap(), 0.5f64);
+/// ```
+pub fn mean_true_class_confidence<
+    T1: num_traits::PrimInt + num_traits::Unsigned + num_traits::FromPrimitive,
+    T2: num_traits::Float + fast_float::FastFloat + num_traits::FromPrimitive,
+>(
+    gt: &ClassificationDataset<T1>,
+    pred: &ClassificationOutput<T1, T2>,
+) -> Result<f64, io::Error> {
+    require_single_label(gt, "mean_true_class_confidence")?;
+    check_num_classes(gt, pred)?;
+
+    let images = common_images(gt, pred);
+    if images.is_empty() {
+        return Err(errors::no_common_images());
+    }
+    let mut total = 0f64;
+    for imagename in &images {
+        total += pred.confidence_for_image(imagename)?[true_class(gt, imagename)?]
+            .to_f64()
+            .unwrap();
+    }
+    Ok(total / images.len() as f64)
+}
+
+/// Returns, for every image in common sorted by name, the confidence of its top-1 class in
+/// `pred` minus the confidence of its label in `gt`.
+///
+/// The gap is zero for correctly classified images, as well as for wrong top-1 classes tied
+/// with the label, and positive otherwise, a large gap meaning that the classifier is
+/// confidently wrong. The top-1 class is computed as in
+/// [`correctness_vector`], so ties are broken in favour of the smaller class index. An
+/// [io::Error] instance is returned if `gt` is multi-label, if `gt` and `pred` have
+/// different number of classes or if there is no image in common.
+///
+/// # Examples
+///
+/// ```rust
+/// use bagheera::classification::{ClassificationDataset, ClassificationOutput};
+/// use bagheera::metrics::confidence_gap;
+///
+/// let mut cls_db = ClassificationDataset::new(2u8, false);
+/// cls_db.add("india.jpg", &vec![1u8]).unwrap();
+/// cls_db.add("iran.jpg", &vec![1u8]).unwrap();
+///
+/// let mut cls_out = ClassificationOutput::<u8, f32>::new(2u8);
+/// cls_out.add("india.jpg", vec![0.25f32, 0.75f32]).unwrap();
+/// cls_out.add("iran.jpg", vec![0.75f32, 0.25f32]).unwrap();
+///
+/// assert_eq!(
+///     confidence_gap(&cls_db, &cls_out).unwrap(),
+///     vec![("india.jpg".to_string(), 0f32), ("iran.jpg".to_string(), 0.5f32)]
+/// );
+/// ```
+pub fn confidence_gap<
+    T1: num_traits::PrimInt + num_traits::Unsigned + num_traits::FromPrimitive,
+    T2: num_traits::Num + Copy + PartialOrd,
+>(
+    gt: &ClassificationDataset<T1>,
+    pred: &ClassificationOutput<T1, T2>,
+) -> Result<Vec<(String, T2)>, io::Error>
+where
+    Vec<T2>: TopK,
+{
+    require_single_label(gt, "confidence_gap")?;
+    check_num_classes(gt, pred)?;
+
+    let images = common_images(gt, pred);
+    if images.is_empty() {
+        return Err(errors::no_common_images());
+    }
+    let mut gaps = Vec::<(String, T2)>::with_capacity(images.len());
+    for imagename in images {
+        let confidences = pred.confidence_for_image(imagename)?;
+        let predicted = confidences.top_k(1usize)?[0];
+        gaps.push((
+            imagename.to_string(),
+            confidences[predicted] - confidences[true_class(gt, imagename)?],
+        ));
+    }
+    Ok(gaps)
+}
+
+/// Smallest confidence used by [`per_class_log_loss`], so that a zero confidence for the
+/// true class gives a large but finite loss.
+const LOG_LOSS_MIN_CONFIDENCE: f64 = 1e-15f64;
+
+/// Returns, for every class, the mean over the images labelled with it in `gt` of
+/// `-ln(p)`, where `p` is the confidence that `pred` assigns to that class.
+///
+/// The classes with the largest losses are those the classifier is least confident on
+/// when they are the right answer. Confidences below `1e-15` are clipped to it so that
+/// the loss stays finite, and classes without any image have a loss of `0.0`. Only images
+/// present in both `gt` and `pred` are considered. An [io::Error] instance is returned if
+/// `gt` is multi-label, if `gt` and `pred` have different number of classes or if there
+/// is no image in common.
+///
+/// # Examples
+///
+/// ```rust
+/// use bagheera::classification::{ClassificationDataset, ClassificationOutput};
+/// use bagheera::metrics::per_class_log_loss;
+///
+/// let mut cls_db = ClassificationDataset::new(3u8, false);
+/// cls_db.add("india.jpg", &vec![0u8]).unwrap();
+/// cls_db.add("iran.jpg", &vec![1u8]).unwrap();
+///
+/// let mut cls_out = ClassificationOutput::<u8, f64>::new(3u8);
+/// cls_out.add("india.jpg", vec![1f64, 0f64, 0f64]).unwrap();
+/// cls_out.add("iran.jpg", vec![0.5f64, 0.5f64, 0f64]).unwrap();
+///
+/// let losses = per_class_log_loss(&cls_db, &cls_out).unwrap();
+/// assert_eq!(losses, vec![0f64, 2f64.ln(), 0f64]);
+/// ```
+pub fn per_class_log_loss<
+    T1: num_traits::PrimInt + num_traits::Unsigned + num_traits::FromPrimitive,
+    T2: num_traits::Float + fast_float::FastFloat + num_traits::FromPrimitive,
+>(
+    gt: &ClassificationDataset<T1>,
+    pred: &ClassificationOutput<T1, T2>,
+) -> Result<Vec<f64>, io::Error> {
+    require_single_label(gt, "per_class_log_loss")?;
+    check_num_classes(gt, pred)?;
+
+    let images = common_images(gt, pred);
+    if images.is_empty() {
+        return Err(errors::no_common_images());
+    }
+    let mut totals = vec![0f64; gt.num_classes_usize()];
+    let mut counts = vec![0usize; gt.num_classes_usize()];
+    for imagename in &images {
+        let class = true_class(gt, imagename)?;
+        let confidence = pred.confidence_for_image(imagename)?[class]
+            .to_f64()
+            .unwrap();
+        totals[class] -= confidence.max(LOG_LOSS_MIN_CONFIDENCE).ln();
+        counts[class] += 1usize;
+    }
+    Ok(totals
+        .iter()
+        .zip(counts.iter())
+        .map(|(total, count)| match count {
+            0 => 0f64,
+            _ => total / *count as f64,
+        })
+        .collect())
+}
+
+/// Generic struct binding a groundtruth and a classifier output which share the class index
+/// type `T1` and the number of classes.
+///
+/// Instances are created with [`evaluate`].
+pub struct Evaluation<
+    'a,
+    T1: num_traits::PrimInt + num_traits::Unsigned + num_traits::FromPrimitive,
+    T2: num_traits::Num + Copy + PartialOrd,
+> {
+    gt: &'a ClassificationDataset<T1>,
+    pred: &'a ClassificationOutput<T1, T2>,
+}
+
+impl<
+        'a,
+        T1: num_traits::PrimInt + num_traits::Unsigned + num_traits::FromPrimitive,
+        T2: num_traits::Num + Copy + PartialOrd,
+    > Evaluation<'a, T1, T2>
+{
+    /// Returns the groundtruth of the [`Self`] instance.
+    #[inline(always)]
+    pub fn gt(&self) -> &'a ClassificationDataset<T1> {
+        self.gt
+    }
+
+    /// Returns the classifier output of the [`Self`] instance.
+    #[inline(always)]
+    pub fn pred(&self) -> &'a ClassificationOutput<T1, T2> {
+        self.pred
+    }
+
+    /// Returns the [`topk_accuracy`] of the classifier output.
+    pub fn topk_accuracy(&self, k: usize) -> Result<f64, io::Error>
+    where
+        Vec<T2>: TopK,
+    {
+        topk_accuracy(self.gt, self.pred, k)
+    }
+
+    /// Returns the [`classification_report`] of the classifier output.
+    pub fn report(&self) -> Result<ClassificationReport, io::Error>
+    where
+        Vec<T2>: TopK,
+    {
+        classification_report(self.gt, self.pred)
+    }
+}
+
+/// Returns an [`Evaluation`] of `pred` against `gt`.
+///
+/// Both arguments must use the same class index type `T1`, which is enforced by the
+/// compiler. An [io::Error] instance is returned if they have different number of classes.
+///
+/// # Examples
+///
+/// ```rust
+/// use bagheera::classification::{ClassificationDataset, ClassificationOutput};
+/// use bagheera::metrics::evaluate;
+///
+/// let cls_db = ClassificationDataset::new(3u8, false);
+/// let cls_out = ClassificationOutput::<u8, f64>::new(4u8);
+/// assert!(evaluate(&cls_db, &cls_out).is_err());
+/// ```
+pub fn evaluate<
+    'a,
+    T1: num_traits::PrimInt + num_traits::Unsigned + num_traits::FromPrimitive,
+    T2: num_traits::Num + Copy + PartialOrd,
+>(
+    gt: &'a ClassificationDataset<T1>,
+    pred: &'a ClassificationOutput<T1, T2>,
+) -> Result<Evaluation<'a, T1, T2>, io::Error> {
+    check_num_classes(gt, pred)?;
+    Ok(Evaluation { gt, pred })
+}
+
+/// Trait for metrics which can be selected at runtime.
+///
+/// Every metric reduces a classifier output evaluated against the groundtruth to a single
+/// `f64`. The trait is object safe, so a configurable pipeline can store its metrics as
+/// `Vec<Box<dyn Metric<T1, T2>>>` and compute them in turn.
+///
+/// # Examples
+///
+/// ```rust
+/// use bagheera::classification::{ClassificationDataset, ClassificationOutput};
+/// use bagheera::metrics::{MacroF1, Metric, Top1Accuracy, TopKAccuracy};
+///
+/// let mut cls_db = ClassificationDataset::new(3u8, false);
+/// cls_db.add("india.jpg", &vec![1u8]).unwrap();
+/// cls_db.add("iran.jpg", &vec![0u8]).unwrap();
+///
+/// let mut cls_out = ClassificationOutput::<u8, f32>::new(3u8);
+/// cls_out.add("india.jpg", vec![0.1f32, 0.8f32, 0.1f32]).unwrap();
+/// cls_out.add("iran.jpg", vec![0.3f32, 0.5f32, 0.2f32]).unwrap();
+///
+/// let metrics: Vec<Box<dyn Metric<u8, f32>>> = vec![
+///     Box::new(Top1Accuracy),
+///     Box::new(TopKAccuracy::new(2usize)),
+///     Box::new(MacroF1),
+/// ];
+/// for metric in &metrics {
+///     println!("{}: {}", metric.name(), metric.compute(&cls_db, &cls_out).unwrap());
+/// }
+/// assert_eq!(metrics[1].name(), "top2_accuracy");
+/// assert_eq!(metrics[1].compute(&cls_db, &cls_out).unwrap(), 1f64);
+/// ```
+pub trait Metric<
+    T1: num_traits::PrimInt + num_traits::Unsigned + num_traits::FromPrimitive,
+    T2: num_traits::Num + Copy + PartialOrd,
+>
+{
+    /// Returns the name of the metric.
+    fn name(&self) -> &str;
+
+    /// Returns the value of the metric for `pred` evaluated against `gt`.
+    fn compute(
+        &self,
+        gt: &ClassificationDataset<T1>,
+        pred: &ClassificationOutput<T1, T2>,
+    ) -> Result<f64, io::Error>;
+}
+
+/// [`Metric`] computing the [`top1_accuracy`].
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Top1Accuracy;
+
+impl<
+        T1: num_traits::PrimInt + num_traits::Unsigned + num_traits::FromPrimitive,
+        T2: num_traits::Num + Copy + PartialOrd,
+    > Metric<T1, T2> for Top1Accuracy
+where
+    Vec<T2>: TopK,
+{
+    fn name(&self) -> &str {
+        "top1_accuracy"
+    }
+
+    fn compute(
+        &self,
+        gt: &ClassificationDataset<T1>,
+        pred: &ClassificationOutput<T1, T2>,
+    ) -> Result<f64, io::Error> {
+        top1_accuracy(gt, pred)
+    }
+}
+
+/// [`Metric`] computing the [`topk_accuracy`] for a given K.
+#[derive(Clone, Debug)]
+pub struct TopKAccuracy {
+    k: usize,
+    name: String,
+}
+
+impl TopKAccuracy {
+    /// Returns a new [`Self`] instance for the Top-K accuracy with `k`, named as in
+    /// `top5_accuracy`.
+    pub fn new(k: usize) -> Self {
+        TopKAccuracy {
+            k,
+            name: format!("top{}_accuracy", k),
+        }
+    }
+}
+
+impl<
+        T1: num_traits::PrimInt + num_traits::Unsigned + num_traits::FromPrimitive,
+        T2: num_traits::Num + Copy + PartialOrd,
+    > Metric<T1, T2> for TopKAccuracy
+where
+    Vec<T2>: TopK,
+{
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn compute(
+        &self,
+        gt: &ClassificationDataset<T1>,
+        pred: &ClassificationOutput<T1, T2>,
+    ) -> Result<f64, io::Error> {
+        topk_accuracy(gt, pred, self.k)
+    }
+}
+
+/// [`Metric`] computing the [`ClassificationReport::macro_f1()`] of a single-label
+/// dataset.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct MacroF1;
+
+impl<
+        T1: num_traits::PrimInt + num_traits::Unsigned + num_traits::FromPrimitive,
+        T2: num_traits::Num + Copy + PartialOrd,
+    > Metric<T1, T2> for MacroF1
+where
+    Vec<T2>: TopK,
+{
+    fn name(&self) -> &str {
+        "macro_f1"
+    }
+
+    fn compute(
+        &self,
+        gt: &ClassificationDataset<T1>,
+        pred: &ClassificationOutput<T1, T2>,
+    ) -> Result<f64, io::Error> {
+        Ok(classification_report(gt, pred)?.macro_f1())
+    }
+}
+
+/// [`Metric`] computing the [`ClassificationReport::micro_f1()`] of a single-label
+/// dataset.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct MicroF1;
+
+impl<
+        T1: num_traits::PrimInt + num_traits::Unsigned + num_traits::FromPrimitive,
+        T2: num_traits::Num + Copy + PartialOrd,
+    > Metric<T1, T2> for MicroF1
+where
+    Vec<T2>: TopK,
+{
+    fn name(&self) -> &str {
+        "micro_f1"
+    }
+
+    fn compute(
+        &self,
+        gt: &ClassificationDataset<T1>,
+        pred: &ClassificationOutput<T1, T2>,
+    ) -> Result<f64, io::Error> {
+        Ok(classification_report(gt, pred)?.micro_f1())
+    }
+}
+
+/// [`Metric`] computing the [`ClassificationReport::weighted_f1()`] of a single-label
+/// dataset.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct WeightedF1;
+
+impl<
+        T1: num_traits::PrimInt + num_traits::Unsigned + num_traits::FromPrimitive,
+        T2: num_traits::Num + Copy + PartialOrd,
+    > Metric<T1, T2> for WeightedF1
+where
+    Vec<T2>: TopK,
+{
+    fn name(&self) -> &str {
+        "weighted_f1"
+    }
+
+    fn compute(
+        &self,
+        gt: &ClassificationDataset<T1>,
+        pred: &ClassificationOutput<T1, T2>,
+    ) -> Result<f64, io::Error> {
+        Ok(classification_report(gt, pred)?.weighted_f1())
+    }
+}
+
+/// [`Metric`] computing the macro [`mean_average_precision`].
+#[derive(Clone, Copy, Debug, Default)]
+pub struct MeanAveragePrecision;
+
+impl<
+        T1: num_traits::PrimInt + num_traits::Unsigned + num_traits::FromPrimitive,
+        T2: num_traits::Num + Copy + PartialOrd,
+    > Metric<T1, T2> for MeanAveragePrecision
+{
+    fn name(&self) -> &str {
+        "mean_average_precision"
+    }
+
+    fn compute(
+        &self,
+        gt: &ClassificationDataset<T1>,
+        pred: &ClassificationOutput<T1, T2>,
+    ) -> Result<f64, io::Error> {
+        mean_average_precision(gt, pred, false)
+    }
+}
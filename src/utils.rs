@@ -1,16 +1,142 @@
 //! Utilities used in bagheera
 
-use std::cmp::Ordering;
-use std::collections::BinaryHeap;
-use std::fs;
-use std::io;
-use std::io::Error;
-use std::path;
+use alloc::collections::BinaryHeap;
+#[cfg(not(feature = "std"))]
+use alloc::{
+    string::{String, ToString},
+    vec,
+    vec::Vec,
+};
+use core::cmp::Ordering;
+use core::convert::TryInto;
+#[cfg(not(feature = "std"))]
+use core::fmt;
+use core::hash::{BuildHasherDefault, Hasher};
+#[cfg(feature = "std")]
+use std::{fs, io, path};
 
+#[cfg(feature = "std")]
 use plain_path::PlainPathExt;
 
+#[cfg(feature = "std")]
 use crate::errors;
 
+/// Error type returned by the utilities.
+///
+/// With the `std` feature, which is enabled by default, this is [`io::Error`] so that the
+/// utilities compose with the rest of the crate.
+#[cfg(feature = "std")]
+pub type Error = io::Error;
+
+/// Error type returned by the utilities.
+///
+/// Without the `std` feature, [`io::Error`](https://doc.rust-lang.org/std/io/struct.Error.html)
+/// is not available and the utilities report their errors with this enum instead.
+#[cfg(not(feature = "std"))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Error {
+    /// K is more than the length of the ranked container.
+    TopKIncorrectK {
+        /// The requested K.
+        k: usize,
+        /// The length of the container.
+        len: usize,
+    },
+    /// K is not between 1 and the length of the ranked container.
+    KthIncorrectK {
+        /// The requested K.
+        k: usize,
+        /// The length of the container.
+        len: usize,
+    },
+    /// A one-hot vector does not have one entry per class.
+    OneHotLengthMismatch {
+        /// The length of the one-hot vector.
+        length: usize,
+        /// The number of classes.
+        num_classes: usize,
+    },
+    /// An image is added while an image with the same name is already present.
+    ImageAlreadyPresent,
+    /// An image is looked up while no image with that name is present.
+    ImageNotPresent,
+}
+
+#[cfg(not(feature = "std"))]
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::TopKIncorrectK { k, len } => write!(
+                f,
+                "In Top-K analysis of a vector v, K <= v.len(). Here K = {} and v.len() = {}.",
+                k, len
+            ),
+            Error::KthIncorrectK { k, len } => write!(
+                f,
+                "The K-th largest value of a vector v requires 1 <= K <= v.len(). Here K = {} and v.len() = {}.",
+                k, len
+            ),
+            Error::OneHotLengthMismatch {
+                length,
+                num_classes,
+            } => write!(
+                f,
+                "The one-hot vector has {} entries while there are {} classes.",
+                length, num_classes
+            ),
+            Error::ImageAlreadyPresent => write!(f, "The image was already present."),
+            Error::ImageNotPresent => write!(f, "The image was not found."),
+        }
+    }
+}
+
+/// Returns the [`Error`] reported when K is more than the length `len` of a container.
+pub(crate) fn topk_incorrect_k(k: usize, len: usize) -> Error {
+    #[cfg(feature = "std")]
+    return errors::topk_incorrect_k(k, len);
+    #[cfg(not(feature = "std"))]
+    return Error::TopKIncorrectK { k, len };
+}
+
+/// Returns the [`Error`] reported when K is not between 1 and the length `len` of a
+/// container.
+fn kth_incorrect_k(k: usize, len: usize) -> Error {
+    #[cfg(feature = "std")]
+    return errors::kth_incorrect_k(k, len);
+    #[cfg(not(feature = "std"))]
+    return Error::KthIncorrectK { k, len };
+}
+
+/// Returns the [`Error`] reported when a one-hot vector of `length` entries is decoded for
+/// `num_classes` classes.
+fn one_hot_length_mismatch(length: usize, num_classes: usize) -> Error {
+    #[cfg(feature = "std")]
+    return errors::one_hot_length_mismatch(length, num_classes);
+    #[cfg(not(feature = "std"))]
+    return Error::OneHotLengthMismatch {
+        length,
+        num_classes,
+    };
+}
+
+/// Returns the [`Error`] reported when `imagename` is added while it is already present.
+#[cfg_attr(not(feature = "std"), allow(unused_variables))]
+pub(crate) fn image_already_present(imagename: &str) -> Error {
+    #[cfg(feature = "std")]
+    return errors::image_already_present(imagename);
+    #[cfg(not(feature = "std"))]
+    return Error::ImageAlreadyPresent;
+}
+
+/// Returns the [`Error`] reported when `imagename` is looked up while it is not present.
+#[cfg_attr(not(feature = "std"), allow(unused_variables))]
+pub(crate) fn image_not_present(imagename: &str) -> Error {
+    #[cfg(feature = "std")]
+    return errors::image_not_present_error(imagename);
+    #[cfg(not(feature = "std"))]
+    return Error::ImageNotPresent;
+}
+
 ///Generic struct to hold a value together with its index.
 pub struct IndexedTuple<T: Ord> {
     index: usize,
@@ -52,24 +178,31 @@ impl<T: Ord> Eq for IndexedTuple<T> {}
 
 impl<T: Ord> PartialEq<Self> for IndexedTuple<T> {
     fn eq(&self, other: &Self) -> bool {
-        self.value().eq(other.value())
+        self.cmp(other) == Ordering::Equal
     }
 }
 
 impl<T: Ord> PartialOrd<Self> for IndexedTuple<T> {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        self.value().partial_cmp(other.value())
+        Some(self.cmp(other))
     }
 }
 
+/// Tuples are ordered by value, and tuples with equal values by decreasing index, so that a
+/// max-heap of tuples yields ties in increasing index order.
 impl<T: Ord> Ord for IndexedTuple<T> {
     fn cmp(&self, other: &Self) -> Ordering {
-        self.value().cmp(other.value())
+        self.value()
+            .cmp(other.value())
+            .then_with(|| other.index().cmp(&self.index()))
     }
 }
 
-#[derive(PartialEq, PartialOrd)]
+#[derive(PartialEq)]
 /// Generic struct to hold all non-NaN floating point numbers.
+///
+/// Any type implementing `num_traits::Float` can be used, including `half::f16` with the
+/// `half` feature.
 pub struct NoNaN<T: num_traits::Float>(T);
 
 impl<T: num_traits::Float> NoNaN<T> {
@@ -110,41 +243,140 @@ impl<T: num_traits::Float> NoNaN<T> {
 
 impl<T: num_traits::Float> Eq for NoNaN<T> {}
 
+impl<T: num_traits::Float> PartialOrd<Self> for NoNaN<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
 impl<T: num_traits::Float> Ord for NoNaN<T> {
     fn cmp(&self, other: &Self) -> Ordering {
-        self.partial_cmp(other).unwrap()
+        self.0.partial_cmp(&other.0).unwrap()
     }
 }
 
 /// Trait for calculating Top-K indices in a container.
+///
+/// It is implemented for vectors of primitive integers and floating point numbers. With the
+/// `half` feature, it is also implemented for vectors of `half::f16`. With the `ndarray`
+/// feature, it is also implemented for `ndarray::ArrayView1` of the same element types, so
+/// that a row of a matrix can be ranked without copying it.
 pub trait TopK {
+    /// Type of the values stored in the container.
+    type Value;
+
     /// Returns the Top-K indices in  a container.
-    fn top_k(&self, k: usize) -> Result<Vec<usize>, io::Error>;
+    ///
+    /// The indices are sorted by descending value, and equal values by increasing index. Only
+    /// the ordering of the values matters, so negative values such as the signed margins of an
+    /// SVM are ranked like any other, `-0.0` is equal to `0.0`, and the same values give the
+    /// same indices whatever their type.
+    ///
+    /// `top_k(0)` returns an empty vector for any container, including an empty one. An
+    /// [io::Error] instance is returned if `k` is more than the length of the container, so
+    /// `top_k(k)` with `k > 0` always fails on an empty container.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use bagheera::utils::TopK;
+    /// let v = vec![3u8, 7u8, 5u8];
+    /// assert_eq!(v.top_k(2usize).unwrap(), vec![1usize, 2usize]);
+    /// let ties = vec![0.5f32, 0.9f32, 0.5f32, 0.9f32];
+    /// assert_eq!(ties.top_k(4usize).unwrap(), vec![1usize, 3usize, 0usize, 2usize]);
+    /// assert_eq!(v.top_k(0usize).unwrap(), Vec::<usize>::new());
+    /// assert!(Vec::<f32>::new().top_k(1usize).is_err());
+    /// ```
+    fn top_k(&self, k: usize) -> Result<Vec<usize>, Error>;
+
+    /// Returns the K-th largest value in a container, i.e. the value at position `k - 1` when
+    /// the container is sorted in descending order.
+    ///
+    /// An [io::Error] instance is returned unless `1 <= k <= len`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use bagheera::utils::TopK;
+    /// let v = vec![0.5f64, 3.5f64, 1.5f64, 2.5f64];
+    /// assert_eq!(v.kth_largest(1usize).unwrap(), 3.5f64);
+    /// assert_eq!(v.kth_largest(3usize).unwrap(), 1.5f64);
+    /// assert!(v.kth_largest(0usize).is_err());
+    /// ```
+    fn kth_largest(&self, k: usize) -> Result<Self::Value, Error>;
+}
+
+/// Returns the indices of the `k` largest of the `len` items yielded by `values`.
+///
+/// An [io::Error] instance is returned if `k > len`.
+fn heap_top_k<T: Ord, I: Iterator<Item = T>>(
+    values: I,
+    len: usize,
+    k: usize,
+) -> Result<Vec<usize>, Error> {
+    if k > len {
+        return Err(topk_incorrect_k(k, len));
+    }
+
+    let mut bheap = BinaryHeap::<IndexedTuple<T>>::with_capacity(len);
+    for (index, value) in values.enumerate() {
+        bheap.push(IndexedTuple::new(index, value));
+    }
+
+    let mut topk_indices = Vec::<usize>::with_capacity(k);
+    for _ in 0usize..k {
+        topk_indices.push(bheap.pop().unwrap().index());
+    }
+    Ok(topk_indices)
+}
+
+/// Returns the index of the `k`-th largest of the `len` items yielded by `values`.
+///
+/// An [io::Error] instance is returned unless `1 <= k <= len`.
+fn heap_kth_index<T: Ord, I: Iterator<Item = T>>(
+    values: I,
+    len: usize,
+    k: usize,
+) -> Result<usize, Error> {
+    if k == 0 || k > len {
+        return Err(kth_incorrect_k(k, len));
+    }
+
+    let mut bheap = values
+        .enumerate()
+        .map(|(index, value)| IndexedTuple::new(index, value))
+        .collect::<BinaryHeap<IndexedTuple<T>>>();
+    for _ in 1usize..k {
+        bheap.pop();
+    }
+    Ok(bheap.pop().unwrap().index())
 }
 
 macro_rules! impl_topk_non_float {
     ($($ty:ty),*) =>{
         $(
         impl TopK for Vec<$ty>{
+            type Value = $ty;
+
             fn top_k(&self, k: usize) -> Result<Vec<usize>, Error> {
-                if k > self.len(){
-                    return Err(errors::topk_incorrect_k(k, self.len()));
-                }
-
-                let mut bheap = BinaryHeap::<IndexedTuple<$ty>>::with_capacity(self.len());
-                for (index, value) in self.iter().enumerate(){
-                bheap.push(
-                    IndexedTuple::new(index, *value)
-                );
-                }
-
-                let mut topk_indices = Vec::<usize>::with_capacity(k);
-                for _ in 0usize..k{
-                    topk_indices.push(
-                        bheap.pop().unwrap().index()
-                    );
-                }
-                Ok(topk_indices)
+                heap_top_k(self.iter().copied(), self.len(), k)
+            }
+
+            fn kth_largest(&self, k: usize) -> Result<$ty, Error> {
+                Ok(self[heap_kth_index(self.iter().copied(), self.len(), k)?])
+            }
+        }
+
+        #[cfg(feature = "ndarray")]
+        impl TopK for ndarray::ArrayView1<'_, $ty>{
+            type Value = $ty;
+
+            fn top_k(&self, k: usize) -> Result<Vec<usize>, Error> {
+                heap_top_k(self.iter().copied(), self.len(), k)
+            }
+
+            fn kth_largest(&self, k: usize) -> Result<$ty, Error> {
+                Ok(self[heap_kth_index(self.iter().copied(), self.len(), k)?])
             }
         }
         )*
@@ -155,25 +387,43 @@ macro_rules! impl_topk_float {
     ($($ty:ty),*) =>{
         $(
         impl TopK for Vec<$ty>{
+            type Value = $ty;
+
+            fn top_k(&self, k: usize) -> Result<Vec<usize>, Error> {
+                heap_top_k(
+                    self.iter().map(|value| NoNaN::new(*value).unwrap()),
+                    self.len(),
+                    k,
+                )
+            }
+
+            fn kth_largest(&self, k: usize) -> Result<$ty, Error> {
+                Ok(self[heap_kth_index(
+                    self.iter().map(|value| NoNaN::new(*value).unwrap()),
+                    self.len(),
+                    k,
+                )?])
+            }
+        }
+
+        #[cfg(feature = "ndarray")]
+        impl TopK for ndarray::ArrayView1<'_, $ty>{
+            type Value = $ty;
+
             fn top_k(&self, k: usize) -> Result<Vec<usize>, Error> {
-                if k > self.len(){
-                    return Err(errors::topk_incorrect_k(k, self.len()));
-                }
-
-                let mut bheap = BinaryHeap::<IndexedTuple<NoNaN<$ty>>>::with_capacity(self.len());
-                for (index, value) in self.iter().enumerate(){
-                bheap.push(
-                    IndexedTuple::new(index, NoNaN::new(*value).unwrap())
-                );
-                }
-
-                let mut topk_indices = Vec::<usize>::with_capacity(k);
-                for _ in 0usize..k{
-                    topk_indices.push(
-                        bheap.pop().unwrap().index()
-                    );
-                }
-                Ok(topk_indices)
+                heap_top_k(
+                    self.iter().map(|value| NoNaN::new(*value).unwrap()),
+                    self.len(),
+                    k,
+                )
+            }
+
+            fn kth_largest(&self, k: usize) -> Result<$ty, Error> {
+                Ok(self[heap_kth_index(
+                    self.iter().map(|value| NoNaN::new(*value).unwrap()),
+                    self.len(),
+                    k,
+                )?])
             }
         }
         )*
@@ -181,10 +431,290 @@ macro_rules! impl_topk_float {
 }
 
 impl_topk_float!(f32, f64);
+#[cfg(feature = "half")]
+impl_topk_float!(half::f16);
 impl_topk_non_float!(u8, i8, u16, i16, u32, i32, u64, i64, u128, i128, usize);
 
+/// Trait mapping a value to a totally ordered key used for ranking it.
+///
+/// Integers are their own key while floating point numbers are wrapped in [`NoNaN`], so
+/// building the key of a NaN panics.
+pub trait RankKey: Copy {
+    /// Totally ordered type of the key.
+    type Key: Ord;
+
+    /// Returns the key of the value.
+    fn rank_key(self) -> Self::Key;
+}
+
+macro_rules! impl_rank_key_non_float {
+    ($($ty:ty),*) =>{
+        $(
+        impl RankKey for $ty{
+            type Key = $ty;
+
+            fn rank_key(self) -> $ty {
+                self
+            }
+        }
+        )*
+    }
+}
+
+macro_rules! impl_rank_key_float {
+    ($($ty:ty),*) =>{
+        $(
+        impl RankKey for $ty{
+            type Key = NoNaN<$ty>;
+
+            fn rank_key(self) -> NoNaN<$ty> {
+                NoNaN::new(self).unwrap()
+            }
+        }
+        )*
+    }
+}
+
+impl_rank_key_float!(f32, f64);
+#[cfg(feature = "half")]
+impl_rank_key_float!(half::f16);
+impl_rank_key_non_float!(u8, i8, u16, i16, u32, i32, u64, i64, u128, i128, usize);
+
+/// Trait for numeric values stored in the binary files of
+/// [`ClassificationOutput::to_bincode_file()`](crate::classification::ClassificationOutput::to_bincode_file).
+///
+/// Values are stored in little-endian order using exactly [`Self::SIZE`] bytes.
+#[cfg(feature = "bincode")]
+pub trait LeBytes: Copy {
+    /// Number of bytes used to store a value.
+    const SIZE: usize;
+
+    /// Appends the little-endian bytes of the value to `out`.
+    fn write_le(self, out: &mut Vec<u8>);
+
+    /// Reads a value from the first [`Self::SIZE`] bytes of `bytes`.
+    fn read_le(bytes: &[u8]) -> Self;
+}
+
+#[cfg(feature = "bincode")]
+macro_rules! impl_le_bytes {
+    ($($ty:ty),*) =>{
+        $(
+        impl LeBytes for $ty{
+            const SIZE: usize = core::mem::size_of::<$ty>();
+
+            fn write_le(self, out: &mut Vec<u8>) {
+                out.extend_from_slice(&self.to_le_bytes());
+            }
+
+            fn read_le(bytes: &[u8]) -> $ty {
+                <$ty>::from_le_bytes(bytes[..Self::SIZE].try_into().unwrap())
+            }
+        }
+        )*
+    }
+}
+
+#[cfg(feature = "bincode")]
+impl_le_bytes!(f32, f64);
+#[cfg(all(feature = "bincode", feature = "half"))]
+impl_le_bytes!(half::f16);
+#[cfg(feature = "bincode")]
+impl_le_bytes!(u8, i8, u16, i16, u32, i32, u64, i64, u128, i128);
+
+/// Generic struct holding reusable buffers for repeated Top-K computations.
+///
+/// [`TopK::top_k()`] allocates a new heap on every call. When ranking many vectors of the
+/// same length in a loop, a [`TopKScratch`] keeps the heap and the output buffer alive
+/// between calls. The results are identical to those of [`TopK::top_k()`].
+///
+/// # Examples
+///
+/// ```rust
+/// use bagheera::utils::TopKScratch;
+/// let mut scratch = TopKScratch::<f32>::with_capacity(3usize);
+/// assert_eq!(scratch.top_k(&[0.2f32, 0.7f32, 0.1f32], 2usize).unwrap(), &[1usize, 0usize]);
+/// assert_eq!(scratch.top_k(&[0.9f32, 0.3f32, 0.5f32], 1usize).unwrap(), &[0usize]);
+/// ```
+pub struct TopKScratch<T: RankKey> {
+    heap: BinaryHeap<IndexedTuple<T::Key>>,
+    indices: Vec<usize>,
+}
+
+impl<T: RankKey> TopKScratch<T> {
+    /// Creates a new instance of [`Self`] with empty buffers.
+    pub fn new() -> Self {
+        TopKScratch {
+            heap: BinaryHeap::new(),
+            indices: Vec::new(),
+        }
+    }
+
+    /// Creates a new instance of [`Self`] with buffers sized for vectors of length `len`.
+    pub fn with_capacity(len: usize) -> Self {
+        TopKScratch {
+            heap: BinaryHeap::with_capacity(len),
+            indices: Vec::with_capacity(len),
+        }
+    }
+
+    /// Returns the Top-K indices in `values`, reusing the buffers of the [`Self`] instance.
+    ///
+    /// The returned slice is valid until the next call. An [io::Error] instance is returned
+    /// if `k` is more than the length of `values`.
+    pub fn top_k(&mut self, values: &[T], k: usize) -> Result<&[usize], Error> {
+        if k > values.len() {
+            return Err(topk_incorrect_k(k, values.len()));
+        }
+
+        self.heap.clear();
+        self.indices.clear();
+        for (index, value) in values.iter().enumerate() {
+            self.heap.push(IndexedTuple::new(index, value.rank_key()));
+        }
+        for _ in 0usize..k {
+            self.indices.push(self.heap.pop().unwrap().index());
+        }
+        Ok(&self.indices)
+    }
+}
+
+impl<T: RankKey> Default for TopKScratch<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Returns the indices of the `k` largest values yielded by `iter`, in descending order of
+/// value.
+///
+/// The values are ranked as they are streamed, keeping only the `k` best ones seen so far,
+/// so a lazily computed sequence of scores does not need to be collected into a container
+/// first. The results are identical to those of [`TopK::top_k()`] on the collected values,
+/// ties included, and building the key of a NaN panics likewise. An [io::Error] instance is
+/// returned if `iter` yields fewer than `k` values.
+///
+/// # Examples
+///
+/// ```rust
+/// use bagheera::utils::top_k_iter;
+/// let scores = (0u32..5u32).map(|x| ((x * 3u32) % 5u32) as f32);
+/// assert_eq!(top_k_iter(scores, 2usize).unwrap(), vec![3usize, 1usize]);
+/// assert!(top_k_iter(core::iter::once(1u8), 2usize).is_err());
+/// ```
+pub fn top_k_iter<T: RankKey, I: Iterator<Item = T>>(
+    iter: I,
+    k: usize,
+) -> Result<Vec<usize>, Error> {
+    let mut len = 0usize;
+    let mut bheap = BinaryHeap::<core::cmp::Reverse<IndexedTuple<T::Key>>>::with_capacity(k);
+    for (index, value) in iter.enumerate() {
+        len += 1usize;
+        let candidate = core::cmp::Reverse(IndexedTuple::new(index, value.rank_key()));
+        if bheap.len() < k {
+            bheap.push(candidate);
+        } else if let Some(mut worst) = bheap.peek_mut() {
+            if candidate < *worst {
+                *worst = candidate;
+            }
+        }
+    }
+    if k > len {
+        return Err(topk_incorrect_k(k, len));
+    }
+    Ok(bheap
+        .into_sorted_vec()
+        .into_iter()
+        .map(|candidate| candidate.0.index())
+        .collect())
+}
+
+/// Multiplier of the Fx hash, as used by the Rust compiler.
+const FX_SEED: u64 = 0x51_7c_c1_b7_27_22_0a_95;
+
+/// Fast, non-cryptographic [`Hasher`] based on the Fx hash of the Rust compiler.
+///
+/// It processes eight bytes at a time with a rotation, a xor and a multiplication, which is
+/// much faster than the default SipHash for short keys such as image names. It is not
+/// resistant to collision attacks and does not depend on a random seed, so maps using it
+/// iterate in the same order across runs.
+///
+/// # Examples
+///
+/// ```rust
+/// use std::hash::Hasher;
+/// use bagheera::utils::FxHasher;
+///
+/// let mut lhs = FxHasher::default();
+/// lhs.write(b"hello.jpg");
+/// let mut rhs = FxHasher::default();
+/// rhs.write(b"hello.jpg");
+/// assert_eq!(lhs.finish(), rhs.finish());
+/// ```
+#[derive(Clone, Copy, Debug, Default)]
+pub struct FxHasher {
+    hash: u64,
+}
+
+impl FxHasher {
+    #[inline(always)]
+    fn add_to_hash(&mut self, word: u64) {
+        self.hash = (self.hash.rotate_left(5) ^ word).wrapping_mul(FX_SEED);
+    }
+}
+
+impl Hasher for FxHasher {
+    #[inline]
+    fn write(&mut self, bytes: &[u8]) {
+        let mut chunks = bytes.chunks_exact(8);
+        for chunk in &mut chunks {
+            self.add_to_hash(u64::from_le_bytes(chunk.try_into().unwrap()));
+        }
+        // The tail is hashed as 4, 2 and 1 byte words so that its length is accounted for.
+        let mut remainder = chunks.remainder();
+        if remainder.len() >= 4 {
+            self.add_to_hash(u32::from_le_bytes(remainder[..4].try_into().unwrap()) as u64);
+            remainder = &remainder[4..];
+        }
+        if remainder.len() >= 2 {
+            self.add_to_hash(u16::from_le_bytes(remainder[..2].try_into().unwrap()) as u64);
+            remainder = &remainder[2..];
+        }
+        if let Some(byte) = remainder.first() {
+            self.add_to_hash(*byte as u64);
+        }
+    }
+
+    #[inline]
+    fn write_u8(&mut self, i: u8) {
+        self.add_to_hash(i as u64);
+    }
+
+    #[inline]
+    fn write_u64(&mut self, i: u64) {
+        self.add_to_hash(i);
+    }
+
+    #[inline]
+    fn write_usize(&mut self, i: usize) {
+        self.add_to_hash(i as u64);
+    }
+
+    #[inline]
+    fn finish(&self) -> u64 {
+        // The multiplication leaves the entropy in the high bits, while hash tables pick
+        // buckets from the low ones, so the high bits are folded into the low ones.
+        let hash = self.hash ^ (self.hash >> 32);
+        hash.wrapping_mul(FX_SEED) ^ (hash >> 29)
+    }
+}
+
+/// Hasher builder creating [`FxHasher`] instances.
+pub type FxBuildHasher = BuildHasherDefault<FxHasher>;
+
 /// Returns a [fs::File] instance if a file exists. Otherwise
 /// returns an [io::Error] instance.
+#[cfg(feature = "std")]
 pub fn open_file(filename: &str) -> Result<fs::File, io::Error> {
     let pth = path::Path::new(filename).plain().unwrap();
     if pth.exists() {
@@ -194,6 +724,57 @@ pub fn open_file(filename: &str) -> Result<fs::File, io::Error> {
     }
 }
 
+/// Returns a [fs::File] instance opened for writing, creating the file if it does not exist
+/// and truncating it otherwise. An [io::Error] instance is returned if the file cannot be
+/// created.
+#[cfg(feature = "std")]
+pub fn create_file(filename: &str) -> Result<fs::File, io::Error> {
+    let pth = path::Path::new(filename).plain().unwrap();
+    fs::File::create(pth)
+}
+
+/// Returns the last component of `path`, splitting on both `/` and `\\`.
+///
+/// This is meant to be passed to the `normalize_keys` functions of
+/// [`crate::classification`] when the groundtruth and the classifier output name images
+/// differently.
+///
+/// # Examples
+///
+/// ```rust
+/// use bagheera::utils::basename;
+/// assert_eq!(basename("/data/val/img_01.jpg"), "img_01.jpg");
+/// assert_eq!(basename("C:\\data\\img_01.jpg"), "img_01.jpg");
+/// assert_eq!(basename("img_01.jpg"), "img_01.jpg");
+/// ```
+pub fn basename(path: &str) -> String {
+    path.rsplit(['/', '\\']).next().unwrap().to_string()
+}
+
+/// Returns `data` with every key replaced by `f(key)`, or an [io::Error] instance if two
+/// keys are mapped to the same one.
+#[cfg(feature = "std")]
+pub(crate) fn normalize_map_keys<V, S: std::hash::BuildHasher + Default>(
+    data: &mut std::collections::HashMap<String, V, S>,
+    f: impl Fn(&str) -> String,
+) -> Result<(), io::Error> {
+    let mut keys = std::collections::HashMap::<String, String>::with_capacity(data.len());
+    for key in data.keys() {
+        let normalized = f(key);
+        if let Some(other) = keys.get(&normalized) {
+            return Err(errors::normalized_key_collision(other, key, &normalized));
+        }
+        keys.insert(normalized, key.clone());
+    }
+    let mut normalized_data =
+        std::collections::HashMap::with_capacity_and_hasher(data.len(), S::default());
+    for (normalized, key) in keys {
+        normalized_data.insert(normalized, data.remove(&key).unwrap());
+    }
+    *data = normalized_data;
+    Ok(())
+}
+
 /// Generic trait representing one-hot vector computation from integer class IDs.
 pub trait ToOneHot<T1: num_traits::PrimInt + num_traits::Unsigned + num_traits::FromPrimitive> {
     /// Given an unsigned integer or a vector of unsigned integers, represents it in one-hot vector notation.
@@ -214,23 +795,117 @@ pub trait ToOneHot<T1: num_traits::PrimInt + num_traits::Unsigned + num_traits::
     /// assert_eq!(one_hot, vec![true, false, false, true, false]);
     /// ```
     fn convert(&self, num_clases: T1) -> Vec<bool>;
+
+    /// Returns the indices which are set in the one-hot vector returned by
+    /// [`Self::convert()`], in increasing order and without duplicates.
+    ///
+    /// Only the labels are stored, which is much more compact than the dense one-hot vector
+    /// for a large number of classes. The dense vector is recovered with
+    /// [`from_sparse_one_hot()`]. Panics in the same cases as [`Self::convert()`].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use bagheera::utils::ToOneHot;
+    /// assert_eq!(vec![7u32, 2u32, 7u32].to_sparse_one_hot(100000u32), vec![2usize, 7usize]);
+    /// assert_eq!(3u8.to_sparse_one_hot(4u8), vec![3usize]);
+    /// ```
+    #[allow(clippy::wrong_self_convention)]
+    fn to_sparse_one_hot(&self, num_classes: T1) -> Vec<usize>;
+
+    /// Returns the one-hot vector of [`Self::convert()`] in which every label carries its
+    /// weight in `weights` instead of `true`.
+    ///
+    /// Labels absent from `weights` have a weight of `1.0`, and classes which are not labels
+    /// have a weight of `0.0`. This represents soft multi-label targets for weighted losses.
+    /// An [io::Error] instance is returned if a label is more than or equal to
+    /// `num_classes`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::collections::HashMap;
+    /// use bagheera::utils::ToOneHot;
+    /// let weights: HashMap<u8, f64> = [(2u8, 0.5f64)].iter().copied().collect();
+    /// let one_hot = vec![0u8, 2u8].to_weighted_one_hot(4u8, &weights).unwrap();
+    /// assert_eq!(one_hot, vec![1f64, 0f64, 0.5f64, 0f64]);
+    /// assert!(vec![4u8].to_weighted_one_hot(4u8, &weights).is_err());
+    /// ```
+    #[cfg(feature = "std")]
+    #[allow(clippy::wrong_self_convention)]
+    fn to_weighted_one_hot(
+        &self,
+        num_classes: T1,
+        weights: &std::collections::HashMap<T1, f64>,
+    ) -> Result<Vec<f64>, Error>
+    where
+        T1: core::hash::Hash;
+}
+
+/// Returns the weighted one-hot vector of [`ToOneHot::to_weighted_one_hot()`] for the
+/// labels `categories`.
+#[cfg(feature = "std")]
+fn weighted_one_hot<
+    T1: num_traits::PrimInt + num_traits::Unsigned + num_traits::FromPrimitive + core::hash::Hash,
+>(
+    categories: &[T1],
+    num_classes: T1,
+    weights: &std::collections::HashMap<T1, f64>,
+) -> Result<Vec<f64>, Error> {
+    let mut one_hot = vec![0f64; num_classes.to_usize().unwrap()];
+    for category in categories {
+        if *category >= num_classes {
+            return Err(errors::label_out_of_range(
+                category.to_usize().unwrap(),
+                num_classes.to_usize().unwrap(),
+            ));
+        }
+        one_hot[category.to_usize().unwrap()] = weights.get(category).copied().unwrap_or(1f64);
+    }
+    Ok(one_hot)
+}
+
+/// Panics with the message of [`ToOneHot::convert()`] if `category` is not less than
+/// `num_classes`.
+fn check_category<T1: num_traits::PrimInt + num_traits::Unsigned + num_traits::FromPrimitive>(
+    category: T1,
+    num_classes: T1,
+) {
+    if category >= num_classes {
+        panic!(
+            "Tried to convert {} to one-hot vector for {} classes.",
+            category.to_usize().unwrap(),
+            num_classes.to_usize().unwrap()
+        );
+    }
 }
 
 impl<T1: num_traits::PrimInt + num_traits::Unsigned + num_traits::FromPrimitive> ToOneHot<T1>
     for T1
 {
     fn convert(&self, num_classes: T1) -> Vec<bool> {
-        if *self >= num_classes {
-            panic!(
-                "Tried to convert {} to one-hot vector for {} classes.",
-                self.to_usize().unwrap(),
-                num_classes.to_usize().unwrap()
-            );
-        }
+        check_category(*self, num_classes);
         let mut one_hot = vec![false; num_classes.to_usize().unwrap()];
         one_hot[self.to_usize().unwrap()] = true;
         one_hot
     }
+
+    fn to_sparse_one_hot(&self, num_classes: T1) -> Vec<usize> {
+        check_category(*self, num_classes);
+        vec![self.to_usize().unwrap()]
+    }
+
+    #[cfg(feature = "std")]
+    fn to_weighted_one_hot(
+        &self,
+        num_classes: T1,
+        weights: &std::collections::HashMap<T1, f64>,
+    ) -> Result<Vec<f64>, Error>
+    where
+        T1: core::hash::Hash,
+    {
+        weighted_one_hot(core::slice::from_ref(self), num_classes, weights)
+    }
 }
 
 impl<T1: num_traits::PrimInt + num_traits::Unsigned + num_traits::FromPrimitive> ToOneHot<T1>
@@ -239,15 +914,118 @@ impl<T1: num_traits::PrimInt + num_traits::Unsigned + num_traits::FromPrimitive>
     fn convert(&self, num_classes: T1) -> Vec<bool> {
         let mut one_hot = vec![false; num_classes.to_usize().unwrap()];
         for category in self {
-            if *category >= num_classes {
-                panic!(
-                    "Tried to convert {} to one-hot vector for {} classes.",
-                    category.to_usize().unwrap(),
-                    num_classes.to_usize().unwrap()
-                );
-            }
+            check_category(*category, num_classes);
             one_hot[category.to_usize().unwrap()] = true;
         }
         one_hot
     }
+
+    fn to_sparse_one_hot(&self, num_classes: T1) -> Vec<usize> {
+        let mut indices = Vec::<usize>::with_capacity(self.len());
+        for category in self {
+            check_category(*category, num_classes);
+            indices.push(category.to_usize().unwrap());
+        }
+        indices.sort_unstable();
+        indices.dedup();
+        indices
+    }
+
+    #[cfg(feature = "std")]
+    fn to_weighted_one_hot(
+        &self,
+        num_classes: T1,
+        weights: &std::collections::HashMap<T1, f64>,
+    ) -> Result<Vec<f64>, Error>
+    where
+        T1: core::hash::Hash,
+    {
+        weighted_one_hot(self, num_classes, weights)
+    }
+}
+
+/// Returns the dense one-hot vector for `num_classes` classes in which the `indices` are set.
+///
+/// This decodes the output of [`ToOneHot::to_sparse_one_hot()`], giving the same vector as
+/// [`ToOneHot::convert()`]. Panics if an index is more than or equal to `num_classes`.
+///
+/// # Examples
+///
+/// ```rust
+/// use bagheera::utils::{from_sparse_one_hot, ToOneHot};
+/// let labels = vec![3u8, 0u8];
+/// let sparse = labels.to_sparse_one_hot(5u8);
+/// assert_eq!(from_sparse_one_hot(&sparse, 5usize), labels.convert(5u8));
+/// ```
+pub fn from_sparse_one_hot(indices: &[usize], num_classes: usize) -> Vec<bool> {
+    let mut one_hot = vec![false; num_classes];
+    for index in indices {
+        check_category(*index, num_classes);
+        one_hot[*index] = true;
+    }
+    one_hot
+}
+
+/// Generic trait representing the decoding of a one-hot vector into integer class IDs.
+pub trait FromOneHot {
+    /// Returns the class IDs set in a one-hot vector, in increasing order.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use bagheera::utils::FromOneHot;
+    /// let labels: Vec<u8> = vec![true, false, false, true, false].from_one_hot();
+    /// assert_eq!(labels, vec![0u8, 3u8]);
+    /// ```
+    #[allow(clippy::wrong_self_convention)]
+    fn from_one_hot<T1: num_traits::PrimInt + num_traits::Unsigned + num_traits::FromPrimitive>(
+        &self,
+    ) -> Vec<T1>;
+
+    /// Returns the class IDs set in a one-hot vector, in increasing order, checking that the
+    /// vector has `num_classes` entries.
+    ///
+    /// Unlike [`Self::from_one_hot()`], a vector that is too short or too long is reported
+    /// as an [`Error`] instance instead of being decoded as is.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use bagheera::utils::FromOneHot;
+    /// let one_hot = vec![false, true, true];
+    /// assert_eq!(one_hot.from_one_hot_checked(3u8).unwrap(), vec![1u8, 2u8]);
+    /// assert!(one_hot.from_one_hot_checked(4u8).is_err());
+    /// ```
+    #[allow(clippy::wrong_self_convention)]
+    fn from_one_hot_checked<
+        T1: num_traits::PrimInt + num_traits::Unsigned + num_traits::FromPrimitive,
+    >(
+        &self,
+        num_classes: T1,
+    ) -> Result<Vec<T1>, Error>;
+}
+
+impl FromOneHot for [bool] {
+    fn from_one_hot<T1: num_traits::PrimInt + num_traits::Unsigned + num_traits::FromPrimitive>(
+        &self,
+    ) -> Vec<T1> {
+        self.iter()
+            .enumerate()
+            .filter(|(_, x)| **x)
+            .map(|(class, _)| T1::from_usize(class).unwrap())
+            .collect()
+    }
+
+    fn from_one_hot_checked<
+        T1: num_traits::PrimInt + num_traits::Unsigned + num_traits::FromPrimitive,
+    >(
+        &self,
+        num_classes: T1,
+    ) -> Result<Vec<T1>, Error> {
+        let num_classes = num_classes.to_usize().unwrap();
+        if self.len() != num_classes {
+            return Err(one_hot_length_mismatch(self.len(), num_classes));
+        }
+        Ok(self.from_one_hot())
+    }
 }